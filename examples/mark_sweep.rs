@@ -0,0 +1,320 @@
+//! A pointer family backed by a small mark and sweep heap.
+//!
+//! Pointers into the heap are plain copies of an address. Nothing is counted, instead
+//! [`collect`] traces the lists that are still in use with a [`Tracer`], and frees every node
+//! it didn't reach. The heap only remembers whether a value was ever shared, so that lists
+//! can still fill in the nodes they just allocated.
+
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+use im_lists::list::GenericList;
+use im_lists::shared::PointerFamily;
+use im_lists::trace::{BufferId, NodeId, Tracer};
+
+thread_local! {
+    // Every value in the heap by its address
+    static HEAP: RefCell<HashMap<usize, Allocation>> = RefCell::new(HashMap::new());
+}
+
+struct Allocation {
+    free: unsafe fn(usize),
+    // Whether more than one pointer to the value was ever made
+    shared: bool,
+}
+
+/// A pointer to a value in the heap. Dropping it does nothing, the value is freed by the
+/// first [`collect`] that can't reach it.
+pub struct Gc<T> {
+    ptr: NonNull<T>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Gc<T> {
+    fn new(value: T) -> Self {
+        unsafe fn free<T>(address: usize) {
+            drop(unsafe { Box::from_raw(address as *mut T) });
+        }
+
+        let ptr = NonNull::from(Box::leak(Box::new(value)));
+        let allocation = Allocation {
+            free: free::<T>,
+            shared: false,
+        };
+        HEAP.with(|heap| heap.borrow_mut().insert(ptr.as_ptr() as usize, allocation));
+
+        Gc {
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Gc<T> {
+    // Values a sweep already freed count as shared, so that freeing the nodes before them
+    // leaves them alone
+    fn is_shared(&self) -> bool {
+        HEAP.with(|heap| {
+            heap.borrow()
+                .get(&self.address())
+                .is_none_or(|allocation| allocation.shared)
+        })
+    }
+
+    fn address(&self) -> usize {
+        self.ptr.as_ptr() as usize
+    }
+}
+
+impl<T> Clone for Gc<T> {
+    fn clone(&self) -> Self {
+        HEAP.with(|heap| {
+            if let Some(allocation) = heap.borrow_mut().get_mut(&self.address()) {
+                allocation.shared = true;
+            }
+        });
+
+        Gc {
+            ptr: self.ptr,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Deref for Gc<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+/// Lists whose nodes live in the heap.
+pub struct GcPointer;
+
+// `Gc` is covariant, and its raw pointer is the address of the value
+unsafe impl PointerFamily for GcPointer {
+    type Pointer<T> = Gc<T>;
+    type Weak<T> = Gc<T>;
+
+    fn new<T>(value: T) -> Gc<T> {
+        Gc::new(value)
+    }
+
+    // Nothing is counted, so values that were ever shared might still be
+    fn strong_count<T>(this: &Gc<T>) -> usize {
+        if this.is_shared() {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn try_unwrap<T>(this: Gc<T>) -> Option<T> {
+        if this.is_shared() {
+            return None;
+        }
+
+        HEAP.with(|heap| heap.borrow_mut().remove(&this.address()));
+        Some(*unsafe { Box::from_raw(this.ptr.as_ptr()) })
+    }
+
+    fn get_mut<T>(this: &mut Gc<T>) -> Option<&mut T> {
+        if this.is_shared() {
+            return None;
+        }
+
+        Some(unsafe { this.ptr.as_mut() })
+    }
+
+    fn ptr_eq<T>(this: &Gc<T>, other: &Gc<T>) -> bool {
+        this.ptr == other.ptr
+    }
+
+    fn make_mut<T: Clone>(ptr: &mut Gc<T>) -> &mut T {
+        if ptr.is_shared() {
+            *ptr = Gc::new(T::clone(ptr));
+        }
+
+        unsafe { ptr.ptr.as_mut() }
+    }
+
+    fn clone<T>(ptr: &Gc<T>) -> Gc<T> {
+        ptr.clone()
+    }
+
+    fn as_ptr<T>(this: &Gc<T>) -> *const T {
+        this.ptr.as_ptr()
+    }
+
+    fn into_raw<T>(this: Gc<T>) -> *const T {
+        this.ptr.as_ptr()
+    }
+
+    unsafe fn from_raw<T>(this: *const T) -> Gc<T> {
+        Gc {
+            ptr: unsafe { NonNull::new_unchecked(this as *mut T) },
+            _marker: PhantomData,
+        }
+    }
+
+    fn downgrade<T>(this: &Gc<T>) -> Gc<T> {
+        this.clone()
+    }
+
+    // A freed address can be handed out again, so this is only a best effort
+    fn upgrade<T>(weak: &Gc<T>) -> Option<Gc<T>> {
+        HEAP.with(|heap| heap.borrow().contains_key(&weak.address()))
+            .then(|| weak.clone())
+    }
+}
+
+pub type GcList<T> = GenericList<T, GcPointer>;
+
+/// Values that can hold on to lists in the heap.
+pub trait Trace {
+    fn trace(&self, marker: &mut Marker);
+}
+
+impl Trace for usize {
+    fn trace(&self, _: &mut Marker) {}
+}
+
+impl<T: Trace> Trace for GcList<T> {
+    fn trace(&self, marker: &mut Marker) {
+        GenericList::trace(self, marker)
+    }
+}
+
+/// Marks the nodes that can be reached.
+#[derive(Default)]
+pub struct Marker {
+    marked: HashSet<NodeId>,
+    // How many elements of each buffer have been traced
+    buffers: HashMap<BufferId, usize>,
+}
+
+impl<T: Trace> Tracer<T> for Marker {
+    fn visit_node(&mut self, node: NodeId) -> bool {
+        self.marked.insert(node)
+    }
+
+    fn visit_buffer(&mut self, buffer: BufferId, len: usize) -> usize {
+        let seen = self.buffers.entry(buffer).or_default();
+        std::mem::replace(seen, len.max(*seen))
+    }
+
+    fn visit_element(&mut self, element: &T) {
+        element.trace(self)
+    }
+}
+
+/// Frees every node that can't be reached from `roots`, returning how many there were. Every
+/// list that is still in use has to be reachable from a root.
+pub fn collect(roots: &[&dyn Trace]) -> usize {
+    let mut marker = Marker::default();
+    for root in roots {
+        root.trace(&mut marker);
+    }
+
+    let before = heap_len();
+    let garbage: Vec<usize> = HEAP.with(|heap| {
+        let heap = heap.borrow();
+        heap.keys()
+            .copied()
+            .filter(|address| !marker.marked.contains(&node_id(*address)))
+            .collect()
+    });
+
+    for address in garbage {
+        // Freeing a node also frees the nodes after it that were never shared
+        let allocation = HEAP.with(|heap| heap.borrow_mut().remove(&address));
+        if let Some(allocation) = allocation {
+            unsafe { (allocation.free)(address) };
+        }
+    }
+
+    before - heap_len()
+}
+
+/// Returns the number of values in the heap.
+pub fn heap_len() -> usize {
+    HEAP.with(|heap| heap.borrow().len())
+}
+
+fn node_id(address: usize) -> NodeId {
+    NodeId::from_ptr(address as *const ())
+}
+
+fn main() {
+    let tail: GcList<usize> = (0..1000).collect();
+    let list = GcList::cons(1000, tail.cdr().unwrap());
+    drop(tail);
+
+    println!("{} values in the heap", heap_len());
+    println!("{} freed", collect(&[&list]));
+    println!("{} values in the heap", heap_len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_nodes_are_freed() {
+        let list: GcList<usize> = (0..1000).collect();
+        let tail = list.cdr().unwrap();
+        let front = GcList::cons(1, tail.clone());
+
+        collect(&[&list, &tail, &front]);
+        let reachable = heap_len();
+
+        drop(list);
+        assert_eq!(collect(&[&tail, &front]), 1);
+        assert_eq!(heap_len(), reachable - 1);
+        assert!(front.iter().copied().eq([1].into_iter().chain(1..1000)));
+
+        drop(front);
+        collect(&[&tail]);
+        assert!(tail.iter().copied().eq(1..1000));
+
+        collect(&[]);
+        assert_eq!(heap_len(), 0);
+    }
+
+    #[test]
+    fn nested_lists_are_traced() {
+        let inner: GcList<usize> = (0..300).collect();
+        let outer: GcList<GcList<usize>> = (0..3).map(|_| inner.clone()).collect();
+        drop(inner);
+
+        collect(&[&outer]);
+        let reachable = heap_len();
+        assert!(outer.iter().all(|inner| inner.iter().copied().eq(0..300)));
+
+        let garbage: GcList<usize> = (0..300).collect();
+        drop(garbage);
+        assert_eq!(collect(&[&outer]), 2);
+        assert_eq!(heap_len(), reachable);
+
+        drop(outer);
+        collect(&[]);
+        assert_eq!(heap_len(), 0);
+    }
+
+    #[test]
+    fn weak_lists_upgrade_until_collected() {
+        let list: GcList<usize> = (0..10).collect();
+        let weak = list.downgrade();
+
+        collect(&[&list]);
+        assert!(weak.upgrade().unwrap().ptr_eq(&list));
+
+        drop(list);
+        collect(&[]);
+        assert!(weak.upgrade().is_none());
+    }
+}
@@ -0,0 +1,113 @@
+/* Generated from src/ffi.rs by cbindgen, do not edit. */
+
+#ifndef IM_LISTS_H
+#define IM_LISTS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A handle to an immutable list of 64 bit values. Each handle holds a reference to its list,
+// which is given back with `im_list_release`.
+typedef struct ImList ImList;
+
+// An iterator over the values of a list, which holds a reference to the list until it is
+// ended with `im_list_iter_end`.
+typedef struct ImListIter ImListIter;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Returns a handle to a new empty list.
+ImList *im_list_new(void);
+
+// Returns a handle to a new list with `value` in front of the values of `list`.
+//
+// # Safety
+//
+// `list` must be a live handle.
+ImList *im_list_cons(uint64_t value, const ImList *list);
+
+// Writes the first value of `list` to `out` and returns `true`, or returns `false` if the
+// list is empty.
+//
+// # Safety
+//
+// `list` must be a live handle, and `out` valid for writes.
+bool im_list_first(const ImList *list, uint64_t *out);
+
+// Returns a handle to the list without its first value, which is empty if `list` has one
+// value or none.
+//
+// # Safety
+//
+// `list` must be a live handle.
+ImList *im_list_rest(const ImList *list);
+
+// Returns the number of values in `list`.
+//
+// # Safety
+//
+// `list` must be a live handle.
+size_t im_list_len(const ImList *list);
+
+// Writes the value at `index` in `list` to `out` and returns `true`, or returns `false` if
+// the index is out of bounds.
+//
+// # Safety
+//
+// `list` must be a live handle, and `out` valid for writes.
+bool im_list_get(const ImList *list, size_t index, uint64_t *out);
+
+// Returns a handle to a new list with the values of `left` followed by those of `right`.
+//
+// # Safety
+//
+// `left` and `right` must be live handles.
+ImList *im_list_append(const ImList *left, const ImList *right);
+
+// Returns an iterator over the values of `list`, front to back.
+//
+// # Safety
+//
+// `list` must be a live handle.
+ImListIter *im_list_iter_begin(const ImList *list);
+
+// Writes the next value of `iter` to `out` and returns `true`, or returns `false` once every
+// value has been visited.
+//
+// # Safety
+//
+// `iter` must be an iterator that hasn't been ended, and `out` valid for writes.
+bool im_list_iter_next(ImListIter *iter, uint64_t *out);
+
+// Frees `iter`, along with its reference to the list. Passing null does nothing.
+//
+// # Safety
+//
+// `iter` must be null, or an iterator that hasn't been ended.
+void im_list_iter_end(ImListIter *iter);
+
+// Returns another handle to `list`, which holds a reference of its own.
+//
+// # Safety
+//
+// `list` must be a live handle.
+ImList *im_list_clone(const ImList *list);
+
+// Releases the reference held by `list`, dropping the list if it was the last one. Passing
+// null does nothing.
+//
+// # Safety
+//
+// `list` must be null, or a live handle, which can't be used afterwards.
+void im_list_release(const ImList *list);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* IM_LISTS_H */
@@ -0,0 +1,571 @@
+//! Lists whose nodes are allocated from a region.
+//!
+//! [`ArenaPointer`] is a [`PointerFamily`] for lists that are built in bulk and thrown away
+//! all at once. Its nodes are bump allocated from the [`Arena`] entered on the current thread,
+//! cloning a pointer to a node is a plain copy, and nothing is counted or freed node by node.
+//! Dropping the arena drops every node allocated from it and releases its memory in one go.
+//!
+//! [`ArenaList`] also allocates its element buffers from the arena, with [`ArenaAllocator`].
+//! The nodes of such a list whose elements don't need to be dropped are then never dropped
+//! one by one either, so dropping the arena only releases its memory.
+//!
+//! # Lifetimes
+//!
+//! Lists don't borrow the arena their nodes live in, so the rules for using them are spelled
+//! out on [`Arena::enter`], which is `unsafe` for that reason. In short, a list of
+//! `ArenaPointer` nodes must not outlive any of the arenas its nodes or element buffers came
+//! from.
+//!
+//! Nodes that are shared are never changed in place. [`make_mut`](PointerFamily::make_mut)
+//! copies them into the arena entered at the time, which is then one more arena the list
+//! depends on. Nodes that were never shared can still be filled in place, which is how lists
+//! build up their nodes.
+//!
+//! # Examples
+//!
+//! ```
+//! # #[macro_use] extern crate im_lists;
+//! # use im_lists::arena::{Arena, ArenaList};
+//! let arena = Arena::new();
+//!
+//! // Safety: the lists are dropped before the arena
+//! let sum = unsafe {
+//!     arena.enter(|| {
+//!         let list: ArenaList<usize> = (0..1000).collect();
+//!         let other = ArenaList::cons(1000, list.clone());
+//!         other.iter().sum::<usize>()
+//!     })
+//! };
+//!
+//! assert_eq!(sum, (0..=1000).sum());
+//! drop(arena);
+//! ```
+
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::cell::{Cell, RefCell};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+
+use crate::{
+    handler::DefaultDropHandler,
+    list::GenericList,
+    shared::PointerFamily,
+    shared_vector::alloc::{AllocError, Allocator},
+};
+
+/// A list whose nodes and element buffers are both allocated from an [`Arena`].
+pub type ArenaList<T, const N: u32 = 256, const G: u32 = 1> =
+    GenericList<T, ArenaPointer, N, G, DefaultDropHandler, ArenaAllocator>;
+
+// The size of the first chunk of an arena, every chunk after it is twice as large as the last
+const FIRST_CHUNK: usize = 4096;
+
+// The most a chunk grows to, larger values get a chunk of their own
+const MAX_CHUNK: usize = 1 << 20;
+
+std::thread_local! {
+    // The arena that nodes are allocated from, see `Arena::enter`
+    static CURRENT: Cell<*const Arena> = const { Cell::new(ptr::null()) };
+}
+
+// What became of the value in a slot
+const UNIQUE: u8 = 0;
+const SHARED: u8 = 1;
+// Moved out with `try_unwrap`, or dropped by the arena
+const GONE: u8 = 2;
+
+// The value comes first, so the address of a slot is the address of its value
+#[repr(C)]
+struct Slot<T> {
+    value: T,
+    state: Cell<u8>,
+    // Whether the arena drops the value, which a copy of it made by `make_mut` takes over
+    dropped: bool,
+}
+
+/// A region that the nodes of [`ArenaPointer`] lists are allocated from, see the
+/// [module documentation](self).
+///
+/// An arena can only be used from the thread that made it.
+pub struct Arena {
+    // Every chunk of memory the arena allocated
+    chunks: RefCell<Vec<(NonNull<u8>, Layout)>>,
+    // Where the free part of the last chunk starts
+    cursor: Cell<usize>,
+    // The slots that hold values with destructors, along with how to drop them
+    drops: RefCell<Vec<(NonNull<u8>, DropSlot)>>,
+}
+
+impl Arena {
+    /// Construct an empty arena. Nothing is allocated until the first node is.
+    pub fn new() -> Self {
+        Arena {
+            chunks: RefCell::new(Vec::new()),
+            cursor: Cell::new(0),
+            drops: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Runs `func` with this arena entered on the current thread, so that every
+    /// [`ArenaPointer`] node made in the meantime is allocated from it. Entering another
+    /// arena within `func` allocates from that one until it is left again.
+    ///
+    /// # Safety
+    ///
+    /// The arena frees the nodes allocated from it when it is dropped, without regard for the
+    /// lists that still point to them. So:
+    ///
+    /// - No list, or [`WeakList`](crate::list::WeakList), with nodes or element buffers from
+    ///   this arena may be used after the arena is dropped.
+    /// - The elements of those nodes have to still be valid to drop when the arena is, since
+    ///   the arena runs their destructors then. Elements that borrow data must not outlive it
+    ///   in a way their destructors could observe.
+    pub unsafe fn enter<R>(&self, func: impl FnOnce() -> R) -> R {
+        // Leaves the arena again, even if `func` panics
+        struct Leave(*const Arena);
+
+        impl Drop for Leave {
+            fn drop(&mut self) {
+                CURRENT.with(|current| current.set(self.0));
+            }
+        }
+
+        let _leave = Leave(CURRENT.with(|current| current.replace(self)));
+        func()
+    }
+
+    /// Returns the number of bytes the arena has allocated so far.
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks
+            .borrow()
+            .iter()
+            .map(|(_, layout)| layout.size())
+            .sum()
+    }
+
+    // The arena entered on this thread
+    fn current() -> &'static Arena {
+        let arena = CURRENT.with(Cell::get);
+        assert!(!arena.is_null(), "no arena entered on this thread");
+
+        // Safety: an entered arena is borrowed until it is left again, and nothing from it is
+        // used after it's dropped
+        unsafe { &*arena }
+    }
+
+    fn try_alloc<T>(&self, value: T, dropped: bool) -> Result<NonNull<Slot<T>>, AllocError> {
+        let slot = self
+            .try_alloc_layout(Layout::new::<Slot<T>>())?
+            .cast::<Slot<T>>();
+
+        let dropped = dropped && core::mem::needs_drop::<T>();
+
+        // Safety: the memory was just allocated with the layout of a slot
+        unsafe {
+            slot.as_ptr().write(Slot {
+                value,
+                state: Cell::new(UNIQUE),
+                dropped,
+            })
+        };
+
+        if dropped {
+            self.drops.borrow_mut().push((slot.cast(), drop_slot::<T>));
+        }
+
+        Ok(slot)
+    }
+
+    fn try_alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+        if let Some((chunk, chunk_layout)) = self.chunks.borrow().last() {
+            // Safety: the cursor is never past the end of the last chunk
+            let free = unsafe { chunk.as_ptr().add(self.cursor.get()) };
+            let start = self.cursor.get() + free.align_offset(layout.align());
+
+            if start + layout.size() <= chunk_layout.size() {
+                self.cursor.set(start + layout.size());
+                // Safety: the range is inside of the chunk
+                return Ok(unsafe { NonNull::new_unchecked(chunk.as_ptr().add(start)) });
+            }
+        }
+
+        self.try_grow(layout)?;
+        self.try_alloc_layout(layout)
+    }
+
+    fn try_grow(&self, layout: Layout) -> Result<(), AllocError> {
+        let mut chunks = self.chunks.borrow_mut();
+
+        let size = chunks
+            .last()
+            .map_or(FIRST_CHUNK, |(_, last)| (last.size() * 2).min(MAX_CHUNK))
+            .max(layout.size());
+
+        let chunk_layout =
+            Layout::from_size_align(size, layout.align().max(16)).map_err(|_| AllocError)?;
+
+        // Safety: the chunk size is never zero
+        let chunk = NonNull::new(unsafe { alloc(chunk_layout) }).ok_or(AllocError)?;
+
+        chunks.push((chunk, chunk_layout));
+        self.cursor.set(0);
+        Ok(())
+    }
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        // Dropping a node can move the nodes after it out of their slots, which then skips them.
+        // Element buffers from the arena are given back by doing nothing, so it doesn't matter
+        // that their memory goes away with the chunks below.
+        for (slot, drop) in core::mem::take(self.drops.get_mut()) {
+            unsafe { drop(slot) };
+        }
+
+        for (chunk, layout) in self.chunks.get_mut().drain(..) {
+            unsafe { dealloc(chunk.as_ptr(), layout) };
+        }
+    }
+}
+
+type DropSlot = unsafe fn(NonNull<u8>);
+
+unsafe fn drop_slot<T>(slot: NonNull<u8>) {
+    let slot = slot.cast::<Slot<T>>().as_ptr();
+
+    if (*slot).state.replace(GONE) != GONE {
+        ptr::drop_in_place(ptr::addr_of_mut!((*slot).value));
+    }
+}
+
+/// A pointer to a value in an [`Arena`]. Cloning it is a copy, and dropping it does nothing.
+pub struct ArenaRef<T> {
+    slot: NonNull<Slot<T>>,
+    _marker: PhantomData<Slot<T>>,
+}
+
+impl<T> ArenaRef<T> {
+    fn state(&self) -> &Cell<u8> {
+        // Safety: slots live as long as their arena, which outlives every pointer into it
+        unsafe { &(*self.slot.as_ptr()).state }
+    }
+}
+
+impl<T> Clone for ArenaRef<T> {
+    fn clone(&self) -> Self {
+        if self.state().get() == UNIQUE {
+            self.state().set(SHARED);
+        }
+
+        ArenaRef {
+            slot: self.slot,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Deref for ArenaRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Safety: slots live as long as their arena, which outlives every pointer into it
+        unsafe { &(*self.slot.as_ptr()).value }
+    }
+}
+
+/// An [`Allocator`] for the element buffers of [`ArenaList`], which bump allocates from the
+/// [`Arena`] entered on the current thread. Memory is only given back when the arena is
+/// dropped.
+///
+/// # Panics
+///
+/// Allocating panics if no arena is entered on the current thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ArenaAllocator;
+
+unsafe impl Allocator for ArenaAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let start = Arena::current().try_alloc_layout(layout)?;
+        Ok(NonNull::slice_from_raw_parts(start, layout.size()))
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
+}
+
+/// Lists whose nodes are allocated from an [`Arena`], see the [module documentation](self).
+///
+/// # Panics
+///
+/// Making a node, or copying a shared one to change it, panics if no arena is entered on the
+/// current thread.
+pub struct ArenaPointer;
+
+impl ArenaPointer {
+    fn try_new_dropped<T>(value: T, dropped: bool) -> Result<ArenaRef<T>, AllocError> {
+        Ok(ArenaRef {
+            slot: Arena::current().try_alloc(value, dropped)?,
+            _marker: PhantomData,
+        })
+    }
+}
+
+// `ArenaRef` is covariant, and the address of a slot is the address of its value
+unsafe impl PointerFamily for ArenaPointer {
+    type Pointer<T> = ArenaRef<T>;
+    type Weak<T> = ArenaRef<T>;
+
+    fn new<T>(value: T) -> ArenaRef<T> {
+        Self::try_new_dropped(value, true)
+            .unwrap_or_else(|_| handle_alloc_error(Layout::new::<Slot<T>>()))
+    }
+
+    unsafe fn try_new_in<T, A: Allocator + Clone + 'static>(
+        value: T,
+        _allocator: &A,
+    ) -> Result<ArenaRef<T>, AllocError> {
+        Self::try_new_dropped(value, true)
+    }
+
+    // The value's memory all comes from arenas, which free it in one go anyway
+    unsafe fn try_new_forgettable_in<T, A: Allocator + Clone + 'static>(
+        value: T,
+        _allocator: &A,
+    ) -> Result<ArenaRef<T>, AllocError> {
+        Self::try_new_dropped(value, TypeId::of::<A>() != TypeId::of::<ArenaAllocator>())
+    }
+
+    // Nothing is counted, so a value that was ever shared might still be
+    fn strong_count<T>(this: &ArenaRef<T>) -> usize {
+        if this.state().get() == UNIQUE {
+            1
+        } else {
+            2
+        }
+    }
+
+    fn try_unwrap<T>(this: ArenaRef<T>) -> Option<T> {
+        if this.state().get() != UNIQUE {
+            return None;
+        }
+        this.state().set(GONE);
+
+        // Safety: the value was never shared, and the arena won't drop it now that it's gone
+        Some(unsafe { ptr::read(&(*this.slot.as_ptr()).value) })
+    }
+
+    fn get_mut<T>(this: &mut ArenaRef<T>) -> Option<&mut T> {
+        if this.state().get() != UNIQUE {
+            return None;
+        }
+
+        // Safety: the value was never shared, and `this` is borrowed mutably
+        Some(unsafe { &mut (*this.slot.as_ptr()).value })
+    }
+
+    fn ptr_eq<T>(this: &ArenaRef<T>, other: &ArenaRef<T>) -> bool {
+        this.slot == other.slot
+    }
+
+    fn make_mut<T: Clone>(ptr: &mut ArenaRef<T>) -> &mut T {
+        Self::try_make_mut(ptr).unwrap_or_else(|_| handle_alloc_error(Layout::new::<Slot<T>>()))
+    }
+
+    fn try_make_mut<T: Clone>(ptr: &mut ArenaRef<T>) -> Result<&mut T, AllocError> {
+        if ptr.state().get() != UNIQUE {
+            // Safety: slots live as long as their arena, which outlives every pointer into it
+            let dropped = unsafe { (*ptr.slot.as_ptr()).dropped };
+            *ptr = Self::try_new_dropped(T::clone(ptr), dropped)?;
+        }
+
+        Ok(Self::get_mut(ptr).unwrap())
+    }
+
+    fn clone<T>(ptr: &ArenaRef<T>) -> ArenaRef<T> {
+        ptr.clone()
+    }
+
+    fn as_ptr<T>(this: &ArenaRef<T>) -> *const T {
+        this.slot.as_ptr().cast()
+    }
+
+    fn into_raw<T>(this: ArenaRef<T>) -> *const T {
+        ManuallyDrop::new(this).slot.as_ptr().cast()
+    }
+
+    unsafe fn from_raw<T>(this: *const T) -> ArenaRef<T> {
+        ArenaRef {
+            slot: NonNull::new_unchecked(this as *mut Slot<T>),
+            _marker: PhantomData,
+        }
+    }
+
+    fn downgrade<T>(this: &ArenaRef<T>) -> ArenaRef<T> {
+        this.clone()
+    }
+
+    fn upgrade<T>(weak: &ArenaRef<T>) -> Option<ArenaRef<T>> {
+        (weak.state().get() != GONE).then(|| weak.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::rc::Rc;
+
+    // Counts how many of these are alive
+    struct Counted(Rc<Cell<isize>>, usize);
+
+    impl Counted {
+        fn new(live: &Rc<Cell<isize>>, value: usize) -> Self {
+            live.set(live.get() + 1);
+            Counted(live.clone(), value)
+        }
+    }
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            Counted::new(&self.0, self.1)
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() - 1);
+        }
+    }
+
+    #[test]
+    fn clones_share_nodes() {
+        let arena = Arena::new();
+
+        unsafe {
+            arena.enter(|| {
+                let list: ArenaList<usize> = (0..1000).collect();
+                let copy = list.clone();
+                assert!(copy.ptr_eq(&list));
+                assert_eq!(list.strong_count(), 2);
+
+                let bytes = arena.allocated_bytes();
+                let copies: Vec<_> = (0..100).map(|_| list.clone()).collect();
+                assert_eq!(arena.allocated_bytes(), bytes);
+                assert!(copies.iter().all(|copy| copy.iter().copied().eq(0..1000)));
+            })
+        };
+    }
+
+    #[test]
+    fn make_mut_copies_shared_nodes() {
+        let arena = Arena::new();
+
+        unsafe {
+            arena.enter(|| {
+                let list: ArenaList<usize> = (0..10).collect();
+                let mut other = list.clone();
+                other.cons_mut(100);
+                other.push_back(200);
+
+                assert!(list.iter().copied().eq(0..10));
+                assert!(other
+                    .iter()
+                    .copied()
+                    .eq([100].into_iter().chain(0..10).chain([200])));
+
+                // A node that was never shared is changed in place
+                let mut fresh: ArenaList<usize> = ArenaList::new();
+                fresh.cons_mut(1);
+                let bytes = arena.allocated_bytes();
+                let node = fresh.as_ptr_usize();
+                fresh.cons_mut(2);
+                assert_eq!(fresh.as_ptr_usize(), node);
+                assert_eq!(arena.allocated_bytes(), bytes);
+            })
+        };
+    }
+
+    #[test]
+    fn dropping_the_arena_drops_every_element_once() {
+        let live = Rc::new(Cell::new(0));
+        let arena = Arena::new();
+
+        unsafe {
+            arena.enter(|| {
+                let list: ArenaList<Counted> = (0..1000).map(|i| Counted::new(&live, i)).collect();
+                let mut other = list.clone();
+                other.cons_mut(Counted::new(&live, 1000));
+                let tail = list.cdr().unwrap();
+                drop(list);
+
+                // Moving elements out of nodes that aren't shared leaves the arena with less
+                let fresh: ArenaList<Counted> = (0..10).map(|i| Counted::new(&live, i)).collect();
+                assert_eq!(fresh.into_iter().map(|value| value.1).sum::<usize>(), 45);
+
+                drop((other, tail));
+            })
+        };
+
+        assert!(live.get() > 0);
+        drop(arena);
+        assert_eq!(live.get(), 0);
+    }
+
+    #[test]
+    fn elements_come_from_the_arena() {
+        let arena = Arena::new();
+
+        unsafe {
+            arena.enter(|| {
+                let list: ArenaList<usize> = (0..1000).collect();
+                let mut other = list.clone();
+                other.push_back(1000);
+                assert!(other.iter().copied().eq(0..=1000));
+
+                // Neither the nodes nor their buffers are left for the arena to drop
+                assert!(arena.allocated_bytes() >= 1000 * core::mem::size_of::<usize>());
+                assert!(arena.drops.borrow().is_empty());
+
+                // Buffers from another allocator still have to be given back
+                let global: GenericList<usize, ArenaPointer> = (0..10).collect();
+                assert_eq!(arena.drops.borrow().len(), 1);
+                drop(global);
+            })
+        };
+    }
+
+    #[test]
+    fn arenas_nest() {
+        let outer = Arena::new();
+        let inner = Arena::new();
+
+        unsafe {
+            outer.enter(|| {
+                let list: ArenaList<usize> = (0..10).collect();
+                let bytes = outer.allocated_bytes();
+
+                inner.enter(|| {
+                    let other = ArenaList::cons(10, list.clone());
+                    assert!(other.iter().copied().eq([10].into_iter().chain(0..10)));
+                });
+
+                assert_eq!(outer.allocated_bytes(), bytes);
+                assert!(inner.allocated_bytes() > 0);
+            })
+        };
+    }
+
+    #[test]
+    #[should_panic(expected = "no arena entered")]
+    fn allocating_without_an_arena() {
+        let _list: ArenaList<usize> = ArenaList::new();
+    }
+}
@@ -0,0 +1,26 @@
+//! Errors returned by the fallible list operations.
+
+use core::fmt;
+
+use crate::shared_vector::alloc::AllocError;
+
+/// The error returned by the `try_` operations on a [`GenericList`](crate::list::GenericList)
+/// when memory for the elements could not be allocated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TryReserveError {
+    _private: (),
+}
+
+impl From<AllocError> for TryReserveError {
+    fn from(_: AllocError) -> Self {
+        TryReserveError { _private: () }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")
+    }
+}
+
+impl core::error::Error for TryReserveError {}
@@ -0,0 +1,341 @@
+//! A C ABI for [`SharedList`]s of opaque 64 bit values, so that parts of a runtime written in
+//! other languages can build and read lists. Needs the `ffi` feature. The declarations are in
+//! `include/im_lists.h`, which is generated from this file with `cbindgen`, see
+//! `cbindgen.toml`. The tests check that it is up to date.
+//!
+//! The doc comments of the exported items are copied into the header, so they are written as
+//! plain prose, without rustdoc links.
+//!
+//! Lists are passed around as [`ImList`] handles, made with [`GenericList::into_raw`]. Every
+//! handle a function returns holds a reference to its list, which has to be given back with
+//! [`im_list_release`] exactly once. Functions that take a handle only borrow it, and never
+//! release it. [`im_list_clone`] makes another handle to the same list, and since lists are
+//! immutable, handles can be used from any number of threads at once.
+//!
+//! [`GenericList::into_raw`]: crate::list::GenericList::into_raw
+
+use alloc::boxed::Box;
+use core::mem::ManuallyDrop;
+
+use crate::list::SharedList;
+
+type Values = SharedList<u64>;
+
+/// A handle to an immutable list of 64 bit values. Each handle holds a reference to its list,
+/// which is given back with `im_list_release`.
+pub struct ImList {
+    _private: [u8; 0],
+}
+
+/// An iterator over the values of a list, which holds a reference to the list until it is
+/// ended with `im_list_iter_end`.
+pub struct ImListIter(<Values as IntoIterator>::IntoIter);
+
+fn into_handle(list: Values) -> *mut ImList {
+    Values::into_raw(list) as *mut ImList
+}
+
+// Lends out the list behind a handle, without releasing the handle's reference
+unsafe fn borrow(list: *const ImList) -> ManuallyDrop<Values> {
+    ManuallyDrop::new(Values::from_raw(list.cast()))
+}
+
+/// Returns a handle to a new empty list.
+#[no_mangle]
+pub extern "C" fn im_list_new() -> *mut ImList {
+    into_handle(Values::new())
+}
+
+/// Returns a handle to a new list with `value` in front of the values of `list`.
+///
+/// # Safety
+///
+/// `list` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_cons(value: u64, list: *const ImList) -> *mut ImList {
+    into_handle(Values::cons(value, Values::clone(&borrow(list))))
+}
+
+/// Writes the first value of `list` to `out` and returns `true`, or returns `false` if the
+/// list is empty.
+///
+/// # Safety
+///
+/// `list` must be a live handle, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_first(list: *const ImList, out: *mut u64) -> bool {
+    match borrow(list).first() {
+        Some(value) => {
+            out.write(*value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns a handle to the list without its first value, which is empty if `list` has one
+/// value or none.
+///
+/// # Safety
+///
+/// `list` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_rest(list: *const ImList) -> *mut ImList {
+    into_handle(borrow(list).cdr().unwrap_or_default())
+}
+
+/// Returns the number of values in `list`.
+///
+/// # Safety
+///
+/// `list` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_len(list: *const ImList) -> usize {
+    borrow(list).len()
+}
+
+/// Writes the value at `index` in `list` to `out` and returns `true`, or returns `false` if
+/// the index is out of bounds.
+///
+/// # Safety
+///
+/// `list` must be a live handle, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_get(list: *const ImList, index: usize, out: *mut u64) -> bool {
+    match borrow(list).get(index) {
+        Some(value) => {
+            out.write(*value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Returns a handle to a new list with the values of `left` followed by those of `right`.
+///
+/// # Safety
+///
+/// `left` and `right` must be live handles.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_append(left: *const ImList, right: *const ImList) -> *mut ImList {
+    let left = Values::clone(&borrow(left));
+    into_handle(left.append(Values::clone(&borrow(right))))
+}
+
+/// Returns an iterator over the values of `list`, front to back.
+///
+/// # Safety
+///
+/// `list` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_iter_begin(list: *const ImList) -> *mut ImListIter {
+    let iter = Values::clone(&borrow(list)).into_iter();
+    Box::into_raw(Box::new(ImListIter(iter)))
+}
+
+/// Writes the next value of `iter` to `out` and returns `true`, or returns `false` once every
+/// value has been visited.
+///
+/// # Safety
+///
+/// `iter` must be an iterator that hasn't been ended, and `out` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_iter_next(iter: *mut ImListIter, out: *mut u64) -> bool {
+    match (*iter).0.next() {
+        Some(value) => {
+            out.write(value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Frees `iter`, along with its reference to the list. Passing null does nothing.
+///
+/// # Safety
+///
+/// `iter` must be null, or an iterator that hasn't been ended.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_iter_end(iter: *mut ImListIter) {
+    if !iter.is_null() {
+        drop(Box::from_raw(iter));
+    }
+}
+
+/// Returns another handle to `list`, which holds a reference of its own.
+///
+/// # Safety
+///
+/// `list` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_clone(list: *const ImList) -> *mut ImList {
+    Values::increment_strong_count(list.cast());
+    list as *mut ImList
+}
+
+/// Releases the reference held by `list`, dropping the list if it was the last one. Passing
+/// null does nothing.
+///
+/// # Safety
+///
+/// `list` must be null, or a live handle, which can't be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn im_list_release(list: *const ImList) {
+    if !list.is_null() {
+        Values::decrement_strong_count(list.cast());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/include/im_lists.h");
+
+    // Run the tests with `IM_LISTS_BLESS=1` to update the header
+    #[test]
+    fn header_is_up_to_date() {
+        let crate_dir = env!("CARGO_MANIFEST_DIR");
+        let config = cbindgen::Config::from_file(alloc::format!("{crate_dir}/cbindgen.toml"));
+        let mut header = alloc::vec::Vec::new();
+        cbindgen::Builder::new()
+            .with_config(config.unwrap())
+            .with_src(alloc::format!("{crate_dir}/src/ffi.rs"))
+            .generate()
+            .unwrap()
+            .write(&mut header);
+
+        if std::env::var_os("IM_LISTS_BLESS").is_some() {
+            std::fs::write(HEADER_PATH, &header).unwrap();
+        }
+
+        let existing = std::fs::read(HEADER_PATH).unwrap_or_default();
+        assert!(
+            existing == header,
+            "include/im_lists.h is out of date, run the tests with IM_LISTS_BLESS=1"
+        );
+    }
+
+    // Calls go through function pointers, the way code in other languages makes them
+    #[test]
+    fn build_and_read_lists() {
+        let new: extern "C" fn() -> *mut ImList = im_list_new;
+        let cons: unsafe extern "C" fn(u64, *const ImList) -> *mut ImList = im_list_cons;
+        let first: unsafe extern "C" fn(*const ImList, *mut u64) -> bool = im_list_first;
+        let rest: unsafe extern "C" fn(*const ImList) -> *mut ImList = im_list_rest;
+        let len: unsafe extern "C" fn(*const ImList) -> usize = im_list_len;
+        let get: unsafe extern "C" fn(*const ImList, usize, *mut u64) -> bool = im_list_get;
+        let append: unsafe extern "C" fn(*const ImList, *const ImList) -> *mut ImList =
+            im_list_append;
+        let release: unsafe extern "C" fn(*const ImList) = im_list_release;
+
+        unsafe {
+            let mut list = new();
+            for value in (0..1000).rev() {
+                let next = cons(value, list);
+                release(list);
+                list = next;
+            }
+            assert_eq!(len(list), 1000);
+
+            let mut out = 0;
+            assert!(first(list, &mut out));
+            assert_eq!(out, 0);
+            assert!(get(list, 999, &mut out));
+            assert_eq!(out, 999);
+            assert!(!get(list, 1000, &mut out));
+
+            let tail = rest(list);
+            assert_eq!(len(tail), 999);
+            assert!(first(tail, &mut out));
+            assert_eq!(out, 1);
+
+            let both = append(list, tail);
+            assert_eq!(len(both), 1999);
+            assert!(get(both, 1000, &mut out));
+            assert_eq!(out, 1);
+
+            let empty = new();
+            assert!(!first(empty, &mut out));
+            let still_empty = rest(empty);
+            assert_eq!(len(still_empty), 0);
+
+            for handle in [list, tail, both, empty, still_empty] {
+                release(handle);
+            }
+            release(core::ptr::null());
+        }
+    }
+
+    #[test]
+    fn iterate_and_clone_handles() {
+        let new: extern "C" fn() -> *mut ImList = im_list_new;
+        let cons: unsafe extern "C" fn(u64, *const ImList) -> *mut ImList = im_list_cons;
+        let clone: unsafe extern "C" fn(*const ImList) -> *mut ImList = im_list_clone;
+        let release: unsafe extern "C" fn(*const ImList) = im_list_release;
+        let begin: unsafe extern "C" fn(*const ImList) -> *mut ImListIter = im_list_iter_begin;
+        let next: unsafe extern "C" fn(*mut ImListIter, *mut u64) -> bool = im_list_iter_next;
+        let end: unsafe extern "C" fn(*mut ImListIter) = im_list_iter_end;
+
+        unsafe {
+            let empty = new();
+            let list = cons(1, empty);
+            release(empty);
+
+            let other = clone(list);
+            assert_eq!(other, list);
+
+            // The iterator keeps the list alive on its own
+            let iter = begin(list);
+            release(list);
+            release(other);
+
+            let mut out = 0;
+            assert!(next(iter, &mut out));
+            assert_eq!(out, 1);
+            assert!(!next(iter, &mut out));
+            end(iter);
+            end(core::ptr::null_mut());
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn share_handles_across_threads() {
+        let new: extern "C" fn() -> *mut ImList = im_list_new;
+        let cons: unsafe extern "C" fn(u64, *const ImList) -> *mut ImList = im_list_cons;
+        let clone: unsafe extern "C" fn(*const ImList) -> *mut ImList = im_list_clone;
+        let release: unsafe extern "C" fn(*const ImList) = im_list_release;
+        let begin: unsafe extern "C" fn(*const ImList) -> *mut ImListIter = im_list_iter_begin;
+        let next: unsafe extern "C" fn(*mut ImListIter, *mut u64) -> bool = im_list_iter_next;
+        let end: unsafe extern "C" fn(*mut ImListIter) = im_list_iter_end;
+
+        unsafe {
+            // Handles can be sent to other threads as plain integers
+            let mut list = new();
+            for value in 0..100 {
+                let next = cons(value, list);
+                release(list);
+                list = next;
+            }
+
+            let handle = clone(list) as usize;
+            let sum = std::thread::spawn(move || {
+                let iter = begin(handle as *const ImList);
+                let mut sum = 0;
+                let mut out = 0;
+                while next(iter, &mut out) {
+                    sum += out;
+                }
+                end(iter);
+                release(handle as *const ImList);
+                sum
+            })
+            .join()
+            .unwrap();
+
+            assert_eq!(sum, (0..100).sum::<u64>());
+            release(list);
+        }
+    }
+}
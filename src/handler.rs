@@ -1,5 +1,34 @@
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use alloc::{boxed::Box, collections::VecDeque};
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "std")]
+use crate::shared::ArcPointer;
+use crate::{
+    list::{DroppedList, GenericList},
+    shared::PointerFamily,
+    shared_vector::alloc::Allocator,
+};
+
 pub trait DropHandler<T> {
     fn drop_handler(obj: &mut T);
+
+    /// Takes ownership of the nodes of `obj` after [`drop_handler`](Self::drop_handler) ran on
+    /// it. The default drops them right away.
+    fn drop_nodes(nodes: DroppedList<T>)
+    where
+        T: DropNodes,
+    {
+        drop(nodes)
+    }
+}
+
+/// Types that hand the nodes they own over to their [`DropHandler`] as a [`DroppedList`]
+/// when they're dropped, which lists do.
+pub trait DropNodes {
+    type Nodes;
 }
 
 pub struct DefaultDropHandler;
@@ -7,3 +36,364 @@ pub struct DefaultDropHandler;
 impl<T> DropHandler<T> for DefaultDropHandler {
     fn drop_handler(_obj: &mut T) {}
 }
+
+/// Elements that own lists of type `L`, which [`NestedDropHandler`] takes out of them to drop
+/// them without recursing.
+pub trait ChildLists<L> {
+    /// Moves every list this value owns into `children`. The value should be left owning no
+    /// lists at all, for instance by replacing it with a variant that holds none.
+    fn take_children(&mut self, children: &mut Vec<L>);
+}
+
+/// A drop handler for lists whose elements own lists of the same type, such as the values of
+/// an interpreter that can be lists themselves.
+///
+/// Dropping a list that holds lists that hold lists, and so on, would otherwise recurse once
+/// per level of nesting. This handler instead takes the lists out of each element the dropped
+/// list owns, see [`ChildLists`], and drops them one at a time from a work queue, taking their
+/// elements apart the same way. Elements in nodes or buffers that are still shared aren't
+/// dropped, so they're left alone.
+///
+/// # Examples
+///
+/// ```
+/// # use im_lists::handler::{ChildLists, NestedDropHandler};
+/// # use im_lists::list::GenericList;
+/// # use im_lists::shared::RcPointer;
+/// type Nested<T> = GenericList<T, RcPointer, 256, 1, NestedDropHandler>;
+///
+/// enum Value {
+///     Int(usize),
+///     List(Nested<Value>),
+/// }
+///
+/// impl ChildLists<Nested<Value>> for Value {
+///     fn take_children(&mut self, children: &mut Vec<Nested<Value>>) {
+///         if let Value::List(list) = std::mem::replace(self, Value::Int(0)) {
+///             children.push(list);
+///         }
+///     }
+/// }
+///
+/// let mut value = Value::Int(0);
+/// for _ in 0..100_000 {
+///     value = Value::List(std::iter::once(value).collect());
+/// }
+/// drop(value);
+/// ```
+pub struct NestedDropHandler;
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    DropHandler<GenericList<T, P, N, G, NestedDropHandler, A>> for NestedDropHandler
+where
+    T: ChildLists<GenericList<T, P, N, G, NestedDropHandler, A>>,
+{
+    fn drop_handler(list: &mut GenericList<T, P, N, G, NestedDropHandler, A>) {
+        let mut queue = Vec::new();
+        list.for_each_owned_mut(|element| element.take_children(&mut queue));
+
+        while let Some(mut child) = queue.pop() {
+            child.for_each_owned_mut(|element| element.take_children(&mut queue));
+            // The elements of `child` own no lists anymore, so dropping it doesn't recurse
+        }
+    }
+}
+
+// How many lists can wait to be freed by default
+#[cfg(feature = "std")]
+const DEFAULT_LIMIT: usize = 1024;
+
+#[cfg(feature = "std")]
+static DEFERRED: std::sync::Mutex<VecDeque<Box<dyn Send>>> = std::sync::Mutex::new(VecDeque::new());
+
+#[cfg(feature = "std")]
+static QUEUED: std::sync::Condvar = std::sync::Condvar::new();
+
+#[cfg(feature = "std")]
+static LIMIT: AtomicUsize = AtomicUsize::new(DEFAULT_LIMIT);
+
+/// A drop handler that frees [`SharedList`](crate::list::SharedList)s, and other lists of
+/// [`ArcPointer`] nodes, away from the thread that drops them.
+///
+/// Dropping the last reference to a list moves its nodes into a queue, instead of freeing
+/// every node and element on the spot. The queue is shared by every list with this handler,
+/// and is emptied by [`reclaim`](Self::reclaim), or as lists arrive by the thread started
+/// with [`spawn_reclaimer`](Self::spawn_reclaimer). Dropping a list that is still shared only
+/// releases its reference, so it happens right away.
+///
+/// The queue holds a limited number of lists, see [`set_limit`](Self::set_limit), after which
+/// threads that drop lists free them themselves.
+///
+/// # Examples
+///
+/// ```
+/// # use im_lists::handler::DeferredDropHandler;
+/// # use im_lists::list::GenericList;
+/// # use im_lists::shared::ArcPointer;
+/// type DeferredList<T> = GenericList<T, ArcPointer, 256, 1, DeferredDropHandler>;
+///
+/// let list: DeferredList<usize> = (0..100_000).collect();
+/// drop(list);
+///
+/// assert_eq!(DeferredDropHandler::reclaim(), 1);
+/// ```
+#[cfg(feature = "std")]
+pub struct DeferredDropHandler;
+
+#[cfg(feature = "std")]
+impl DeferredDropHandler {
+    // A panic while the queue is locked can't leave it in a broken state
+    fn queue() -> std::sync::MutexGuard<'static, VecDeque<Box<dyn Send>>> {
+        DEFERRED
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Frees every list waiting in the queue on the calling thread, returning how many there
+    /// were.
+    pub fn reclaim() -> usize {
+        let lists = core::mem::take(&mut *Self::queue());
+        lists.len()
+    }
+
+    /// Returns the number of lists waiting to be freed.
+    pub fn pending() -> usize {
+        Self::queue().len()
+    }
+
+    /// Sets how many lists can wait to be freed, which is 1024 to begin with. Once that many
+    /// are waiting, threads that drop lists free them on the spot, so that lists can't pile up
+    /// faster than they are freed.
+    pub fn set_limit(limit: usize) {
+        LIMIT.store(limit, Ordering::Relaxed);
+    }
+
+    /// Starts a thread that frees lists as soon as they are queued, unless one is running
+    /// already. The thread runs until the process exits.
+    pub fn spawn_reclaimer() {
+        static SPAWNED: std::sync::Once = std::sync::Once::new();
+
+        SPAWNED.call_once(|| {
+            std::thread::Builder::new()
+                .name("im-lists-reclaimer".into())
+                .spawn(|| loop {
+                    let mut queue = Self::queue();
+                    while queue.is_empty() {
+                        queue = QUEUED
+                            .wait(queue)
+                            .unwrap_or_else(std::sync::PoisonError::into_inner);
+                    }
+
+                    let lists = core::mem::take(&mut *queue);
+                    drop(queue);
+                    drop(lists);
+                })
+                .expect("failed to spawn the reclaimer thread");
+        });
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T, const N: u32, const G: u32, A>
+    DropHandler<GenericList<T, ArcPointer, N, G, DeferredDropHandler, A>> for DeferredDropHandler
+where
+    T: Send + Sync + 'static,
+    A: Allocator + Clone + Send + Sync + 'static,
+{
+    fn drop_handler(_list: &mut GenericList<T, ArcPointer, N, G, DeferredDropHandler, A>) {}
+
+    fn drop_nodes(nodes: DroppedList<GenericList<T, ArcPointer, N, G, DeferredDropHandler, A>>) {
+        if !nodes.is_unique() {
+            return;
+        }
+
+        let mut queue = DeferredDropHandler::queue();
+        if queue.len() >= LIMIT.load(Ordering::Relaxed) {
+            drop(queue);
+            drop(nodes);
+            return;
+        }
+
+        queue.push_back(Box::new(nodes));
+        QUEUED.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::{ArcPointer, RcPointer};
+    use crate::shared_vector::alloc::Global;
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    type Nested<T> = GenericList<T, RcPointer, 4, 1, NestedDropHandler, Global>;
+    type SharedNested<T> = GenericList<T, ArcPointer, 4, 1, NestedDropHandler, Global>;
+
+    // Counts how many leaves were dropped
+    struct Leaf(Rc<Cell<usize>>);
+
+    impl Drop for Leaf {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    enum Value {
+        Leaf(#[allow(dead_code)] Option<Leaf>),
+        List(Nested<Value>),
+    }
+
+    impl ChildLists<Nested<Value>> for Value {
+        fn take_children(&mut self, children: &mut Vec<Nested<Value>>) {
+            if let Value::List(list) = core::mem::replace(self, Value::Leaf(None)) {
+                children.push(list);
+            }
+        }
+    }
+
+    fn nest(value: Value) -> Value {
+        Value::List(core::iter::once(value).collect())
+    }
+
+    #[test]
+    fn million_deep_nesting() {
+        let dropped = Rc::new(Cell::new(0));
+
+        let mut value = Value::Leaf(Some(Leaf(dropped.clone())));
+        for _ in 0..1_000_000 {
+            value = nest(value);
+        }
+
+        drop(value);
+        assert_eq!(dropped.get(), 1);
+    }
+
+    #[test]
+    fn wide_and_deep_nesting() {
+        let dropped = Rc::new(Cell::new(0));
+
+        let mut value = Value::Leaf(Some(Leaf(dropped.clone())));
+        for _ in 0..100_000 {
+            let leaf = Value::Leaf(Some(Leaf(dropped.clone())));
+            let empty = Value::List(Nested::new());
+            value = Value::List([empty, leaf, value].into_iter().collect());
+        }
+
+        drop(value);
+        assert_eq!(dropped.get(), 100_001);
+    }
+
+    #[test]
+    fn shared_children_are_kept() {
+        let dropped = Rc::new(Cell::new(0));
+
+        let mut value = Value::Leaf(Some(Leaf(dropped.clone())));
+        for _ in 0..1000 {
+            value = nest(value);
+        }
+
+        let Value::List(list) = &value else {
+            unreachable!()
+        };
+        let Some(Value::List(kept)) = list.first() else {
+            unreachable!()
+        };
+        let kept = kept.clone();
+
+        drop(value);
+        assert_eq!(dropped.get(), 0);
+
+        // The kept list still reaches the leaf
+        let mut depth = 0;
+        let mut cur = kept.clone();
+        while let Some(Value::List(next)) = cur.first() {
+            cur = next.clone();
+            depth += 1;
+        }
+        assert_eq!(depth, 998);
+
+        drop((cur, kept));
+        assert_eq!(dropped.get(), 1);
+    }
+
+    #[cfg(feature = "std")]
+    type DeferredList<T> = GenericList<T, ArcPointer, 256, 1, DeferredDropHandler, Global>;
+
+    // Counts how many of these were dropped
+    #[cfg(feature = "std")]
+    struct Tally(std::sync::Arc<AtomicUsize>);
+
+    #[cfg(feature = "std")]
+    impl Drop for Tally {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    // The queue is shared by the whole process, so this is all one test
+    #[cfg(feature = "std")]
+    #[test]
+    fn deferred_drops() {
+        let dropped = std::sync::Arc::new(AtomicUsize::new(0));
+        let tallies =
+            |count| -> DeferredList<Tally> { (0..count).map(|_| Tally(dropped.clone())).collect() };
+
+        // Shared lists are dropped right away, the last reference waits in the queue
+        let list = tallies(1000);
+        drop(list.clone());
+        assert_eq!(DeferredDropHandler::pending(), 0);
+
+        drop(list);
+        assert_eq!(DeferredDropHandler::pending(), 1);
+        assert_eq!(dropped.load(Ordering::Relaxed), 0);
+
+        assert_eq!(DeferredDropHandler::reclaim(), 1);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1000);
+
+        // A full queue makes threads free their own lists
+        DeferredDropHandler::set_limit(2);
+        let lists: Vec<_> = (0..3).map(|_| tallies(10)).collect();
+        drop(lists);
+        assert_eq!(DeferredDropHandler::pending(), 2);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1010);
+
+        assert_eq!(DeferredDropHandler::reclaim(), 2);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1030);
+        DeferredDropHandler::set_limit(DEFAULT_LIMIT);
+
+        // The reclaimer frees lists in the background
+        DeferredDropHandler::spawn_reclaimer();
+        drop(tallies(1000));
+
+        let start = std::time::Instant::now();
+        while dropped.load(Ordering::Relaxed) < 2030 {
+            assert!(start.elapsed() < std::time::Duration::from_secs(10));
+            std::thread::yield_now();
+        }
+        assert_eq!(DeferredDropHandler::pending(), 0);
+    }
+
+    enum SharedValue {
+        Leaf,
+        List(SharedNested<SharedValue>),
+    }
+
+    impl ChildLists<SharedNested<SharedValue>> for SharedValue {
+        fn take_children(&mut self, children: &mut Vec<SharedNested<SharedValue>>) {
+            if let SharedValue::List(list) = core::mem::replace(self, SharedValue::Leaf) {
+                children.push(list);
+            }
+        }
+    }
+
+    #[test]
+    fn million_deep_shared_nesting() {
+        let mut value = SharedValue::Leaf;
+        for _ in 0..1_000_000 {
+            value = SharedValue::List(core::iter::once(value).collect());
+        }
+
+        std::thread::spawn(move || drop(value)).join().unwrap();
+    }
+}
@@ -0,0 +1,457 @@
+//! A list that remembers its hash.
+//!
+//! Hashing a [`GenericList`] visits every element, every time. A [`HashedList`] keeps a hash
+//! of its elements next to the list instead, which is updated as elements are added and
+//! removed. Hashing it, or comparing two of them that aren't equal, is then O(1), which
+//! makes it a cheap key for maps and memoization.
+//!
+//! The hash is a polynomial over the hashes of the elements, so it only depends on the
+//! elements themselves and not on how they are split up into nodes. Consing, popping or
+//! pushing onto either end only has to hash the elements that were added or removed.
+
+use core::hash::{Hash, Hasher};
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+use core::ops::Deref;
+
+use crate::{
+    handler::{DefaultDropHandler, DropHandler},
+    list::GenericList,
+    shared::{PointerFamily, RcPointer},
+    shared_vector::alloc::{Allocator, Global},
+};
+
+// The base of the polynomial, along with its multiplicative inverse modulo 2^64.
+const BASE: u64 = 0x0000_0100_0000_01b3;
+const BASE_INVERSE: u64 = inverse(BASE);
+
+// Newton's method, each step doubles the number of correct low bits of an odd number's inverse
+const fn inverse(value: u64) -> u64 {
+    let mut inverse = value;
+    let mut i = 0;
+    while i < 6 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(value.wrapping_mul(inverse)));
+        i += 1;
+    }
+    inverse
+}
+
+/// The 64 bit FNV-1a hash, the default hasher for the elements of a [`HashedList`].
+///
+/// Element hashes have to be the same for every list, so the hasher can't be randomly seeded.
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher(u64);
+
+impl Default for FnvHasher {
+    fn default() -> Self {
+        FnvHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(BASE);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A [`GenericList`] along with a hash of its elements, see the [module documentation](self).
+///
+/// The list can be read through [`Deref`], but only changed through the methods here so that
+/// the hash stays up to date. Each element is hashed with a fresh `H`, which has to give the
+/// same result for equal elements no matter which list they are in.
+pub struct HashedList<
+    T,
+    P: PointerFamily = RcPointer,
+    const N: u32 = 256,
+    const G: u32 = 1,
+    D: DropHandler<GenericList<T, P, N, G, D, A>> = DefaultDropHandler,
+    A: Allocator + Clone + 'static = Global,
+    H: Hasher + Default = FnvHasher,
+> {
+    list: GenericList<T, P, N, G, D, A>,
+    // The sum of the hash of each element times `BASE` to the power of how many elements follow it
+    hash: u64,
+    // `BASE` to the power of the length of the list
+    power: u64,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+impl<
+        T: Hash,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > HashedList<T, P, N, G, D, A, H>
+{
+    fn hash_element(value: &T) -> u64 {
+        let mut hasher = H::default();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Construct an empty list.
+    pub fn new() -> Self
+    where
+        A: Default,
+    {
+        GenericList::new().into()
+    }
+
+    /// Returns the hash of the elements of the list.
+    ///
+    /// Time: O(1)
+    pub fn elements_hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// Returns a reference to the underlying list.
+    pub fn as_list(&self) -> &GenericList<T, P, N, G, D, A> {
+        &self.list
+    }
+
+    /// Returns the underlying list, forgetting the hash.
+    pub fn into_list(self) -> GenericList<T, P, N, G, D, A> {
+        self.list
+    }
+
+    /// Construct a new list by consing `value` onto the front of `other`.
+    ///
+    /// Time: O(1) on average, plus hashing `value`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use im_lists::hashed::HashedList;
+    /// let list: HashedList<usize> = (1..10).collect();
+    /// let list = HashedList::cons(0, list);
+    ///
+    /// assert_eq!(list.elements_hash(), (0..10).collect::<HashedList<usize>>().elements_hash());
+    /// ```
+    pub fn cons(value: T, other: Self) -> Self
+    where
+        T: Clone,
+    {
+        let hash = Self::hash_element(&value)
+            .wrapping_mul(other.power)
+            .wrapping_add(other.hash);
+
+        Self {
+            list: GenericList::cons(value, other.list),
+            hash,
+            power: other.power.wrapping_mul(BASE),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Mutably pushes an element onto the front of the list, in place.
+    pub fn cons_mut(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.hash = Self::hash_element(&value)
+            .wrapping_mul(self.power)
+            .wrapping_add(self.hash);
+        self.power = self.power.wrapping_mul(BASE);
+        self.list.cons_mut(value);
+    }
+
+    /// Push one value to the back of the list.
+    ///
+    /// Time: O(n), plus hashing `value`
+    pub fn push_back(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.hash = self
+            .hash
+            .wrapping_mul(BASE)
+            .wrapping_add(Self::hash_element(&value));
+        self.power = self.power.wrapping_mul(BASE);
+        self.list.push_back(value);
+    }
+
+    // Takes `value`, which was just removed from the front of the list, out of the hash
+    fn remove_front(&mut self, value: &T) {
+        self.power = self.power.wrapping_mul(BASE_INVERSE);
+        self.hash = self
+            .hash
+            .wrapping_sub(Self::hash_element(value).wrapping_mul(self.power));
+    }
+
+    /// Gets the rest of the list, which is `None` if that would be empty, like
+    /// [`GenericList::cdr`].
+    ///
+    /// Time: O(1), plus hashing the first element
+    pub fn cdr(&self) -> Option<Self> {
+        let list = self.list.cdr()?;
+        let mut rest = Self {
+            list,
+            hash: self.hash,
+            power: self.power,
+            _hasher: PhantomData,
+        };
+        rest.remove_front(self.list.first()?);
+        Some(rest)
+    }
+
+    /// Removes the first element of the list and returns it.
+    pub fn pop_front(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let value = self.list.pop_front()?;
+        self.remove_front(&value);
+        Some(value)
+    }
+
+    /// Append the list `other` to the end of this one.
+    ///
+    /// Time: the same as [`GenericList::append`], without hashing any element
+    pub fn append(self, other: Self) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            hash: self.hash.wrapping_mul(other.power).wrapping_add(other.hash),
+            power: self.power.wrapping_mul(other.power),
+            list: self.list.append(other.list),
+            _hasher: PhantomData,
+        }
+    }
+}
+
+impl<
+        T: Hash,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > From<GenericList<T, P, N, G, D, A>> for HashedList<T, P, N, G, D, A, H>
+{
+    /// Hashes every element of `list`.
+    fn from(list: GenericList<T, P, N, G, D, A>) -> Self {
+        let (hash, power) = list.iter().fold((0u64, 1u64), |(hash, power), value| {
+            (
+                hash.wrapping_mul(BASE)
+                    .wrapping_add(Self::hash_element(value)),
+                power.wrapping_mul(BASE),
+            )
+        });
+
+        Self {
+            list,
+            hash,
+            power,
+            _hasher: PhantomData,
+        }
+    }
+}
+
+impl<
+        T: Hash,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone + Default,
+        H: Hasher + Default,
+    > FromIterator<T> for HashedList<T, P, N, G, D, A, H>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        GenericList::from_iter(iter).into()
+    }
+}
+
+impl<
+        T: Hash,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone + Default,
+        H: Hasher + Default,
+    > Default for HashedList<T, P, N, G, D, A, H>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > Deref for HashedList<T, P, N, G, D, A, H>
+{
+    type Target = GenericList<T, P, N, G, D, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > Clone for HashedList<T, P, N, G, D, A, H>
+{
+    fn clone(&self) -> Self {
+        Self {
+            list: self.list.clone(),
+            hash: self.hash,
+            power: self.power,
+            _hasher: PhantomData,
+        }
+    }
+}
+
+impl<
+        T: core::fmt::Debug,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > core::fmt::Debug for HashedList<T, P, N, G, D, A, H>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.list.fmt(f)
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > Hash for HashedList<T, P, N, G, D, A, H>
+{
+    fn hash<S: Hasher>(&self, state: &mut S) {
+        state.write_u64(self.hash)
+    }
+}
+
+// Lists with different hashes can't be equal, so only lists with the same hash are compared
+impl<
+        T: PartialEq,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > PartialEq for HashedList<T, P, N, G, D, A, H>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.power == other.power && self.list == other.list
+    }
+}
+
+impl<
+        T: Eq,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+        H: Hasher + Default,
+    > Eq for HashedList<T, P, N, G, D, A, H>
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::{List, SharedVList};
+
+    use std::collections::HashMap;
+
+    type HashedVList<T> = HashedList<T, crate::shared::ArcPointer, 2, 2>;
+
+    #[test]
+    fn base_inverse() {
+        assert_eq!(BASE.wrapping_mul(BASE_INVERSE), 1);
+    }
+
+    #[test]
+    fn hash_follows_elements() {
+        let mut list: HashedList<usize> = HashedList::new();
+        for i in (0..1000).rev() {
+            list.cons_mut(i);
+        }
+
+        let expected: HashedList<usize> = (0..1000).collect();
+        assert_eq!(list.elements_hash(), expected.elements_hash());
+        assert_eq!(list, expected);
+
+        let rest = list.cdr().unwrap();
+        assert_eq!(rest, (1..1000).collect());
+        assert_eq!(HashedList::cons(0, rest), expected);
+
+        list.pop_front();
+        list.push_back(1000);
+        assert_eq!(list, (1..1001).collect());
+
+        let (left, right): (HashedList<usize>, HashedList<usize>) =
+            ((0..500).collect(), (500..1000).collect());
+        assert_eq!(left.append(right), expected);
+    }
+
+    #[test]
+    fn hash_ignores_node_layout() {
+        let list: List<usize> = (0..1000).collect();
+        let vlist: SharedVList<usize> = list.convert().rechunk();
+
+        let hashed: HashedList<usize> = list.into();
+        let hashed_vlist: HashedVList<usize> = vlist.into();
+        assert_eq!(hashed.elements_hash(), hashed_vlist.elements_hash());
+    }
+
+    #[test]
+    fn map_keys() {
+        let mut map = HashMap::new();
+        let tail: HashedList<&str> = ["b", "c"].into_iter().collect();
+
+        map.insert(HashedList::cons("a", tail.clone()), 1);
+        map.insert(tail.clone(), 2);
+
+        assert_eq!(map.get(&["a", "b", "c"].into_iter().collect()), Some(&1));
+        assert_eq!(map.get(&tail), Some(&2));
+        assert_eq!(map.get(&tail.cdr().unwrap()), None);
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn differs_by_order_and_length() {
+        let forward: HashedList<u8> = [1, 2].into_iter().collect();
+        let backward: HashedList<u8> = [2, 1].into_iter().collect();
+        let zeros: HashedList<u8> = [0, 0].into_iter().collect();
+
+        assert_ne!(forward.elements_hash(), backward.elements_hash());
+        assert_ne!(zeros, zeros.cdr().unwrap());
+    }
+}
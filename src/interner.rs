@@ -0,0 +1,457 @@
+//! Hash consing for lists.
+//!
+//! An [`Interner`] turns lists into canonical instances, so that structurally equal lists end
+//! up as the very same nodes. Nodes are deduplicated from the back of the list to the front,
+//! so equal suffixes of different lists are shared as well. Comparing interned lists for
+//! equality is then just [`ptr_eq`](GenericList::ptr_eq).
+//!
+//! The interner only keeps weak references to the nodes it has handed out, so it doesn't
+//! keep any list alive. The entries of nodes that have been dropped are cleared out when a
+//! lookup comes across them, every so often as more lists are interned, or all at once with
+//! [`purge`](Interner::purge).
+//!
+//! The tables are only locked to look up and store weak references. Elements are hashed,
+//! compared, cloned and dropped with every table unlocked, so their code may intern lists of
+//! its own.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::{
+    handler::DropHandler,
+    list::GenericList,
+    shared::{PointerFamily, RcPointer},
+    shared_vector::{
+        alloc::{Allocator, Global},
+        Vector,
+    },
+    unrolled::{NodePtr, UnrolledCell, UnrolledList},
+};
+
+// Nodes are spread over this many tables, each with its own lock
+const SHARDS: usize = 16;
+
+// The fewest entries a table holds before it looks for dropped ones
+const MIN_LIMIT: usize = 64;
+
+type WeakNode<T, P, const N: u32, const G: u32, A> =
+    <P as PointerFamily>::Weak<UnrolledCell<T, P, N, G, A>>;
+
+struct Shard<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + 'static> {
+    // The interned nodes, by the hash of their elements and of the node that follows them
+    nodes: HashMap<u64, Vec<WeakNode<T, P, N, G, A>>>,
+    len: usize,
+    // Once there are more entries than this, the ones of dropped nodes are cleared out
+    limit: usize,
+    // Bumped whenever a node is added, so a lookup made with the table unlocked can tell
+    // whether it is still up to date
+    generation: u64,
+}
+
+// What a table lets go of, to be dropped once it is unlocked. Dropping a node can drop its
+// elements, and dropping the entry of a dropped node frees it with the allocator.
+struct Garbage<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + 'static> {
+    // Nodes upgraded while sweeping, which may be the last references to them
+    nodes: Vec<UnrolledList<T, P, N, G, A>>,
+    entries: Vec<WeakNode<T, P, N, G, A>>,
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Garbage<T, P, N, G, A> {
+    fn new() -> Self {
+        Garbage {
+            nodes: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    // Upgrades the nodes of `bucket`, and moves out the entries of the dropped ones
+    fn sweep(&mut self, bucket: &mut Vec<WeakNode<T, P, N, G, A>>) {
+        for entry in mem::take(bucket) {
+            match NodePtr::upgrade(&entry) {
+                Some(node) => {
+                    self.nodes.push(UnrolledList(node));
+                    bucket.push(entry);
+                }
+                None => self.entries.push(entry),
+            }
+        }
+    }
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Shard<T, P, N, G, A> {
+    fn new() -> Self {
+        Shard {
+            nodes: HashMap::new(),
+            len: 0,
+            limit: MIN_LIMIT,
+            generation: 0,
+        }
+    }
+
+    // Clears out the entries of nodes that have been dropped
+    fn collect(&mut self, garbage: &mut Garbage<T, P, N, G, A>) {
+        self.nodes.retain(|_, bucket| {
+            garbage.sweep(bucket);
+            !bucket.is_empty()
+        });
+
+        self.len = self.nodes.values().map(Vec::len).sum();
+        self.limit = (self.len * 2).max(MIN_LIMIT);
+    }
+}
+
+/// A table of canonical lists, see the [module documentation](self).
+///
+/// The tables are behind locks, so an interner of [`ArcPointer`](crate::shared::ArcPointer)
+/// lists can be shared between threads.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate im_lists;
+/// # use im_lists::interner::Interner;
+/// # use im_lists::list::List;
+/// let interner = Interner::new();
+///
+/// let left = interner.intern(&list![1, 2, 3]);
+/// let right = interner.intern(&List::cons(1, list![2, 3]));
+/// assert!(left.ptr_eq(&right));
+/// assert!(!left.ptr_eq(&interner.intern(&list![2, 3])));
+/// ```
+pub struct Interner<
+    T,
+    P: PointerFamily = RcPointer,
+    const N: u32 = 256,
+    const G: u32 = 1,
+    A: Allocator + Clone + 'static = Global,
+> {
+    shards: Box<[Mutex<Shard<T, P, N, G, A>>]>,
+    hasher: RandomState,
+    allocator: A,
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    Interner<T, P, N, G, A>
+{
+    /// Construct an empty interner.
+    pub fn new() -> Self
+    where
+        A: Default,
+    {
+        Self::new_in(A::default())
+    }
+
+    /// Construct an empty interner, whose nodes allocate their elements from `allocator`.
+    pub fn new_in(allocator: A) -> Self {
+        Interner {
+            shards: (0..SHARDS).map(|_| Mutex::new(Shard::new())).collect(),
+            hasher: RandomState::new(),
+            allocator,
+        }
+    }
+
+    // A panic while a table is locked can't leave it in a broken state
+    fn lock(&self, shard: usize) -> MutexGuard<'_, Shard<T, P, N, G, A>> {
+        self.shards[shard]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the number of nodes in the interner, including the ones that have been dropped
+    /// but not cleared out yet.
+    pub fn len(&self) -> usize {
+        (0..SHARDS).map(|shard| self.lock(shard).len).sum()
+    }
+
+    /// Returns `true` if the interner holds no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Clears out the entries of every node that has been dropped.
+    pub fn purge(&self) {
+        for shard in 0..SHARDS {
+            let mut garbage = Garbage::new();
+            self.lock(shard).collect(&mut garbage);
+        }
+    }
+
+    /// Returns the canonical instance of `list`. Interning lists that are equal gives lists
+    /// that are [`ptr_eq`](GenericList::ptr_eq).
+    ///
+    /// The elements are split up into nodes counting from the back of the list, so that
+    /// equal suffixes are split up the same way and can share their nodes. Elements of new
+    /// nodes are cloned out of `list`.
+    ///
+    /// Time: O(n)
+    pub fn intern<D>(&self, list: &GenericList<T, P, N, G, D, A>) -> GenericList<T, P, N, G, D, A>
+    where
+        T: Clone + Hash + Eq,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+    {
+        let elements: Vec<&T> = list.iter().collect();
+
+        let mut end = elements.len();
+        let mut size = N as usize;
+        let mut node = None;
+
+        loop {
+            let start = end.saturating_sub(size);
+            node = Some(self.intern_node(&elements[start..end], size as u32, node));
+
+            end = start;
+            if end == 0 {
+                break;
+            }
+            size = size.saturating_mul(G as usize);
+        }
+
+        GenericList::from_inner(node.unwrap())
+    }
+
+    fn intern_node(
+        &self,
+        elements: &[&T],
+        size: u32,
+        mut next: Option<UnrolledList<T, P, N, G, A>>,
+    ) -> UnrolledList<T, P, N, G, A>
+    where
+        T: Clone + Hash + Eq,
+    {
+        // Nodes after this one are already canonical, so they can be told apart by address
+        let next_ptr = next.as_ref().map(|next| next.as_ptr() as usize);
+        let hash = self.hasher.hash_one((elements, next_ptr));
+
+        let shard = hash as usize % SHARDS;
+
+        let mut new_node = None;
+        loop {
+            let mut candidates = Garbage::new();
+            let generation = {
+                let mut table = self.lock(shard);
+                if let Some(bucket) = table.nodes.get_mut(&hash) {
+                    candidates.sweep(bucket);
+                    if bucket.is_empty() {
+                        table.nodes.remove(&hash);
+                    }
+                }
+                table.len -= candidates.entries.len();
+                table.generation
+            };
+
+            // A node that is still alive keeps the one after it alive too, so their addresses
+            // can't have been reused
+            let existing = candidates.nodes.iter().position(|node| {
+                node.0.next.as_ref().map(|next| next.as_ptr() as usize) == next_ptr
+                    && node.elements().iter().rev().eq(elements.iter().copied())
+            });
+            if let Some(index) = existing {
+                return candidates.nodes.swap_remove(index);
+            }
+            drop(candidates);
+
+            let node = new_node.take().unwrap_or_else(|| {
+                let mut vector = Vector::with_capacity_in(elements.len(), self.allocator.clone());
+                vector.extend(elements.iter().rev().map(|value| (*value).clone()));
+                UnrolledList::from_node_parts(vector.into_shared_atomic(), size, next.take())
+            });
+
+            let mut garbage = Garbage::new();
+            let mut table = self.lock(shard);
+            if table.generation != generation {
+                // A node was added while the table was unlocked, which may be an equal one
+                drop(table);
+                new_node = Some(node);
+                continue;
+            }
+
+            table
+                .nodes
+                .entry(hash)
+                .or_default()
+                .push(NodePtr::downgrade(&node.0));
+            table.len += 1;
+            table.generation += 1;
+
+            if table.len > table.limit {
+                table.collect(&mut garbage);
+            }
+            drop(table);
+
+            return node;
+        }
+    }
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + Default> Default
+    for Interner<T, P, N, G, A>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list;
+    use crate::list::{List, SharedList, SharedVList, VList};
+    use crate::shared::ArcPointer;
+
+    #[test]
+    fn equal_lists_share_nodes() {
+        let interner = Interner::new();
+
+        let list: List<usize> = (0..1000).collect();
+        let other: List<usize> = List::cons(0, (1..1000).collect());
+        let left = interner.intern(&list);
+        let right = interner.intern(&other);
+
+        assert!(left.ptr_eq(&right));
+        assert_eq!(left, list);
+        assert_eq!(interner.len(), 4);
+
+        // The last three nodes are shared with the interned longer list
+        let suffix = interner.intern(&(1..1000).collect::<List<usize>>());
+        assert!(!suffix.ptr_eq(&left));
+        assert_eq!(interner.len(), 5);
+
+        let empty = interner.intern(&List::new());
+        assert!(empty.ptr_eq(&interner.intern(&List::new())));
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn vlist_nodes_grow_towards_the_front() {
+        let interner = Interner::new();
+        let list: VList<usize> = (0..100).collect();
+        let interned = interner.intern(&list);
+
+        assert_eq!(interned, list);
+        assert!(interned.ptr_eq(&interner.intern(&interned)));
+        assert_eq!(interner.len(), 6);
+
+        // Only the front node isn't shared with the longer list
+        interner.intern(&(3..100).collect::<VList<usize>>());
+        assert_eq!(interner.len(), 7);
+    }
+
+    #[test]
+    fn unused_nodes_are_collected() {
+        let interner = Interner::new();
+
+        let kept = interner.intern(&(0..600).collect::<List<usize>>());
+        for i in 0..100 {
+            interner.intern(&List::cons(i, kept.clone()));
+        }
+        interner.purge();
+
+        assert_eq!(interner.len(), 3);
+        assert!(kept.ptr_eq(&interner.intern(&(0..600).collect())));
+
+        drop(kept);
+        interner.purge();
+        assert!(interner.is_empty());
+
+        // The interner doesn't keep what it hands out alive
+        let weak = interner
+            .intern(&(0..3).collect::<List<usize>>())
+            .downgrade();
+        assert!(weak.upgrade().is_none());
+        assert_eq!(interner.len(), 1);
+
+        // Looking the list up again clears out the entry of the dropped node
+        let again = interner.intern(&(0..3).collect::<List<usize>>());
+        assert_eq!(interner.len(), 1);
+        assert!(again.ptr_eq(&interner.intern(&again)));
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let interner: Interner<usize, ArcPointer> = Interner::new();
+
+        let lists: Vec<SharedList<usize>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| interner.intern(&(0..1000).collect::<SharedList<_>>())))
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        assert!(lists.iter().all(|list| list.ptr_eq(&lists[0])));
+
+        let vlists: Interner<usize, ArcPointer, 2, 2> = Interner::new();
+        let list: SharedVList<usize> = (0..10).collect();
+        assert!(vlists.intern(&list).ptr_eq(&vlists.intern(&list.clone())));
+    }
+
+    #[test]
+    fn elements_can_intern_while_dropped() {
+        use std::cell::{Cell, RefCell};
+
+        // Only `value` is hashed, so every element with the same one lands in the same table
+        #[derive(Clone, Debug)]
+        struct Elem {
+            value: usize,
+            reintern: bool,
+        }
+
+        thread_local! {
+            static INTERNER: Interner<Elem> = Interner::new();
+            static PARKED: RefCell<Option<List<Elem>>> = const { RefCell::new(None) };
+            static REINTERNED: Cell<usize> = const { Cell::new(0) };
+        }
+
+        impl Hash for Elem {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                self.value.hash(state)
+            }
+        }
+
+        // Lets go of the parked list, leaving the interner with the last reference to it
+        impl PartialEq for Elem {
+            fn eq(&self, other: &Self) -> bool {
+                drop(PARKED.with(|parked| parked.borrow_mut().take()));
+                self.value == other.value && self.reintern == other.reintern
+            }
+        }
+
+        impl Eq for Elem {}
+
+        impl Drop for Elem {
+            fn drop(&mut self) {
+                if self.reintern {
+                    let value = self.value;
+                    INTERNER.with(|interner| {
+                        interner.intern(&list![Elem {
+                            value,
+                            reintern: false,
+                        }])
+                    });
+                    REINTERNED.with(|count| count.set(count.get() + 1));
+                }
+            }
+        }
+
+        INTERNER.with(|interner| {
+            let parked = interner.intern(&list![Elem {
+                value: 1,
+                reintern: true,
+            }]);
+            PARKED.with(|slot| *slot.borrow_mut() = Some(parked));
+            let before = REINTERNED.with(Cell::get);
+
+            // Comparing against the parked node drops it, once the table is unlocked
+            let other = interner.intern(&list![Elem {
+                value: 1,
+                reintern: false,
+            }]);
+            assert_eq!(REINTERNED.with(Cell::get), before + 1);
+            assert!(other.ptr_eq(&interner.intern(&other)));
+        });
+    }
+}
@@ -1,10 +1,28 @@
 #![doc = include_str!("../README.md")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod arena;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod handler;
+pub mod hashed;
+#[cfg(feature = "std")]
+pub mod interner;
 pub mod list;
 pub mod shared;
-mod shared_vector;
+pub mod shared_vector;
+#[cfg(test)]
+mod test_alloc;
+pub mod trace;
 pub(crate) mod unrolled;
+pub mod zipper;
+
+#[cfg(all(test, loom))]
+mod loom_tests;
 
 /// Construct a [`List`](crate::list::List) from a sequence of elements
 #[macro_export]
@@ -12,13 +30,13 @@ macro_rules! list {
     () => { $crate::list::List::new() };
 
     ( $($x:expr),+ $(,)? ) => {{
-        vec![$(
+        [$(
             $x,
         ) *].into_iter().collect::<$crate::list::List<_>>()
     }};
 
     ( $($x:expr ,)+ $(,)? ) => {{
-        vec![$($x)*].into_iter().collect::<$crate::list::List<_>>()
+        [$($x)*].into_iter().collect::<$crate::list::List<_>>()
     }};
 }
 
@@ -28,13 +46,13 @@ macro_rules! vlist {
     () => { $crate::list::VList::new() };
 
     ( $($x:expr),+ $(,)? ) => {{
-        vec![$(
+        [$(
             $x,
         ) *].into_iter().collect::<$crate::list::VList<_>>()
     }};
 
     ( $($x:expr ,)+ $(,)? ) => {{
-        vec![$($x)*].into_iter().collect::<$crate::list::VList<_>>()
+        [$($x)*].into_iter().collect::<$crate::list::VList<_>>()
     }};
 }
 
@@ -44,13 +62,13 @@ macro_rules! shared_list {
     () => { $crate::list::SharedList::new() };
 
     ( $($x:expr),+ $(,)? ) => {{
-        vec![$(
+        [$(
             $x,
         ) *].into_iter().collect::<$crate::list::SharedList<_>>()
     }};
 
     ( $($x:expr ,)+ $(,)? ) => {{
-        vec![$($x)*].into_iter().collect::<$crate::list::SharedList<_>>()
+        [$($x)*].into_iter().collect::<$crate::list::SharedList<_>>()
     }};
 }
 
@@ -60,12 +78,12 @@ macro_rules! shared_vlist {
     () => { $crate::list::SharedVList::new() };
 
     ( $($x:expr),+ $(,)? ) => {{
-        vec![$(
+        [$(
             $x,
         ) *].into_iter().collect::<$crate::list::SharedVList<_>>()
     }};
 
     ( $($x:expr ,)+ $(,)? ) => {{
-        vec![$($x)*].into_iter().collect::<$crate::list::SharedVList<_>>()
+        [$($x)*].into_iter().collect::<$crate::list::SharedVList<_>>()
     }};
 }
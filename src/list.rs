@@ -10,12 +10,20 @@
 //! Using the mutable functions when possible enables in place mutation. Much of the internal structure is shared,
 //! so even immutable functions can be fast, but the mutable functions will be faster.
 
-use std::{cmp::Ordering, iter::FromIterator, marker::PhantomData};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::{cmp::Ordering, iter::FromIterator, marker::PhantomData, mem::ManuallyDrop};
 
 use crate::{
-    handler::{DefaultDropHandler, DropHandler},
+    error::TryReserveError,
+    handler::{DefaultDropHandler, DropHandler, DropNodes},
     shared::{ArcPointer, PointerFamily, RcPointer},
-    unrolled::{ConsumingWrapper, IterWrapper, UnrolledCell, UnrolledList},
+    shared_vector::{
+        alloc::{Allocator, Global},
+        AtomicSharedVector, SharedVector, Vector,
+    },
+    trace::Tracer,
+    unrolled::{ChunkPairs, ConsumingWrapper, IterWrapper, NodePtr, UnrolledCell, UnrolledList},
 };
 
 /// A persistent list.
@@ -38,6 +46,9 @@ use crate::{
 ///
 /// ```
 ///
+/// The element buffers of each node are allocated with `A`, which defaults to the global allocator.
+/// See [`GenericList::new_in`].
+///
 /// The list is also designed to leverage in place mutations whenever possible - if the number of references pointing to either a cell containing a vector
 /// or the shared vector is one, then that mutation is done in place. Otherwise, it is copy-on-write, maintaining our persistent invariant.
 ///
@@ -56,12 +67,13 @@ use crate::{
 /// decrease of either a factor of *m* or *m / 2*. Similarly, we will see O(log(n)) performance characteristics if the growth rate is set to be larger than 1.
 #[repr(transparent)]
 pub struct GenericList<
-    T: Clone + 'static,
+    T,
     P: PointerFamily = RcPointer,
     const N: u32 = 256,
     const G: u32 = 1,
     D: DropHandler<Self> = DefaultDropHandler,
->(UnrolledList<T, P, N, G>, PhantomData<D>);
+    A: Allocator + Clone + 'static = Global,
+>(ManuallyDrop<UnrolledList<T, P, N, G, A>>, PhantomData<D>);
 
 pub type SharedList<T> = GenericList<T, ArcPointer, 256>;
 pub type List<T> = GenericList<T, RcPointer, 256>;
@@ -69,35 +81,187 @@ pub type List<T> = GenericList<T, RcPointer, 256>;
 pub type SharedVList<T> = GenericList<T, ArcPointer, 2, 2>;
 pub type VList<T> = GenericList<T, RcPointer, 2, 2>;
 
+/// A handle to a list that doesn't keep it alive, made with
+/// [`downgrade`](GenericList::downgrade).
+///
+/// Only the first node of the list is held weakly, the nodes after it are kept alive by the
+/// ones in front of them as usual.
+pub struct WeakList<
+    T,
+    P: PointerFamily = RcPointer,
+    const N: u32 = 256,
+    const G: u32 = 1,
+    D: DropHandler<GenericList<T, P, N, G, D, A>> = DefaultDropHandler,
+    A: Allocator + Clone + 'static = Global,
+>(P::Weak<UnrolledCell<T, P, N, G, A>>, PhantomData<D>);
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > WeakList<T, P, N, G, D, A>
+{
+    /// Returns the list, or `None` if it has been dropped already.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// let list = list![1, 2, 3];
+    /// let weak = list.downgrade();
+    /// assert_eq!(weak.upgrade(), Some(list![1, 2, 3]));
+    ///
+    /// drop(list);
+    /// assert!(weak.upgrade().is_none());
+    /// ```
+    pub fn upgrade(&self) -> Option<GenericList<T, P, N, G, D, A>> {
+        NodePtr::upgrade(&self.0).map(|node| GenericList::from_inner(UnrolledList(node)))
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > Clone for WeakList<T, P, N, G, D, A>
+{
+    fn clone(&self) -> Self {
+        WeakList(self.0.clone(), PhantomData)
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > core::fmt::Debug for WeakList<T, P, N, G, D, A>
+{
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("(WeakList)")
+    }
+}
+
 #[doc(hidden)]
 #[derive(Copy, Clone)]
 pub struct RawCell<
-    T: Clone + 'static,
+    T,
     P: PointerFamily,
     const N: u32,
     const G: u32,
-    D: DropHandler<GenericList<T, P, N, G, D>>,
->(*const UnrolledCell<T, P, N, G>, PhantomData<D>);
+    D: DropHandler<GenericList<T, P, N, G, D, A>>,
+    A: Allocator + Clone + 'static,
+>(*const UnrolledCell<T, P, N, G, A>, PhantomData<D>);
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> Clone
-    for GenericList<T, P, N, G, D>
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > Clone for GenericList<T, P, N, G, D, A>
 {
     fn clone(&self) -> Self {
-        Self(self.0.clone(), PhantomData)
+        Self::from_inner(UnrolledList::clone(&self.0))
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>>
-    GenericList<T, P, N, G, D>
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > GenericList<T, P, N, G, D, A>
 {
     /// Construct an empty list.
-    pub fn new() -> Self {
-        GenericList(UnrolledList::new(), PhantomData)
+    pub fn new() -> Self
+    where
+        A: Default,
+    {
+        GenericList::from_inner(UnrolledList::new())
+    }
+
+    /// Construct an empty list that allocates its elements with the given allocator.
+    ///
+    /// Every list derived from this one, whether by consing, appending, taking or any other
+    /// operation, allocates its nodes and element buffers from the same allocator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use im_lists::list::GenericList;
+    /// # use im_lists::shared::RcPointer;
+    /// # use im_lists::handler::DefaultDropHandler;
+    /// # use im_lists::shared_vector::alloc::Global;
+    /// let mut list: GenericList<usize, RcPointer, 256, 1, DefaultDropHandler, Global> =
+    ///     GenericList::new_in(Global);
+    /// list.cons_mut(1);
+    /// list.cons_mut(0);
+    /// assert_eq!(list, im_lists::list![0, 1]);
+    /// ```
+    pub fn new_in(allocator: A) -> Self {
+        GenericList::from_inner(UnrolledList::new_in(allocator))
+    }
+
+    /// Fallible version of [`new_in`](GenericList::new_in), returning an error if the
+    /// empty node could not be allocated.
+    pub fn try_new_in(allocator: A) -> Result<Self, TryReserveError> {
+        Ok(GenericList::from_inner(UnrolledList::try_new_in(
+            allocator,
+        )?))
+    }
+
+    /// Constructs a list from an iterator, returning an error instead of aborting if memory
+    /// for the nodes or their elements could not be allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::List;
+    /// let list = List::try_from_iter(0..5).unwrap();
+    /// assert_eq!(list, list![0, 1, 2, 3, 4]);
+    /// ```
+    pub fn try_from_iter(iter: impl IntoIterator<Item = T>) -> Result<Self, TryReserveError>
+    where
+        A: Default,
+    {
+        Self::try_from_iter_in(iter, A::default())
+    }
+
+    /// Fallible version of [`FromIterator`] that allocates the elements with the given
+    /// allocator.
+    pub fn try_from_iter_in(
+        iter: impl IntoIterator<Item = T>,
+        allocator: A,
+    ) -> Result<Self, TryReserveError> {
+        Ok(GenericList::from_inner(UnrolledList::try_from_iter_in(
+            iter, allocator,
+        )?))
     }
 
     /// Constructs an empty list with capacity `N`
-    pub fn new_with_capacity() -> Self {
-        GenericList(UnrolledList::new_with_capacity(), PhantomData)
+    pub fn new_with_capacity() -> Self
+    where
+        A: Default,
+    {
+        GenericList::from_inner(UnrolledList::new_with_capacity())
+    }
+
+    /// Returns a reference to the allocator used for the elements of this list.
+    pub fn allocator(&self) -> &A {
+        self.0.allocator()
     }
 
     /// Get the number of strong references pointing to this list
@@ -107,11 +271,126 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
         self.0.strong_count()
     }
 
+    /// Walks the list front to back, handing each node, its buffer and the elements in it to
+    /// `tracer`. Walking stops at the first node the tracer skips, and elements are skipped
+    /// when the tracer has visited them through another node sharing the buffer, see
+    /// [`Tracer`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::List;
+    /// # use im_lists::trace::{BufferId, NodeId, Tracer};
+    /// # use std::collections::{HashMap, HashSet};
+    /// #[derive(Default)]
+    /// struct Sum {
+    ///     visited: HashSet<NodeId>,
+    ///     buffers: HashMap<BufferId, usize>,
+    ///     total: usize,
+    /// }
+    ///
+    /// impl Tracer<usize> for Sum {
+    ///     fn visit_node(&mut self, node: NodeId) -> bool {
+    ///         self.visited.insert(node)
+    ///     }
+    ///
+    ///     fn visit_buffer(&mut self, buffer: BufferId, len: usize) -> usize {
+    ///         let seen = self.buffers.entry(buffer).or_default();
+    ///         core::mem::replace(seen, len.max(*seen))
+    ///     }
+    ///
+    ///     fn visit_element(&mut self, element: &usize) {
+    ///         self.total += element;
+    ///     }
+    /// }
+    ///
+    /// let tail: List<usize> = (0..1000).collect();
+    /// let left = List::cons(1, tail.clone());
+    /// let right = List::cons(2, tail.clone());
+    ///
+    /// let mut sum = Sum::default();
+    /// left.trace(&mut sum);
+    /// right.trace(&mut sum);
+    /// tail.trace(&mut sum);
+    ///
+    /// // The nodes `left` and `right` copied from the front of `tail` are walked, the rest once
+    /// assert_eq!(sum.total, 1 + 2 + 2 * (0..232).sum::<usize>() + (0..1000).sum::<usize>());
+    /// ```
+    pub fn trace(&self, tracer: &mut impl Tracer<T>) {
+        self.0.trace(tracer)
+    }
+
+    /// Makes a [`WeakList`] handle to this list, which doesn't keep it alive.
+    pub fn downgrade(&self) -> WeakList<T, P, N, G, D, A> {
+        WeakList(NodePtr::downgrade(&self.0 .0), PhantomData)
+    }
+
     /// Compare this list to another for pointer equality
     pub fn ptr_eq(&self, other: &Self) -> bool {
         self.0.ptr_eq(&other.0)
     }
 
+    /// Converts this list into one using the pointer family `P2`, such as turning a [`List`]
+    /// into a [`SharedList`]. Only the nodes are rebuilt, the element buffers are shared
+    /// between both lists, so no element is cloned.
+    ///
+    /// Time: O(n / N)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::{List, SharedList};
+    /// # use im_lists::shared::ArcPointer;
+    /// let list: List<usize> = (0..1000).collect();
+    /// let shared: SharedList<usize> = list.convert::<ArcPointer>();
+    ///
+    /// assert!(shared.iter().eq(list.iter()));
+    /// std::thread::spawn(move || assert_eq!(shared.len(), 1000)).join().unwrap();
+    /// ```
+    pub fn convert<P2: PointerFamily>(&self) -> GenericList<T, P2, N, G, D, A>
+    where
+        D: DropHandler<GenericList<T, P2, N, G, D, A>>,
+    {
+        GenericList::from_inner(self.0.convert())
+    }
+
+    /// Converts this list into one with nodes of `N2` elements growing by a factor of `G2`,
+    /// such as turning a [`List`] into a [`VList`]. The element buffer of a node is shared
+    /// whenever it fits into a node of the new configuration, otherwise its elements are
+    /// cloned into new nodes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::{List, VList};
+    /// let vlist: VList<usize> = (0..1000).collect();
+    /// let list: List<usize> = vlist.rechunk::<256, 1>();
+    ///
+    /// assert!(list.iter().eq(vlist.iter()));
+    /// assert_eq!(list.rechunk::<2, 2>(), vlist);
+    /// ```
+    pub fn rechunk<const N2: u32, const G2: u32>(&self) -> GenericList<T, P, N2, G2, D, A>
+    where
+        T: Clone,
+        D: DropHandler<GenericList<T, P, N2, G2, D, A>>,
+    {
+        GenericList::from_inner(self.0.rechunk())
+    }
+
+    /// Fallible version of [`rechunk`](GenericList::rechunk).
+    pub fn try_rechunk<const N2: u32, const G2: u32>(
+        &self,
+    ) -> Result<GenericList<T, P, N2, G2, D, A>, TryReserveError>
+    where
+        T: Clone,
+        D: DropHandler<GenericList<T, P, N2, G2, D, A>>,
+    {
+        Ok(GenericList::from_inner(self.0.try_rechunk()?))
+    }
+
     #[doc(hidden)]
     pub fn index(&self) -> usize {
         self.0.index() as _
@@ -122,14 +401,38 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
         self.0 .0.elements.len()
     }
 
-    #[doc(hidden)]
-    pub fn inner_ptr(&self) -> &P::Pointer<UnrolledCell<T, P, N, G>> {
-        &self.0 .0
+    /// Take the underlying list out, without running the drop handler on it.
+    fn into_inner(self) -> UnrolledList<T, P, N, G, A> {
+        let this = ManuallyDrop::new(self);
+        // Safety: `this` is never dropped, so the list is only owned by the return value
+        ManuallyDrop::into_inner(unsafe { core::ptr::read(&this.0) })
+    }
+
+    pub(crate) fn from_inner(list: UnrolledList<T, P, N, G, A>) -> Self {
+        GenericList(ManuallyDrop::new(list), PhantomData)
+    }
+
+    // The elements that dropping this list would drop, see `UnrolledList::for_each_owned_mut`
+    pub(crate) fn for_each_owned_mut(&mut self, func: impl FnMut(&mut T)) {
+        self.0.for_each_owned_mut(func)
+    }
+
+    // The number of elements in the first node
+    pub(crate) fn front_node_len(&self) -> usize {
+        self.0.index() as usize
+    }
+
+    // See `UnrolledList::shift_front_node`
+    pub(crate) fn shift_front_node(&mut self, onto: &mut Self)
+    where
+        T: Clone,
+    {
+        self.0.shift_front_node(&mut onto.0)
     }
 
     #[doc(hidden)]
-    pub fn inner_ptr_mut(&mut self) -> &mut P::Pointer<UnrolledCell<T, P, N, G>> {
-        &mut self.0 .0
+    pub fn inner_ptr(&self) -> &UnrolledCell<T, P, N, G, A> {
+        &self.0 .0
     }
 
     #[doc(hidden)]
@@ -170,22 +473,40 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
         self.0.cell_count()
     }
 
-    #[doc(hidden)]
-    pub fn draining_iterator(
-        mut self,
-        // default: UnrolledList<T, P, N, G>,
-    ) -> impl Iterator<Item = T> {
-        std::mem::take(&mut self.0).draining_iterator()
-        // std::mem::replace(&mut self.0, default).draining_iterator()
-        // todo!()
-        // let x = MaybeUninit::new(self);
-        // let x = x.as_ptr();
-
-        // unsafe { std::ptr::read(&(*x).0).draining_iterator() }
-
-        // self.0.draining_iterator()
-        // ManuallyDrop::take(slot)
-        // todo!()
+    /// Consumes the list, moving out the elements of the nodes that this list owns outright.
+    /// Unlike [`into_iter`](IntoIterator::into_iter) this doesn't need `T: Clone`.
+    ///
+    /// # Truncation
+    ///
+    /// **This doesn't always yield every element of the list.** The elements of a node that
+    /// is shared with another list can't be moved out without cloning them, so iteration
+    /// stops at the first such node, and the elements from there on are only dropped along
+    /// with this list. Use [`into_iter`](IntoIterator::into_iter) if every element is needed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use im_lists::list::List;
+    /// let mut list: List<Box<dyn FnOnce() -> usize>> = List::new();
+    /// list.cons_mut_no_clone(Box::new(|| 2));
+    /// list.cons_mut_no_clone(Box::new(|| 1));
+    ///
+    /// let results: Vec<usize> = list.draining_iterator().map(|f| f()).collect();
+    /// assert_eq!(results, vec![1, 2]);
+    /// ```
+    ///
+    /// Shared nodes cut the iteration short:
+    ///
+    /// ```
+    /// # use im_lists::list::List;
+    /// let list: List<usize> = (0..10).collect();
+    /// let other = list.clone();
+    ///
+    /// assert_eq!(list.draining_iterator().count(), 0);
+    /// assert_eq!(other.len(), 10);
+    /// ```
+    pub fn draining_iterator(self) -> impl Iterator<Item = T> {
+        self.into_inner().draining_iterator()
     }
 
     #[doc(hidden)]
@@ -194,14 +515,14 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
             .node_iter()
             .map(|x| {
                 let mut x = x.clone();
-                P::make_mut(&mut x.0).next = None;
-                Self(x, PhantomData)
+                NodePtr::make_mut(&mut x.0).next = None;
+                Self::from_inner(x)
             })
             .collect()
     }
 
     #[doc(hidden)]
-    pub fn as_ptr(&self) -> RawCell<T, P, N, G, D> {
+    pub fn as_ptr(&self) -> RawCell<T, P, N, G, D, A> {
         RawCell(self.0.as_ptr(), PhantomData)
     }
 
@@ -210,20 +531,105 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// This must be called with a valid pointer as returned from as_ptr
     #[doc(hidden)]
     pub unsafe fn call_from_raw<O, F: FnOnce(&Self) -> O>(
-        cell: RawCell<T, P, N, G, D>,
+        cell: RawCell<T, P, N, G, D, A>,
         func: F,
     ) -> O {
-        let value = unsafe { Self::from_raw(cell) };
+        let value = unsafe { Self::from_raw_cell(cell) };
         let res = func(&value);
-        std::mem::forget(value);
+        core::mem::forget(value);
         res
     }
 
     /// # Safety
     /// This must be called with a valid pointer as returned from as_ptr
     #[doc(hidden)]
-    unsafe fn from_raw(cell: RawCell<T, P, N, G, D>) -> Self {
-        Self(UnrolledList(P::from_raw(cell.0)), PhantomData)
+    unsafe fn from_raw_cell(cell: RawCell<T, P, N, G, D, A>) -> Self {
+        Self::from_inner(UnrolledList(NodePtr::from_raw(cell.0)))
+    }
+
+    /// The alignment of the pointers returned by [`into_raw`](Self::into_raw). It is at least
+    /// 8 on every platform, so the lowest three bits of those pointers are always zero and
+    /// free to hold a tag.
+    pub const RAW_ALIGN: usize = core::mem::align_of::<UnrolledCell<T, P, N, G, A>>();
+
+    /// Consumes the list, returning a pointer to its first node. The list's reference to the
+    /// node is kept alive by the pointer, until it's turned back into a list with
+    /// [`from_raw`](Self::from_raw) or released with
+    /// [`decrement_strong_count`](Self::decrement_strong_count).
+    ///
+    /// The pointer is never null, and is aligned to [`RAW_ALIGN`](Self::RAW_ALIGN).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::List;
+    /// let list = list![1, 2, 3];
+    ///
+    /// // Keep a tag in the low bits
+    /// let tagged = List::<i32>::into_raw(list) as u64 | 0b101;
+    ///
+    /// let pointer = (tagged & !0b111) as *const ();
+    /// let list = unsafe { List::<i32>::from_raw(pointer) };
+    /// assert_eq!(list, list![1, 2, 3]);
+    /// ```
+    pub fn into_raw(this: Self) -> *const () {
+        NodePtr::into_raw(this.into_inner().0).cast()
+    }
+
+    /// Turns a pointer returned by [`into_raw`](Self::into_raw) back into the list.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `into_raw` on a list of this exact type, with the same
+    /// element type, pointer family, node size, growth rate, drop handler and allocator. Each
+    /// pointer gives back the one reference it holds, so it can only be turned back into a
+    /// list once for every time it was returned by `into_raw`, or passed to
+    /// [`increment_strong_count`](Self::increment_strong_count).
+    pub unsafe fn from_raw(ptr: *const ()) -> Self {
+        Self::from_inner(UnrolledList(NodePtr::from_raw(ptr.cast())))
+    }
+
+    /// Adds a reference to the list behind a pointer returned by
+    /// [`into_raw`](Self::into_raw), as if the list were cloned and the clone turned into a
+    /// pointer as well.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `into_raw` on a list of this exact type, and still
+    /// hold its reference.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::List;
+    /// let pointer = List::into_raw(list![1, 2, 3]);
+    ///
+    /// unsafe {
+    ///     List::<i32>::increment_strong_count(pointer);
+    ///     let list = List::<i32>::from_raw(pointer);
+    ///     assert_eq!(list.strong_count(), 2);
+    ///
+    ///     List::<i32>::decrement_strong_count(pointer);
+    ///     assert_eq!(list.strong_count(), 1);
+    /// }
+    /// ```
+    pub unsafe fn increment_strong_count(ptr: *const ()) {
+        let list = ManuallyDrop::new(Self::from_raw(ptr));
+        core::mem::forget(GenericList::clone(&list));
+    }
+
+    /// Releases the reference held by a pointer returned by [`into_raw`](Self::into_raw), as
+    /// if it were turned back into a list and dropped. Dropping the last reference drops the
+    /// list.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been returned by `into_raw` on a list of this exact type, and still
+    /// hold its reference. The reference is gone afterwards.
+    pub unsafe fn decrement_strong_count(ptr: *const ()) {
+        drop(Self::from_raw(ptr));
     }
 
     /// Get the length of the list
@@ -250,9 +656,20 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// let list = list![1, 2, 3, 4, 5].reverse();
     /// assert_eq!(list, list![5, 4, 3, 2, 1])
     /// ```
-    pub fn reverse(mut self) -> Self {
-        self.0 = std::mem::take(&mut self.0).reverse();
-        self
+    pub fn reverse(self) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_inner(self.into_inner().reverse())
+    }
+
+    /// Fallible version of [`reverse`](GenericList::reverse). The input list is consumed
+    /// either way.
+    pub fn try_reverse(self) -> Result<Self, TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(Self::from_inner(self.into_inner().try_reverse()?))
     }
 
     /// Get the last element of the list.
@@ -287,7 +704,10 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// let car = list.car();
     /// assert!(car.is_none());
     /// ```
-    pub fn car(&self) -> Option<T> {
+    pub fn car(&self) -> Option<T>
+    where
+        T: Clone,
+    {
         self.0.car()
     }
 
@@ -327,13 +747,13 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// let cdr = list.cdr();
     /// assert!(cdr.is_none());
     /// ```
-    pub fn cdr(&self) -> Option<GenericList<T, P, N, G, D>> {
-        self.0.cdr().map(|x| GenericList(x, PhantomData))
+    pub fn cdr(&self) -> Option<GenericList<T, P, N, G, D, A>> {
+        self.0.cdr().map(|x| GenericList::from_inner(x))
     }
 
     /// Get the "rest" of the elements as a list.
     /// Alias for [`cdr`](crate::list::List::cdr)
-    pub fn rest(&self) -> Option<GenericList<T, P, N, G, D>> {
+    pub fn rest(&self) -> Option<GenericList<T, P, N, G, D, A>> {
         self.cdr()
     }
 
@@ -386,11 +806,34 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// let list = List::cons(1, List::cons(2, List::cons(3, List::cons(4, List::new()))));
     /// assert_eq!(list, list![1, 2, 3, 4]);
     /// ```
-    pub fn cons(value: T, mut other: GenericList<T, P, N, G, D>) -> GenericList<T, P, N, G, D> {
-        Self(
-            UnrolledList::cons(value, std::mem::take(&mut other.0)),
-            PhantomData,
-        )
+    pub fn cons(value: T, other: GenericList<T, P, N, G, D, A>) -> GenericList<T, P, N, G, D, A>
+    where
+        T: Clone,
+    {
+        Self::from_inner(UnrolledList::cons(value, other.into_inner()))
+    }
+
+    /// Fallible version of [`cons`](GenericList::cons). The input list is consumed either way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list::List;
+    /// let list = List::try_cons(1, list![2, 3]).unwrap();
+    /// assert_eq!(list, list![1, 2, 3]);
+    /// ```
+    pub fn try_cons(
+        value: T,
+        other: GenericList<T, P, N, G, D, A>,
+    ) -> Result<GenericList<T, P, N, G, D, A>, TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(Self::from_inner(UnrolledList::try_cons(
+            value,
+            other.into_inner(),
+        )?))
     }
 
     /// Mutably pushes an element onto the front of the list, in place
@@ -407,10 +850,45 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// list.cons_mut(0);
     /// assert_eq!(list, list![0, 1, 2, 3])
     /// ```
-    pub fn cons_mut(&mut self, value: T) {
+    pub fn cons_mut(&mut self, value: T)
+    where
+        T: Clone,
+    {
         self.0.cons_mut(value)
     }
 
+    /// Pushes an element onto the front of the list in place without ever cloning elements,
+    /// so unlike [`cons_mut`](GenericList::cons_mut) it works for any `T`.
+    ///
+    /// The element is stored in the first node if the list owns that node outright and it has
+    /// room, otherwise it goes into a new node. Consing onto a uniquely owned list this way is
+    /// just as compact as `cons_mut`, but consing onto a shared list starts a new node where
+    /// `cons_mut` would copy the shared one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use im_lists::list::List;
+    /// struct Handle(usize);
+    ///
+    /// let mut list = List::new();
+    /// list.cons_mut_no_clone(Handle(1));
+    /// list.cons_mut_no_clone(Handle(0));
+    /// assert!(list.iter().map(|h| h.0).eq([0, 1]));
+    /// ```
+    pub fn cons_mut_no_clone(&mut self, value: T) {
+        self.0.cons_mut_no_clone(value)
+    }
+
+    /// Fallible version of [`cons_mut`](GenericList::cons_mut). The list is left unchanged
+    /// on failure.
+    pub fn try_cons_mut(&mut self, value: T) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(self.0.try_cons_mut(value)?)
+    }
+
     /// Alias for cons_mut
     ///
     /// # Examples
@@ -425,10 +903,21 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// list.push_front(0);
     /// assert_eq!(list, list![0, 1, 2, 3])
     /// ```
-    pub fn push_front(&mut self, value: T) {
+    pub fn push_front(&mut self, value: T)
+    where
+        T: Clone,
+    {
         self.0.push_front(value)
     }
 
+    /// Alias for try_cons_mut
+    pub fn try_push_front(&mut self, value: T) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+    {
+        self.try_cons_mut(value)
+    }
+
     /// Mutably pop the first value off of the list
     ///
     /// # Examples
@@ -442,7 +931,10 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// assert_eq!(list.pop_front().unwrap(), 3);
     /// assert!(list.pop_front().is_none())
     /// ```
-    pub fn pop_front(&mut self) -> Option<T> {
+    pub fn pop_front(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
         self.0.pop_front()
     }
 
@@ -461,11 +953,32 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// list.push_back(3);
     /// assert_eq!(list, list![0, 1, 2, 3])
     /// ```
-    pub fn push_back(&mut self, value: T) {
+    pub fn push_back(&mut self, value: T)
+    where
+        T: Clone,
+    {
         self.0.push_back(value)
     }
 
-    /// Construct a new list from the first `count` elements from the current list
+    /// Fallible version of [`push_back`](GenericList::push_back). The list is left unchanged
+    /// on failure.
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list;
+    /// let mut list = list![0, 1];
+    /// list.try_push_back(2).unwrap();
+    /// assert_eq!(list, list![0, 1, 2])
+    /// ```
+    pub fn try_push_back(&mut self, value: T) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(self.0.try_push_back(value)?)
+    }
+
+    /// Construct a new list from the first `count` elements from the current list
     ///
     /// # Examples
     /// ```
@@ -475,8 +988,19 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// let new_list = list.take(3);
     /// assert_eq!(new_list, list![0, 1, 2]);
     /// ```
-    pub fn take(&self, count: usize) -> Self {
-        GenericList(self.0.take(count), PhantomData)
+    pub fn take(&self, count: usize) -> Self
+    where
+        T: Clone,
+    {
+        GenericList::from_inner(self.0.take(count))
+    }
+
+    /// Fallible version of [`take`](GenericList::take).
+    pub fn try_take(&self, count: usize) -> Result<Self, TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(GenericList::from_inner(self.0.try_take(count)?))
     }
 
     /// Returns the list after the first `len` elements of lst.
@@ -494,7 +1018,7 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// assert!(no_list.is_none())
     /// ```
     pub fn tail(&self, len: usize) -> Option<Self> {
-        self.0.tail(len).map(|x| GenericList(x, PhantomData))
+        self.0.tail(len).map(|x| GenericList::from_inner(x))
     }
 
     /// Constructs an iterator over the list
@@ -519,11 +1043,22 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// let right = list![4usize, 5, 6];
     /// assert_eq!(left.append(right), list![1, 2, 3, 4, 5, 6])
     /// ```
-    pub fn append(mut self, mut other: Self) -> Self {
-        GenericList(
-            std::mem::take(&mut self.0).append(std::mem::take(&mut other.0)),
-            PhantomData,
-        )
+    pub fn append(self, other: Self) -> Self
+    where
+        T: Clone,
+    {
+        GenericList::from_inner(self.into_inner().append(other.into_inner()))
+    }
+
+    /// Fallible version of [`append`](GenericList::append). Both input lists are consumed
+    /// either way.
+    pub fn try_append(self, other: Self) -> Result<Self, TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(GenericList::from_inner(
+            self.into_inner().try_append(other.into_inner())?,
+        ))
     }
 
     /// Append the list 'other' to the end of the current list in place.
@@ -538,8 +1073,41 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// left.append_mut(right);
     /// assert_eq!(left, list![1, 2, 3, 4, 5, 6])
     /// ```
-    pub fn append_mut(&mut self, mut other: Self) {
-        self.0.append_mut(std::mem::take(&mut other.0));
+    pub fn append_mut(&mut self, other: Self)
+    where
+        T: Clone,
+    {
+        self.0.append_mut(other.into_inner());
+    }
+
+    /// Fallible version of [`append_mut`](GenericList::append_mut). The list is left unchanged
+    /// on failure.
+    pub fn try_append_mut(&mut self, other: Self) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+    {
+        Ok(self.0.try_append_mut(other.into_inner())?)
+    }
+
+    /// Starts a batch of edits on a copy of this list. See [`TransientList`].
+    ///
+    /// Time: O(1)
+    ///
+    /// # Examples
+    /// ```
+    /// # #[macro_use] extern crate im_lists;
+    /// # use im_lists::list;
+    /// let list = list![3usize, 4, 5];
+    /// let mut transient = list.transient();
+    /// transient.push_front(2);
+    /// transient.push_front(1);
+    /// transient.push_back(6);
+    ///
+    /// assert_eq!(transient.persistent(), list![1, 2, 3, 4, 5, 6]);
+    /// assert_eq!(list, list![3, 4, 5]);
+    /// ```
+    pub fn transient(&self) -> TransientList<T, P, N, G, D, A> {
+        TransientList(self.clone())
     }
 
     /// Checks whether a list is empty
@@ -570,6 +1138,7 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// ```
     pub fn sort(&mut self)
     where
+        T: Clone,
         T: Ord,
     {
         self.0.sort()
@@ -587,75 +1156,224 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self
     /// ```
     pub fn sort_by<F>(&mut self, cmp: F)
     where
+        T: Clone,
         F: Fn(&T, &T) -> Ordering,
     {
         self.0.sort_by(cmp)
     }
+
+    /// Fallible version of [`sort`](GenericList::sort). The list is left unchanged on failure.
+    pub fn try_sort(&mut self) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+        T: Ord,
+    {
+        self.try_sort_by(Ord::cmp)
+    }
+
+    /// Fallible version of [`sort_by`](GenericList::sort_by). The list is left unchanged on
+    /// failure.
+    pub fn try_sort_by<F>(&mut self, cmp: F) -> Result<(), TryReserveError>
+    where
+        T: Clone,
+        F: Fn(&T, &T) -> Ordering,
+    {
+        Ok(self.0.try_sort_by(cmp)?)
+    }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> Default
-    for GenericList<T, P, N, G, D>
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > Default for GenericList<T, P, N, G, D, A>
 {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> Extend<T>
-    for GenericList<T, P, N, G, D>
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > Extend<T> for GenericList<T, P, N, G, D, A>
 {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        self.append_mut(iter.into_iter().collect())
+        self.0.extend(iter)
     }
 }
 
 // and we'll implement FromIterator
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> FromIterator<T>
-    for GenericList<T, P, N, G, D>
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > FromIterator<T> for GenericList<T, P, N, G, D, A>
 {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        GenericList(iter.into_iter().collect(), PhantomData)
+        GenericList::from_inner(iter.into_iter().collect())
     }
 }
 
-impl<'a, T: 'a + Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>>
-    FromIterator<&'a T> for GenericList<T, P, N, G, D>
+impl<
+        'a,
+        T: 'a + Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > FromIterator<&'a T> for GenericList<T, P, N, G, D, A>
 {
     fn from_iter<I: IntoIterator<Item = &'a T>>(iter: I) -> Self {
-        GenericList(iter.into_iter().cloned().collect(), PhantomData)
+        GenericList::from_inner(iter.into_iter().cloned().collect())
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>>
-    FromIterator<GenericList<T, P, N, G, D>> for GenericList<T, P, N, G, D>
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > FromIterator<GenericList<T, P, N, G, D, A>> for GenericList<T, P, N, G, D, A>
 {
-    fn from_iter<I: IntoIterator<Item = GenericList<T, P, N, G, D>>>(iter: I) -> Self {
-        GenericList(
+    fn from_iter<I: IntoIterator<Item = GenericList<T, P, N, G, D, A>>>(iter: I) -> Self {
+        GenericList::from_inner(
             iter.into_iter()
-                .flat_map(|mut x| std::mem::take(&mut x.0).into_node_iter())
+                .flat_map(|x| x.into_inner().into_node_iter())
                 .collect(),
-            PhantomData,
         )
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> From<Vec<T>>
-    for GenericList<T, P, N, G, D>
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > From<Vec<T>> for GenericList<T, P, N, G, D, A>
 {
     fn from(vec: Vec<T>) -> Self {
-        GenericList(vec.into_iter().collect(), PhantomData)
+        GenericList::from_inner(vec.into_iter().collect())
+    }
+}
+
+/// Builds a list from the vector, in order. If the vector fits in a single node, its buffer
+/// is reused as the storage for that node.
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > From<Vector<T, A>> for GenericList<T, P, N, G, D, A>
+{
+    fn from(vector: Vector<T, A>) -> Self {
+        GenericList::from_inner(vector.into())
+    }
+}
+
+/// Builds a list from the vector, in order. If the vector fits in a single node and is unique,
+/// its buffer is reused as the storage for that node.
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > From<AtomicSharedVector<T, A>> for GenericList<T, P, N, G, D, A>
+{
+    fn from(vector: AtomicSharedVector<T, A>) -> Self {
+        GenericList::from_inner(vector.into())
+    }
+}
+
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > From<SharedVector<T, A>> for GenericList<T, P, N, G, D, A>
+{
+    fn from(vector: SharedVector<T, A>) -> Self {
+        vector.into_unique().into()
+    }
+}
+
+/// Collects the list into a vector. Elements of nodes that aren't shared with any other
+/// list are moved rather than cloned.
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > From<GenericList<T, P, N, G, D, A>> for Vector<T, A>
+{
+    fn from(list: GenericList<T, P, N, G, D, A>) -> Self {
+        let mut vector = Vector::with_capacity_in(list.len(), list.allocator().clone());
+        vector.extend(list);
+        vector
+    }
+}
+
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > From<GenericList<T, P, N, G, D, A>> for SharedVector<T, A>
+{
+    fn from(list: GenericList<T, P, N, G, D, A>) -> Self {
+        Vector::from(list).into_shared()
     }
 }
 
 impl<
-        T: Clone + std::fmt::Debug,
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > From<GenericList<T, P, N, G, D, A>> for AtomicSharedVector<T, A>
+{
+    fn from(list: GenericList<T, P, N, G, D, A>) -> Self {
+        Vector::from(list).into_shared_atomic()
+    }
+}
+
+impl<
+        T: core::fmt::Debug,
         P: PointerFamily,
         const N: u32,
         const G: u32,
         D: DropHandler<Self>,
-    > std::fmt::Debug for GenericList<T, P, N, G, D>
+        A: Allocator + Clone,
+    > core::fmt::Debug for GenericList<T, P, N, G, D, A>
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_list().entries(self).finish()
     }
 }
@@ -663,21 +1381,23 @@ impl<
 /// An iterator over lists with values of type `T`.
 pub struct Iter<
     'a,
-    T: Clone + 'static,
+    T,
     P: PointerFamily,
     const N: u32,
     const G: u32,
-    D: DropHandler<GenericList<T, P, N, G, D>>,
->(IterWrapper<'a, T, P, N, G>, PhantomData<D>);
+    D: DropHandler<GenericList<T, P, N, G, D, A>>,
+    A: Allocator + Clone + 'static,
+>(IterWrapper<'a, T, P, N, G, A>, PhantomData<D>);
 
 impl<
         'a,
-        T: Clone + 'static,
+        T,
         P: PointerFamily,
         const N: u32,
         const G: u32,
-        D: DropHandler<GenericList<T, P, N, G, D>>,
-    > Iterator for Iter<'a, T, P, N, G, D>
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > Iterator for Iter<'a, T, P, N, G, D, A>
 {
     type Item = &'a T;
 
@@ -703,15 +1423,16 @@ impl<
 
 impl<
         'a,
-        T: Clone,
+        T,
         P: PointerFamily,
         const N: u32,
         const G: u32,
-        D: DropHandler<GenericList<T, P, N, G, D>>,
-    > IntoIterator for &'a GenericList<T, P, N, G, D>
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > IntoIterator for &'a GenericList<T, P, N, G, D, A>
 {
     type Item = &'a T;
-    type IntoIter = Iter<'a, T, P, N, G, D>;
+    type IntoIter = Iter<'a, T, P, N, G, D, A>;
 
     #[inline(always)]
     fn into_iter(self) -> Self::IntoIter {
@@ -721,20 +1442,22 @@ impl<
 
 /// A consuming iterator over lists with values of type `T`.
 pub struct ConsumingIter<
-    T: Clone + 'static,
+    T,
     P: PointerFamily,
     const N: u32,
     const G: u32,
-    D: DropHandler<GenericList<T, P, N, G, D>>,
->(ConsumingWrapper<T, P, N, G>, PhantomData<D>);
+    D: DropHandler<GenericList<T, P, N, G, D, A>>,
+    A: Allocator + Clone + 'static,
+>(ConsumingWrapper<T, P, N, G, A>, PhantomData<D>);
 
 impl<
-        T: Clone,
+        T,
         P: PointerFamily,
         const N: u32,
         const G: u32,
-        D: DropHandler<GenericList<T, P, N, G, D>>,
-    > Iterator for ConsumingIter<T, P, N, G, D>
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > Iterator for ConsumingIter<T, P, N, G, D, A>
 {
     type Item = T;
 
@@ -758,15 +1481,21 @@ impl<
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> IntoIterator
-    for GenericList<T, P, N, G, D>
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > IntoIterator for GenericList<T, P, N, G, D, A>
 {
     type Item = T;
-    type IntoIter = ConsumingIter<T, P, N, G, D>;
+    type IntoIter = ConsumingIter<T, P, N, G, D, A>;
 
     #[inline(always)]
-    fn into_iter(mut self) -> Self::IntoIter {
-        ConsumingIter(std::mem::take(&mut self.0).into_iter(), PhantomData)
+    fn into_iter(self) -> Self::IntoIter {
+        ConsumingIter(self.into_inner().into_iter(), PhantomData)
     }
 }
 
@@ -777,58 +1506,401 @@ impl<
         const N: u32,
         const G: u32,
         D: 'a + DropHandler<Self>,
-    > FromIterator<&'a GenericList<T, P, N, G, D>> for GenericList<T, P, N, G, D>
+        A: Allocator + Clone + Default,
+    > FromIterator<&'a GenericList<T, P, N, G, D, A>> for GenericList<T, P, N, G, D, A>
 {
-    fn from_iter<I: IntoIterator<Item = &'a GenericList<T, P, N, G, D>>>(iter: I) -> Self {
+    fn from_iter<I: IntoIterator<Item = &'a GenericList<T, P, N, G, D, A>>>(iter: I) -> Self {
         iter.into_iter().cloned().collect()
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> From<&[T]>
-    for GenericList<T, P, N, G, D>
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > From<&[T]> for GenericList<T, P, N, G, D, A>
 {
     fn from(vec: &[T]) -> Self {
         vec.iter().cloned().collect()
     }
 }
 
-impl<T: Clone + PartialEq, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>>
-    PartialEq for GenericList<T, P, N, G, D>
+// Lists are compared against sequences a chunk at a time, see `ChunkPairs`
+fn eq_chunks<'a, T: PartialEq + 'a>(
+    left: impl Iterator<Item = &'a [T]>,
+    left_len: usize,
+    right: impl Iterator<Item = &'a [T]>,
+    right_len: usize,
+) -> bool {
+    left_len == right_len && ChunkPairs::new(left, right).all(|(l, r)| l.iter().rev().eq(r))
+}
+
+fn partial_cmp_chunks<'a, T: PartialOrd + 'a>(
+    left: impl Iterator<Item = &'a [T]>,
+    left_len: usize,
+    right: impl Iterator<Item = &'a [T]>,
+    right_len: usize,
+) -> Option<Ordering> {
+    for (l, r) in ChunkPairs::new(left, right) {
+        match l.iter().rev().partial_cmp(r) {
+            Some(Ordering::Equal) => {}
+            ordering => return ordering,
+        }
+    }
+
+    left_len.partial_cmp(&right_len)
+}
+
+impl<
+        T: PartialEq,
+        P: PointerFamily,
+        P2: PointerFamily,
+        const N: u32,
+        const N2: u32,
+        const G: u32,
+        const G2: u32,
+        D: DropHandler<Self>,
+        D2: DropHandler<GenericList<T, P2, N2, G2, D2, A2>>,
+        A: Allocator + Clone,
+        A2: Allocator + Clone,
+    > PartialEq<GenericList<T, P2, N2, G2, D2, A2>> for GenericList<T, P, N, G, D, A>
+{
+    fn eq(&self, other: &GenericList<T, P2, N2, G2, D2, A2>) -> bool {
+        self.0
+            .compare_chunks(&other.0, |l, r| (l == r).then_some(Ordering::Equal))
+            == Some(Ordering::Equal)
+    }
+}
+
+impl<
+        T: Eq,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > Eq for GenericList<T, P, N, G, D, A>
+{
+}
+
+impl<
+        T: PartialOrd,
+        P: PointerFamily,
+        P2: PointerFamily,
+        const N: u32,
+        const N2: u32,
+        const G: u32,
+        const G2: u32,
+        D: DropHandler<Self>,
+        D2: DropHandler<GenericList<T, P2, N2, G2, D2, A2>>,
+        A: Allocator + Clone,
+        A2: Allocator + Clone,
+    > PartialOrd<GenericList<T, P2, N2, G2, D2, A2>> for GenericList<T, P, N, G, D, A>
+{
+    fn partial_cmp(&self, other: &GenericList<T, P2, N2, G2, D2, A2>) -> Option<Ordering> {
+        self.0
+            .compare_chunks(&other.0, |l, r| l.iter().rev().partial_cmp(r.iter().rev()))
+    }
+}
+
+impl<
+        T: Ord,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > Ord for GenericList<T, P, N, G, D, A>
+{
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .compare_chunks(&other.0, |l, r| Some(l.iter().rev().cmp(r.iter().rev())))
+            .unwrap()
+    }
+}
+
+// Compares lists against sequences stored in order, in both directions
+macro_rules! impl_sequence_comparisons {
+    ($($sequence:ty, [$($param:tt)*], |$this:ident| $chunks:expr;)*) => {$(
+        impl<
+                T: PartialEq,
+                P: PointerFamily,
+                const N: u32,
+                const G: u32,
+                D: DropHandler<Self>,
+                A: Allocator + Clone,
+                $($param)*
+            > PartialEq<$sequence> for GenericList<T, P, N, G, D, A>
+        {
+            fn eq(&self, other: &$sequence) -> bool {
+                let $this = other;
+                eq_chunks(self.0.chunks(), self.len(), $chunks, other.len())
+            }
+        }
+
+        impl<
+                T: PartialEq,
+                P: PointerFamily,
+                const N: u32,
+                const G: u32,
+                D: DropHandler<GenericList<T, P, N, G, D, A>>,
+                A: Allocator + Clone,
+                $($param)*
+            > PartialEq<GenericList<T, P, N, G, D, A>> for $sequence
+        {
+            fn eq(&self, other: &GenericList<T, P, N, G, D, A>) -> bool {
+                other == self
+            }
+        }
+
+        impl<
+                T: PartialOrd,
+                P: PointerFamily,
+                const N: u32,
+                const G: u32,
+                D: DropHandler<Self>,
+                A: Allocator + Clone,
+                $($param)*
+            > PartialOrd<$sequence> for GenericList<T, P, N, G, D, A>
+        {
+            fn partial_cmp(&self, other: &$sequence) -> Option<Ordering> {
+                let $this = other;
+                partial_cmp_chunks(self.0.chunks(), self.len(), $chunks, other.len())
+            }
+        }
+    )*};
+}
+
+impl_sequence_comparisons! {
+    [T], [], |slice| core::iter::once(slice);
+    &[T], [], |slice| core::iter::once(*slice);
+    [T; M], [const M: usize], |array| core::iter::once(&array[..]);
+    Vec<T>, [], |vec| core::iter::once(vec.as_slice());
+    VecDeque<T>, [], |deque| {
+        let (front, back) = deque.as_slices();
+        [front, back].into_iter()
+    };
+}
+
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > core::ops::Add for GenericList<T, P, N, G, D, A>
 {
-    fn eq(&self, other: &Self) -> bool {
-        self.iter().eq(other.iter())
+    type Output = GenericList<T, P, N, G, D, A>;
+
+    /// Concatenate two lists
+    fn add(self, other: Self) -> Self::Output {
+        self.append(other)
     }
 }
 
-impl<T: Clone + Eq, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> Eq
-    for GenericList<T, P, N, G, D>
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > core::ops::Add for &GenericList<T, P, N, G, D, A>
 {
+    type Output = GenericList<T, P, N, G, D, A>;
+
+    /// Concatenate two lists
+    fn add(self, other: Self) -> Self::Output {
+        self.clone().append(other.clone())
+    }
 }
 
-impl<T: Clone + PartialOrd, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>>
-    PartialOrd for GenericList<T, P, N, G, D>
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone + Default,
+    > core::iter::Sum for GenericList<T, P, N, G, D, A>
 {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.iter().partial_cmp(other.iter())
+    fn sum<I>(it: I) -> Self
+    where
+        I: Iterator<Item = Self>,
+    {
+        it.fold(Self::new(), |a, b| a + b)
+    }
+}
+
+impl<
+        T: core::hash::Hash,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > core::hash::Hash for GenericList<T, P, N, G, D, A>
+{
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        for chunk in self.0.chunks() {
+            for i in chunk.iter().rev() {
+                i.hash(state)
+            }
+        }
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > core::ops::Index<usize> for GenericList<T, P, N, G, D, A>
+{
+    type Output = T;
+    /// Get a reference to the value at index `index` in the vector.
+    ///
+    /// Time: O(log n)
+    fn index(&self, index: usize) -> &Self::Output {
+        match self.get(index) {
+            Some(value) => value,
+            None => panic!(
+                "{}::index: index out of bounds: {} < {}",
+                stringify!($list),
+                index,
+                self.len()
+            ),
+        }
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > Drop for GenericList<T, P, N, G, D, A>
+{
+    fn drop(&mut self) {
+        D::drop_handler(self);
+        // Safety: the list is never touched again
+        D::drop_nodes(DroppedList(unsafe { ManuallyDrop::take(&mut self.0) }))
+    }
+}
+
+impl<
+        T,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<Self>,
+        A: Allocator + Clone,
+    > DropNodes for GenericList<T, P, N, G, D, A>
+{
+    type Nodes = UnrolledList<T, P, N, G, A>;
+}
+
+/// A list that is being edited in a batch, made with [`transient`](GenericList::transient).
+///
+/// The first edit that touches a node shared with another list copies it, and every later
+/// edit in the batch changes the copy in place. That includes the nodes on the way to the
+/// back of the list, so [`push_back`](TransientList::push_back) and
+/// [`append`](TransientList::append) copy each shared node of the spine once per batch, not
+/// once per call.
+///
+/// There is no owner token: whether a node may be changed in place is decided by its
+/// reference count alone, as for the in place edits of a [`GenericList`]. A copied node is
+/// only referenced by the transient, and since a transient can't be cloned or borrowed as a
+/// [`GenericList`], nothing can take a second reference to it before
+/// [`persistent`](TransientList::persistent) hands the result back, so no other list can
+/// observe the edits. Without a token, though, every edit still checks the reference count
+/// of each node it walks, and the nodes of an appended list that is still referenced
+/// elsewhere are copied the first time an edit walks through them.
+pub struct TransientList<
+    T,
+    P: PointerFamily = RcPointer,
+    const N: u32 = 256,
+    const G: u32 = 1,
+    D: DropHandler<GenericList<T, P, N, G, D, A>> = DefaultDropHandler,
+    A: Allocator + Clone + 'static = Global,
+>(GenericList<T, P, N, G, D, A>);
+
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > TransientList<T, P, N, G, D, A>
+{
+    /// Ends the batch, returning the edited list.
+    ///
+    /// Time: O(1)
+    pub fn persistent(self) -> GenericList<T, P, N, G, D, A> {
+        self.0
+    }
+
+    /// Push one value to the front of the list.
+    ///
+    /// Time: O(1)
+    pub fn push_front(&mut self, value: T) {
+        self.0 .0.transient_cons(value)
+    }
+
+    /// Pop the first value off of the list.
+    ///
+    /// Time: O(1)
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.0 .0.transient_pop_front()
+    }
+
+    /// Push one value to the back of the list. The nodes on the way are copied the first time
+    /// only, and the value is inserted into the last node if it has room.
+    ///
+    /// Time: O(n / N + N)
+    pub fn push_back(&mut self, value: T) {
+        self.0 .0.transient_push_back(value)
+    }
+
+    /// Append the list `other` to the end of the list, sharing its nodes.
+    ///
+    /// Time: O(n / N)
+    pub fn append(&mut self, other: GenericList<T, P, N, G, D, A>) {
+        self.0 .0.transient_append(other.into_inner())
+    }
+
+    /// Get the number of elements in the list.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
-}
 
-impl<T: Clone + Ord, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> Ord
-    for GenericList<T, P, N, G, D>
-{
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.iter().cmp(other.iter())
+    /// Get a reference to the first element of the list.
+    pub fn first(&self) -> Option<&T> {
+        self.0.first()
     }
-}
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> std::ops::Add
-    for GenericList<T, P, N, G, D>
-{
-    type Output = GenericList<T, P, N, G, D>;
+    /// Get a reference to the value at index `index` in the list.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.0.get(index)
+    }
 
-    /// Concatenate two lists
-    fn add(self, other: Self) -> Self::Output {
-        self.append(other)
+    /// Constructs an iterator over the list.
+    pub fn iter(&self) -> impl Iterator<Item = &'_ T> {
+        self.0.iter()
     }
 }
 
@@ -837,83 +1909,73 @@ impl<
         P: PointerFamily,
         const N: u32,
         const G: u32,
-        D: DropHandler<GenericList<T, P, N, G, D>>,
-    > std::ops::Add for &GenericList<T, P, N, G, D>
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > Extend<T> for TransientList<T, P, N, G, D, A>
 {
-    type Output = GenericList<T, P, N, G, D>;
-
-    /// Concatenate two lists
-    fn add(self, other: Self) -> Self::Output {
-        self.clone().append(other.clone())
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.0 .0.transient_extend(iter)
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> std::iter::Sum
-    for GenericList<T, P, N, G, D>
-{
-    fn sum<I>(it: I) -> Self
-    where
-        I: Iterator<Item = Self>,
-    {
-        it.fold(Self::new(), |a, b| a + b)
-    }
-}
+/// The nodes of a list that is being dropped, which its [`DropHandler`] takes ownership of in
+/// [`drop_nodes`](DropHandler::drop_nodes). Dropping this frees the nodes that nothing else
+/// refers to anymore.
+pub struct DroppedList<L: DropNodes>(L::Nodes);
 
 impl<
-        T: Clone + std::hash::Hash,
+        T,
         P: PointerFamily,
         const N: u32,
         const G: u32,
-        D: DropHandler<Self>,
-    > std::hash::Hash for GenericList<T, P, N, G, D>
-{
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        for i in self {
-            i.hash(state)
-        }
-    }
-}
-
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>>
-    std::ops::Index<usize> for GenericList<T, P, N, G, D>
-{
-    type Output = T;
-    /// Get a reference to the value at index `index` in the vector.
-    ///
-    /// Time: O(log n)
-    fn index(&self, index: usize) -> &Self::Output {
-        match self.get(index) {
-            Some(value) => value,
-            None => panic!(
-                "{}::index: index out of bounds: {} < {}",
-                stringify!($list),
-                index,
-                self.len()
-            ),
-        }
-    }
-}
-
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, D: DropHandler<Self>> Drop
-    for GenericList<T, P, N, G, D>
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > DroppedList<GenericList<T, P, N, G, D, A>>
 {
-    fn drop(&mut self) {
-        D::drop_handler(self)
+    /// Returns `true` if nothing else refers to the first node, so dropping this frees it,
+    /// along with the nodes after it that aren't shared either.
+    pub fn is_unique(&self) -> bool {
+        self.0.strong_count() == 1
     }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use std::ops::Add;
+    use core::ops::Add;
 
     use super::*;
+    use crate::test_alloc::{CountingAllocator, LimitedAllocator};
     use crate::{list, vlist};
 
     #[test]
     fn strong_count_empty() {
         let list: List<usize> = List::new();
-        assert!(list.strong_count() >= 1);
+        assert_eq!(list.strong_count(), 1);
+        let list: SharedList<usize> = SharedList::new();
+        assert_eq!(list.strong_count(), 1);
+    }
+
+    #[test]
+    fn empty_lists_own_their_node() {
+        let empty = List::<usize>::new();
+        assert!(!empty.ptr_eq(&List::default()));
+
+        // Emptying a list leaves a node of its own behind, which is edited in place
+        let mut list = list![1];
+        assert!(list.cdr_mut().is_none());
+        assert_eq!(list.strong_count(), 1);
+        list.push_front(2);
+        assert_eq!(list, list![2]);
+        assert!(empty.is_empty());
+
+        // Elements aligned to more than the shared empty buffer get a buffer of their own
+        #[repr(align(128))]
+        #[derive(Clone, PartialEq, Debug)]
+        struct Aligned(u8);
+        let mut aligned = List::<Aligned>::new();
+        aligned.push_front(Aligned(1));
+        assert_eq!(aligned.first(), Some(&Aligned(1)));
     }
 
     #[test]
@@ -1204,7 +2266,7 @@ mod tests {
         let slice: &[usize] = &[0, 1, 2, 3, 4, 5];
         let list: VList<usize> = vlist![0, 1, 2, 3, 4, 5];
 
-        assert_eq!(list, slice.into());
+        assert_eq!(list, VList::from(slice));
     }
 
     #[test]
@@ -1266,6 +2328,60 @@ mod tests {
         assert_eq!("[1, 2, 3, 4, 5]", format!("{:?}", list));
     }
 
+    #[test]
+    fn from_vector() {
+        let vector: Vector<usize> = crate::vector![1, 2, 3];
+        let data = vector.as_ptr();
+
+        let list: List<usize> = vector.into();
+        assert_eq!(list, list![1, 2, 3]);
+
+        // Fits in a single node, so the buffer is reused
+        assert_eq!(list.node_count(), 1);
+        assert_eq!(list.0 .0.elements.as_ptr(), data);
+    }
+
+    #[test]
+    fn from_vector_multiple_nodes() {
+        let mut vector = Vector::new();
+        vector.extend(0..1000usize);
+
+        let list: List<usize> = vector.into();
+        assert!(list.iter().copied().eq(0..1000));
+        assert!(list.0.assert_invariants());
+
+        let mut vector = Vector::new();
+        vector.extend(0..10usize);
+
+        let list: VList<usize> = vector.into();
+        assert!(list.iter().copied().eq(0..10));
+        assert!(list.0.assert_invariants());
+    }
+
+    #[test]
+    fn from_shared_vector() {
+        let vector: AtomicSharedVector<usize> = crate::arc_vector![1, 2, 3];
+        let copy = vector.clone();
+
+        let list: List<usize> = vector.into();
+        assert_eq!(list, list![1, 2, 3]);
+        assert_eq!(copy.as_slice(), &[1, 2, 3]);
+
+        let vector: SharedVector<usize> = crate::rc_vector![4, 5, 6];
+        let list: List<usize> = vector.into();
+        assert_eq!(list, list![4, 5, 6]);
+    }
+
+    #[test]
+    fn into_vector() {
+        let list = list![1usize, 2, 3, 4];
+        let rest = list.cdr().unwrap();
+
+        assert_eq!(Vector::from(list).as_slice(), &[1, 2, 3, 4]);
+        assert_eq!(SharedVector::from(rest.clone()).as_slice(), &[2, 3, 4]);
+        assert_eq!(AtomicSharedVector::from(rest).as_slice(), &[2, 3, 4]);
+    }
+
     #[test]
     fn cons_grows_as_expected() {
         let list = vlist![1, 2];
@@ -1275,12 +2391,553 @@ mod tests {
         assert_eq!(vlist![0, 1, 2], list);
         assert_eq!(2, list.0.node_iter().count());
     }
+
+    type CountingVList<T> = GenericList<T, RcPointer, 2, 2, DefaultDropHandler, CountingAllocator>;
+
+    #[test]
+    fn new_in_allocator() {
+        let allocator = CountingAllocator::default();
+        let uses_allocator = |list: &CountingVList<usize>| list.allocator().ptr_eq(&allocator);
+
+        let mut list = CountingVList::new_in(allocator.clone());
+        for i in (0..100).rev() {
+            list.cons_mut(i);
+        }
+
+        let other = CountingVList::cons(0, list.take(10));
+        let mut appended = other.clone().append(list.clone());
+        appended.extend(0..10);
+        appended.sort();
+        let reversed = appended.clone().reverse();
+
+        assert!(list.iter().copied().eq(0..100));
+        for list in [&list, &other, &appended, &reversed] {
+            assert!(list.nodes().iter().all(uses_allocator));
+        }
+        assert!(uses_allocator(&list.tail(100).unwrap()));
+        assert!(allocator.live() > 0);
+
+        let vector = Vector::from(other);
+        assert!(vector.allocator().ptr_eq(&allocator));
+
+        drop((list, appended, reversed, vector));
+        assert_eq!(allocator.live(), 0);
+    }
+
+    #[test]
+    fn nodes_are_allocated_in_allocator() {
+        let allocator = CountingAllocator::default();
+        let mut list = CountingVList::new_in(allocator.clone());
+        list.extend(0..100);
+
+        // Every node takes one allocation for itself and one for its element buffer
+        assert_eq!(allocator.live(), 2 * list.node_count());
+
+        // Copying the shared front node on write allocates from the same allocator
+        let before = allocator.live();
+        let copy = list.clone();
+        list.push_front(0);
+        assert_eq!(allocator.live(), before + 2);
+
+        drop((list, copy));
+        assert_eq!(allocator.live(), 0);
+    }
+
+    type LimitedVList<T> = GenericList<T, RcPointer, 2, 2, DefaultDropHandler, LimitedAllocator>;
+
+    #[test]
+    fn try_operations_report_allocation_failure() {
+        let allocator = LimitedAllocator::default();
+        allocator.set_budget(usize::MAX);
+
+        let mut list = LimitedVList::try_from_iter_in(0..20usize, allocator.clone()).unwrap();
+        let shared = list.clone();
+        allocator.set_budget(0);
+
+        assert!(LimitedVList::<usize>::try_new_in(allocator.clone()).is_err());
+        assert!(LimitedVList::try_from_iter_in(0..5, allocator.clone()).is_err());
+        assert!(LimitedVList::try_cons(0, list.clone()).is_err());
+        assert!(list.try_take(3).is_err());
+        assert!(list.clone().try_append(shared.clone()).is_err());
+        assert!(list.clone().try_reverse().is_err());
+
+        assert!(list.try_cons_mut(100).is_err());
+        assert!(list.try_push_back(100).is_err());
+        assert!(list.try_append_mut(shared.clone()).is_err());
+        assert!(list.try_sort_by(|l, r| r.cmp(l)).is_err());
+        assert!(list.iter().copied().eq(0..20));
+        assert_eq!(list, shared);
+
+        allocator.set_budget(usize::MAX);
+        list.try_sort_by(|l, r| r.cmp(l)).unwrap();
+        list.try_push_back(100).unwrap();
+        assert!(list.iter().copied().eq((0..20).rev().chain(Some(100))));
+        assert!(shared.iter().copied().eq(0..20));
+    }
+
+    #[test]
+    fn try_operations_report_node_allocation_failure() {
+        type Attempt = fn(&mut LimitedVList<usize>) -> bool;
+        let attempts: [Attempt; 9] = [
+            |list| LimitedVList::try_cons(0, list.clone()).is_ok(),
+            |list| list.try_take(15).is_ok(),
+            |list| list.clone().try_append(list.clone()).is_ok(),
+            |list| list.clone().try_reverse().is_ok(),
+            |list| list.try_cons_mut(100).is_ok(),
+            |list| list.try_push_back(100).is_ok(),
+            |list| list.try_sort_by(|l, r| r.cmp(l)).is_ok(),
+            |list| LimitedVList::try_from_iter_in(0..50, list.allocator().clone()).is_ok(),
+            |list| list.try_rechunk::<3, 1>().is_ok(),
+        ];
+
+        // Run out of memory at every point of each operation, including after the buffer
+        // of a node has been allocated but not the node itself
+        let allocator = LimitedAllocator::default();
+        for attempt in attempts {
+            for budget in 0..32 {
+                allocator.set_budget(usize::MAX);
+                let mut list = LimitedVList::try_from_iter_in(0..20, allocator.clone()).unwrap();
+                let shared = list.clone();
+
+                allocator.set_budget(budget);
+                if !attempt(&mut list) {
+                    assert_eq!(list, shared);
+                }
+            }
+        }
+
+        allocator.set_budget(1);
+        assert!(LimitedVList::try_from_iter_in(0..1, allocator.clone()).is_err());
+    }
+
+    #[test]
+    fn sort_by_panic_leaves_empty_list() {
+        let mut list = vlist![4, 2, 6, 3, 1, 5, 8, 7];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            list.sort_by(|_, _| panic!("comparison failed"))
+        }));
+
+        assert!(result.is_err());
+        assert!(list.is_empty());
+        list.cons_mut(1);
+        assert_eq!(list, vlist![1]);
+    }
+
+    /// Deliberately not `Clone`.
+    #[derive(Debug, PartialEq)]
+    struct Handle(usize);
+
+    #[test]
+    fn non_clone_elements() {
+        let mut list: VList<Handle> = (1..10).map(Handle).collect();
+        list.cons_mut_no_clone(Handle(0));
+
+        assert_eq!(list.len(), 10);
+        assert_eq!(list.first(), Some(&Handle(0)));
+        assert_eq!(list.get(5), Some(&Handle(5)));
+        assert!(list.iter().map(|h| h.0).eq(0..10));
+
+        let rest = list.cdr().unwrap();
+        assert!(rest.iter().map(|h| h.0).eq(1..10));
+        drop(rest);
+
+        assert!(list.draining_iterator().map(|h| h.0).eq(0..10));
+    }
+
+    #[test]
+    fn cons_mut_no_clone_on_shared_list() {
+        let mut list: List<Handle> = List::new();
+        for i in (0..3).rev() {
+            list.cons_mut_no_clone(Handle(i));
+        }
+        assert_eq!(list.node_count(), 1);
+
+        let shared = list.clone();
+        list.cons_mut_no_clone(Handle(100));
+
+        assert_eq!(list.node_count(), 2);
+        assert!(list.iter().map(|h| h.0).eq([100, 0, 1, 2]));
+        assert!(shared.iter().map(|h| h.0).eq(0..3));
+
+        // Only the node this list owns outright can be drained
+        assert!(list.draining_iterator().map(|h| h.0).eq([100]));
+        assert!(shared.draining_iterator().map(|h| h.0).eq(0..3));
+    }
+
+    #[test]
+    fn convert_shares_element_buffers() {
+        let list: List<usize> = (0..1000).collect();
+        let rest = list.cdr().unwrap();
+        let shared: SharedList<usize> = rest.convert();
+
+        assert!(shared.iter().copied().eq(1..1000));
+        assert_eq!(shared.node_count(), rest.node_count());
+        assert_eq!(shared.elements_as_ptr_usize(), rest.elements_as_ptr_usize());
+
+        let back: List<usize> = shared.convert();
+        assert_eq!(back, rest);
+        assert!(List::<usize>::new().convert::<ArcPointer>().is_empty());
+    }
+
+    #[test]
+    fn rechunk_shares_buffers_that_fit() {
+        let list: List<usize> = (0..1000).collect();
+        let vlist: VList<usize> = list.rechunk();
+        assert_eq!(vlist.node_count(), list.node_count());
+        assert_eq!(vlist.elements_as_ptr_usize(), list.elements_as_ptr_usize());
+
+        let small: GenericList<usize, RcPointer, 1024> = list.rechunk();
+        assert_eq!(small.node_count(), list.node_count());
+        assert!(small.iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn rechunk_splits_buffers_that_dont_fit() {
+        let list: List<usize> = (0..1000).collect();
+        let rest = list.cdr().unwrap();
+        let chunked: GenericList<usize, RcPointer, 100> = rest.rechunk();
+
+        assert!(chunked.iter().copied().eq(1..1000));
+        assert!(chunked
+            .nodes()
+            .iter()
+            .all(|node| node.elements_length() <= 100));
+        assert_eq!(chunked.try_rechunk::<256, 1>().unwrap(), rest);
+
+        let mut chunked = chunked;
+        chunked.cons_mut(0);
+        assert!(chunked.iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn compare_across_configurations() {
+        let list: List<usize> = (0..1000).collect();
+        let shared: SharedList<usize> = (0..1000).collect();
+        let vlist: SharedVList<usize> = (0..1000).collect();
+
+        assert_eq!(list, shared);
+        assert_eq!(vlist, list);
+        assert_eq!(vlist.cdr().unwrap(), list.cdr().unwrap());
+        assert_ne!(vlist.cdr().unwrap(), list);
+        assert_ne!(vlist, list.take(999));
+
+        assert!(list.take(999) < vlist);
+        assert!(list.cdr().unwrap() > vlist);
+        assert_eq!(list.partial_cmp(&vlist), Some(Ordering::Equal));
+        assert_eq!(
+            vlist![1.0, f64::NAN].partial_cmp(&list![1.0, f64::NAN]),
+            None
+        );
+    }
+
+    #[test]
+    fn compare_against_sequences() {
+        let list: VList<usize> = (0..100).collect();
+        let vec: Vec<usize> = (0..100).collect();
+        let mut deque: VecDeque<usize> = (50..100).collect();
+        deque.extend(0..50);
+        deque.rotate_left(50);
+
+        assert_eq!(list, vec);
+        assert_eq!(vec, list);
+        assert_eq!(list, vec.as_slice());
+        assert_eq!(*vec.as_slice(), list);
+        assert_eq!(list, deque);
+        assert_eq!(deque, list);
+        assert_eq!(list![1, 2, 3], [1, 2, 3]);
+        assert_eq!([1, 2, 3], list![1, 2, 3]);
+        assert_ne!(list, vec[1..]);
+        assert_ne!(list, vec[..99]);
+
+        assert!(list < vec![0, 2]);
+        assert!(list > [0, 1, 2]);
+        assert!(list.cdr().unwrap() > deque);
+        assert_eq!(list.partial_cmp(&deque), Some(Ordering::Equal));
+    }
+
+    // Counts how many times elements are compared
+    #[derive(Clone)]
+    struct Counted<'a>(usize, &'a core::cell::Cell<usize>);
+
+    impl PartialEq for Counted<'_> {
+        fn eq(&self, other: &Self) -> bool {
+            self.1.set(self.1.get() + 1);
+            self.0 == other.0
+        }
+    }
+
+    impl PartialOrd for Counted<'_> {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            self.1.set(self.1.get() + 1);
+            self.0.partial_cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn compare_shared_tails() {
+        let count = core::cell::Cell::new(0);
+        let tail: List<Counted> = (0..10000).map(|i| Counted(i, &count)).collect();
+        let left = List::cons(Counted(1, &count), tail.clone());
+        let right = List::cons(Counted(1, &count), tail.clone());
+
+        assert!(left == right);
+        assert!(count.get() <= 256);
+
+        count.set(0);
+        let right = List::cons(Counted(2, &count), tail.clone());
+        assert!(left < right);
+        assert_eq!(count.get(), 1);
+
+        count.set(0);
+        let shared: SharedVList<Counted> = tail.cdr().unwrap().convert().rechunk();
+        assert!(shared == tail.cdr().unwrap());
+        assert!(shared.partial_cmp(&tail) == Some(Ordering::Greater));
+        assert_eq!(count.get(), 1);
+    }
+
+    #[test]
+    fn hash_matches_across_chunkings() {
+        use std::hash::{BuildHasher, RandomState};
+
+        let state = RandomState::new();
+        let list: List<usize> = (0..1000).collect();
+        let vlist: VList<usize> = List::cons(0, list.cdr().unwrap()).rechunk();
+
+        assert_eq!(state.hash_one(&list), state.hash_one(&vlist));
+        assert_ne!(state.hash_one(&list), state.hash_one(list.cdr().unwrap()));
+    }
+
+    #[test]
+    fn weak_lists() {
+        let list: SharedList<usize> = (0..1000).collect();
+        let weak = list.downgrade();
+        let rest = list.cdr().unwrap();
+
+        let upgraded = std::thread::spawn({
+            let weak = weak.clone();
+            move || weak.upgrade().map(|list| list.len())
+        })
+        .join()
+        .unwrap();
+        assert_eq!(upgraded, Some(1000));
+        assert!(weak.upgrade().unwrap().ptr_eq(&list));
+
+        drop(list);
+        assert!(weak.upgrade().is_none());
+        assert!(rest.iter().copied().eq(1..1000));
+
+        let weak = List::<usize>::new().downgrade();
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn borrowed_elements() {
+        let words = String::from("the quick brown fox");
+        let list: VList<&str> = words.split(' ').collect();
+        let shared: SharedList<&str> = list.iter().copied().collect();
+
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.last(), Some(&"fox"));
+        assert!(list.iter().eq(shared.iter()));
+        assert_eq!(VList::cons("a", list.cdr().unwrap()).first(), Some(&"a"));
+    }
+
+    // Only needs to compile, lists have to be covariant in their element type
+    #[allow(dead_code)]
+    fn covariance<'a>(
+        list: List<&'static str>,
+        shared: SharedVList<&'static str>,
+    ) -> (List<&'a str>, SharedVList<&'a str>) {
+        (list, shared)
+    }
+
+    #[test]
+    fn cons_after_cdr_drops_stale_elements() {
+        let list = list![1, 2, 3];
+        let list = List::cons(0, list.cdr().unwrap());
+
+        assert_eq!(list, list![0, 2, 3]);
+    }
+
+    #[test]
+    fn trace_visits_shared_buffers_once() {
+        use crate::trace::{BufferId, NodeId};
+        use std::collections::{HashMap, HashSet};
+
+        #[derive(Default)]
+        struct Collect {
+            nodes: HashSet<NodeId>,
+            buffers: HashMap<BufferId, usize>,
+            elements: Vec<usize>,
+        }
+
+        impl Tracer<usize> for Collect {
+            fn visit_node(&mut self, node: NodeId) -> bool {
+                self.nodes.insert(node)
+            }
+
+            fn visit_buffer(&mut self, buffer: BufferId, len: usize) -> usize {
+                let seen = self.buffers.entry(buffer).or_default();
+                core::mem::replace(seen, len.max(*seen))
+            }
+
+            fn visit_element(&mut self, element: &usize) {
+                self.elements.push(*element);
+            }
+        }
+
+        // Both nodes hold the same buffer, `rest` only the back of it
+        let list: List<usize> = (0..10).collect();
+        let rest = list.cdr().unwrap().cdr().unwrap();
+        assert!(!list.ptr_eq(&rest));
+
+        let mut collect = Collect::default();
+        rest.trace(&mut collect);
+        list.trace(&mut collect);
+        assert_eq!(collect.nodes.len(), 2);
+        assert_eq!(collect.elements, [2, 3, 4, 5, 6, 7, 8, 9, 0, 1]);
+
+        let mut collect = Collect::default();
+        list.trace(&mut collect);
+        rest.trace(&mut collect);
+        assert_eq!(collect.elements, (0..10).collect::<Vec<_>>());
+
+        // Popped off elements aren't visited, even though the buffer still holds them
+        drop(list);
+        let mut collect = Collect::default();
+        rest.trace(&mut collect);
+        assert_eq!(collect.elements, (2..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn transient_edits_copy_once() {
+        let list: List<usize> = (10..20).collect();
+        let other = list.clone();
+
+        let mut transient = list.transient();
+        transient.push_front(9);
+        let head = transient.0.as_ptr_usize();
+        assert_ne!(head, list.as_ptr_usize());
+
+        for i in (0..9).rev() {
+            transient.push_front(i);
+            assert_eq!(transient.0.as_ptr_usize(), head);
+        }
+        assert_eq!(transient.pop_front(), Some(0));
+        transient.extend(20..25);
+        assert_eq!(transient.len(), 24);
+        assert_eq!(transient.first(), Some(&1));
+
+        let edited = transient.persistent();
+        assert!(edited.iter().copied().eq(1..25));
+        assert!(list.iter().copied().eq(10..20));
+        assert!(other.ptr_eq(&list));
+    }
+
+    #[test]
+    fn transient_of_vlist() {
+        let list: VList<usize> = (0..100).collect();
+        let mut transient = list.transient();
+        for i in 100..200 {
+            transient.push_back(i);
+        }
+        transient.append(list.clone());
+
+        let edited = transient.persistent();
+        assert!(edited.iter().copied().eq((0..200).chain(0..100)));
+        assert!(list.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn transient_copies_shared_nodes_once_per_batch() {
+        type CountingList<T> =
+            GenericList<T, RcPointer, 4, 1, DefaultDropHandler, CountingAllocator>;
+
+        let allocator = CountingAllocator::default();
+        let mut list = CountingList::new_in(allocator.clone());
+        list.extend(0..12);
+        let shared = list.clone();
+        let start = allocator.live();
+
+        // The three nodes on the way to the back are copied, and a node and buffer added
+        let mut transient = list.transient();
+        transient.push_back(12);
+        assert_eq!(allocator.live(), start + 5);
+
+        // Later edits change the copies in place, and the buffers they share are copied once
+        transient.push_back(13);
+        transient.push_back(14);
+        assert_eq!(allocator.live(), start + 5);
+        assert_eq!(transient.pop_front(), Some(0));
+        assert_eq!(allocator.live(), start + 6);
+        assert_eq!(transient.pop_front(), Some(1));
+        transient.push_front(1);
+        transient.push_back(15);
+        assert_eq!(allocator.live(), start + 6);
+
+        // A new batch copies the nodes shared with the result of the last one again
+        let edited = transient.persistent();
+        let copy = edited.clone();
+        let mut transient = edited.transient();
+        transient.push_back(16);
+        let copied = allocator.live();
+        assert!(copied > start + 6);
+        transient.push_back(17);
+        assert_eq!(allocator.live(), copied);
+        transient.push_front(0);
+        assert_eq!(allocator.live(), copied + 1);
+
+        assert!(transient.persistent().iter().copied().eq(0..18));
+        assert!(copy.iter().copied().eq(1..16));
+        assert!(shared.iter().copied().eq(0..12));
+    }
+
+    #[test]
+    fn transient_push_back_copies_once() {
+        let list: List<usize> = (0..1000).collect();
+        let mut transient = list.transient();
+
+        transient.push_back(1000);
+        let nodes: Vec<usize> = transient
+            .0
+             .0
+            .node_iter()
+            .map(|node| node.as_ptr_usize())
+            .collect();
+        assert!(!nodes.contains(&list.as_ptr_usize()));
+
+        transient.push_back(1001);
+        transient.append((1002..1010).collect());
+        transient.push_back(1010);
+        assert!(transient
+            .0
+             .0
+            .node_iter()
+            .map(|node| node.as_ptr_usize())
+            .take(nodes.len())
+            .eq(nodes.iter().copied()));
+
+        // Once the batch is over, its nodes are copied again before being changed
+        let edited = transient.persistent();
+        let copy = edited.clone();
+        let mut again = edited.transient();
+        again.push_front(1);
+        again.push_back(1011);
+        assert_ne!(again.0.as_ptr_usize(), copy.as_ptr_usize());
+        assert!(copy.iter().copied().eq(0..1011));
+        assert!(again
+            .persistent()
+            .iter()
+            .copied()
+            .eq([1].into_iter().chain(0..1012)));
+        assert!(list.iter().copied().eq(0..1000));
+    }
 }
 
 #[cfg(test)]
 mod arc_tests {
 
-    use std::ops::Add;
+    use core::ops::Add;
 
     use super::*;
     use crate::{shared_list, shared_vlist, vlist};
@@ -1563,7 +3220,7 @@ mod arc_tests {
         let slice: &[usize] = &[0, 1, 2, 3, 4, 5];
         let list: SharedVList<usize> = shared_vlist![0, 1, 2, 3, 4, 5];
 
-        assert_eq!(list, slice.into());
+        assert_eq!(list, SharedVList::from(slice));
     }
 
     #[test]
@@ -1671,7 +3328,91 @@ mod arc_tests {
         let pointer = list.as_ptr();
 
         // Create value from pointer
-        let value = unsafe { SharedVList::from_raw(pointer) };
-        std::mem::forget(value);
+        let value = unsafe { SharedVList::from_raw_cell(pointer) };
+        core::mem::forget(value);
+    }
+
+    #[test]
+    fn raw_pointers_round_trip_through_tagged_values() {
+        const TAG: u64 = 0b111;
+
+        fn tag<T>(list: List<T>) -> u64 {
+            let pointer = List::into_raw(list) as u64;
+            assert_eq!(pointer & TAG, 0);
+            pointer | TAG
+        }
+
+        unsafe fn untag<T>(value: u64) -> List<T> {
+            assert_eq!(value & TAG, TAG);
+            List::from_raw((value & !TAG) as *const ())
+        }
+
+        let values: Vec<u64> = (0..100)
+            .map(|i| tag((0..i).collect::<List<usize>>()))
+            .collect();
+
+        for (i, value) in values.into_iter().enumerate() {
+            let list = unsafe { untag::<usize>(value) };
+            assert!(list.iter().copied().eq(0..i));
+            assert_eq!(list.strong_count(), 1);
+        }
+
+        // Zero sized elements and tiny nodes are aligned all the same
+        let list: GenericList<(), RcPointer, 1> = core::iter::repeat_n((), 5).collect();
+        let pointer = GenericList::into_raw(list);
+        assert_eq!(
+            pointer as usize % GenericList::<(), RcPointer, 1>::RAW_ALIGN,
+            0
+        );
+        assert_eq!(pointer as usize % 8, 0);
+        let list = unsafe { GenericList::<(), RcPointer, 1>::from_raw(pointer) };
+        assert_eq!(list.len(), 5);
+    }
+
+    #[test]
+    fn raw_strong_counts() {
+        let list: SharedList<usize> = (0..1000).collect();
+        let other = list.clone();
+        let value = SharedList::into_raw(list) as u64;
+
+        unsafe {
+            SharedList::<usize>::increment_strong_count(value as *const ());
+            SharedList::<usize>::increment_strong_count(value as *const ());
+        }
+        assert_eq!(other.strong_count(), 4);
+
+        // Each reference can be taken back on another thread
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                std::thread::spawn(move || {
+                    let list = unsafe { SharedList::<usize>::from_raw(value as *const ()) };
+                    list.iter().sum::<usize>()
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), (0..1000).sum::<usize>());
+        }
+        assert_eq!(other.strong_count(), 2);
+
+        unsafe { SharedList::<usize>::decrement_strong_count(value as *const ()) };
+        assert_eq!(other.strong_count(), 1);
+
+        // Releasing the last reference drops the elements
+        struct Dropped<'a>(&'a core::cell::Cell<usize>);
+
+        impl Drop for Dropped<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let dropped = core::cell::Cell::new(0);
+        let list: List<Dropped> = (0..10).map(|_| Dropped(&dropped)).collect();
+        let pointer = List::into_raw(list);
+        assert_eq!(dropped.get(), 0);
+        unsafe { List::<Dropped>::decrement_strong_count(pointer) };
+        assert_eq!(dropped.get(), 10);
     }
 }
@@ -0,0 +1,195 @@
+//! Concurrency model tests for the atomically reference counted storage.
+//!
+//! These run every interleaving (and every permitted reordering of the weaker memory
+//! orderings) of small clone / drop / copy-on-write scenarios through [loom], which
+//! reports data races on the elements, leaks and double frees. They are deterministic
+//! and don't need anything beyond the crate's own dependencies, but loom has to be
+//! compiled in, so they only exist when the crate is built with `--cfg loom`:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg loom" cargo test --release --lib loom_tests
+//! ```
+
+use loom::cell::UnsafeCell;
+use loom::sync::Arc;
+use loom::thread;
+
+use crate::list::SharedList;
+use crate::shared_vector::AtomicSharedVector;
+
+/// An element whose reads and writes are tracked by loom, so that an in-place mutation
+/// which isn't ordered after another thread's last read of the buffer is reported as a
+/// data race. The `Arc` lets loom catch elements that are leaked or dropped twice.
+struct Tracked(Arc<UnsafeCell<u32>>);
+
+// Safety: every access to the cell goes through loom, which checks that they are
+// properly synchronized.
+unsafe impl Send for Tracked {}
+unsafe impl Sync for Tracked {}
+
+impl Tracked {
+    fn new(value: u32) -> Self {
+        Tracked(Arc::new(UnsafeCell::new(value)))
+    }
+
+    fn get(&self) -> u32 {
+        self.0.with(|value| unsafe { *value })
+    }
+
+    fn set(&mut self, value: u32) {
+        self.0.with_mut(|slot| unsafe { *slot = value })
+    }
+}
+
+// Copy-on-write must never share the underlying cell between the copies.
+impl Clone for Tracked {
+    fn clone(&self) -> Self {
+        Tracked::new(self.get())
+    }
+}
+
+fn tracked_vector(values: &[u32]) -> AtomicSharedVector<Tracked> {
+    let mut vector = AtomicSharedVector::with_capacity(values.len());
+    vector.extend(values.iter().copied().map(Tracked::new));
+    vector
+}
+
+fn tracked_list(values: &[u32]) -> SharedList<Tracked> {
+    values.iter().copied().map(Tracked::new).collect()
+}
+
+fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(3);
+    builder.check(f);
+}
+
+#[test]
+fn vector_clone_and_drop() {
+    model(|| {
+        let vector = tracked_vector(&[1, 2]);
+        let other = vector.clone();
+
+        let handle = thread::spawn(move || {
+            let another = other.clone();
+            assert_eq!(another[0].get(), 1);
+            drop(other);
+            drop(another);
+        });
+
+        assert_eq!(vector[1].get(), 2);
+        drop(vector);
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn vector_ensure_unique_races_with_drop() {
+    model(|| {
+        let mut vector = tracked_vector(&[1, 2]);
+        let other = vector.clone();
+
+        let handle = thread::spawn(move || {
+            assert_eq!(other[0].get(), 1);
+            drop(other);
+        });
+
+        // Depending on whether the other reference is gone yet, this either copies the
+        // buffer or writes to it in place. In the latter case the write has to be
+        // ordered after the other thread's read.
+        vector.ensure_unique();
+        vector.as_mut_slice()[0].set(10);
+        assert_eq!(vector[0].get(), 10);
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn vector_push_on_both_sides() {
+    model(|| {
+        let mut vector = tracked_vector(&[1]);
+        let mut other = vector.clone();
+
+        let handle = thread::spawn(move || {
+            other.push(Tracked::new(3));
+            other.iter().map(Tracked::get).collect::<Vec<_>>()
+        });
+
+        vector.push(Tracked::new(2));
+
+        assert_eq!(
+            vector.iter().map(Tracked::get).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(handle.join().unwrap(), vec![1, 3]);
+    });
+}
+
+#[test]
+fn list_clone_and_drop() {
+    model(|| {
+        let list = tracked_list(&[1, 2, 3]);
+        let other = list.clone();
+
+        let handle = thread::spawn(move || {
+            let rest = other.cdr().unwrap();
+            assert_eq!(rest.first().map(Tracked::get), Some(2));
+            drop(other);
+            drop(rest);
+        });
+
+        assert_eq!(list.first().map(Tracked::get), Some(1));
+        drop(list);
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn list_pop_front_races_with_drop() {
+    model(|| {
+        let mut list = tracked_list(&[1, 2, 3]);
+        let other = list.clone();
+
+        let handle = thread::spawn(move || {
+            // The rest shares the element buffer without sharing the cell.
+            let rest = other.cdr().unwrap();
+            drop(other);
+            assert_eq!(rest.first().map(Tracked::get), Some(2));
+            assert_eq!(rest.get(1).map(Tracked::get), Some(3));
+        });
+
+        // The popped value is moved straight out of the buffer if both the cell and the
+        // buffer look unique, so writing to it must not race with the reads above.
+        let mut first = list.pop_front().unwrap();
+        first.set(10);
+
+        let mut second = list.pop_front().unwrap();
+        second.set(20);
+
+        handle.join().unwrap();
+    });
+}
+
+#[test]
+fn list_cons_mut_on_both_sides() {
+    model(|| {
+        let mut list = tracked_list(&[1, 2]);
+        let mut other = list.clone();
+
+        let handle = thread::spawn(move || {
+            other.cons_mut(Tracked::new(4));
+            other.iter().map(Tracked::get).collect::<Vec<_>>()
+        });
+
+        list.cons_mut(Tracked::new(3));
+
+        assert_eq!(
+            list.iter().map(Tracked::get).collect::<Vec<_>>(),
+            vec![3, 1, 2]
+        );
+        assert_eq!(handle.join().unwrap(), vec![4, 1, 2]);
+    });
+}
@@ -1,110 +1,152 @@
-use std::{ops::Deref, rc::Rc, sync::Arc};
-
-pub trait PointerFamily: 'static {
-    type Pointer<T: 'static>: Deref<Target = T>;
+use core::ops::Deref;
+
+use crate::shared_vector::alloc::{AllocError, Allocator, Global};
+
+mod rc;
+
+pub use rc::{Arc, ArcWeak, Rc, RcWeak};
+
+/// A family of reference counted smart pointers that the nodes of a list are stored in.
+///
+/// # Safety
+///
+/// Lists store their nodes as the raw pointers returned by `into_raw`, so implementations must
+/// uphold the following:
+///
+/// - `into_raw` returns the same, non null, address that the pointer dereferences to, and
+///   `from_raw` turns that address back into the pointer it came from.
+/// - `Pointer<T>` is covariant in `T`, so that a `Pointer<T>` can be treated as a `Pointer<U>`
+///   whenever `T` is a subtype of `U`. This lets lists be covariant in their element type.
+pub unsafe trait PointerFamily: 'static {
+    type Pointer<T>: Deref<Target = T>;
+    /// A pointer that doesn't keep its value alive, made with `downgrade`.
+    type Weak<T>: Clone;
 
     fn new<T>(value: T) -> Self::Pointer<T>;
+    /// Like `new`, but allocates from `allocator` where the family supports it, and reports
+    /// allocation failure instead of aborting. By default this falls back to `new`.
+    ///
+    /// # Safety
+    ///
+    /// `value` has to own a clone of `allocator`, so that the pointer can be sent or shared
+    /// wherever `value` can and the allocator can go along with it.
+    unsafe fn try_new_in<T, A: Allocator + Clone + 'static>(
+        value: T,
+        allocator: &A,
+    ) -> Result<Self::Pointer<T>, AllocError> {
+        let _ = allocator;
+        Ok(Self::new(value))
+    }
+    /// Like `try_new_in`, for a value whose drop does nothing but give memory back to
+    /// `allocator` and drop pointers of this family. A family that frees everything it and
+    /// the allocator handed out at once, like [`ArenaPointer`](crate::arena::ArenaPointer),
+    /// may then never drop the value. By default this falls back to `try_new_in`.
+    ///
+    /// # Safety
+    ///
+    /// The same as for `try_new_in`.
+    unsafe fn try_new_forgettable_in<T, A: Allocator + Clone + 'static>(
+        value: T,
+        allocator: &A,
+    ) -> Result<Self::Pointer<T>, AllocError> {
+        Self::try_new_in(value, allocator)
+    }
     fn strong_count<T>(this: &Self::Pointer<T>) -> usize;
     fn try_unwrap<T>(this: Self::Pointer<T>) -> Option<T>;
     fn get_mut<T>(this: &mut Self::Pointer<T>) -> Option<&mut T>;
     fn ptr_eq<T>(this: &Self::Pointer<T>, other: &Self::Pointer<T>) -> bool;
     fn make_mut<T: Clone>(ptr: &mut Self::Pointer<T>) -> &mut T;
+    /// Like `make_mut`, but reports allocation failure instead of aborting. By default this
+    /// falls back to `make_mut`.
+    fn try_make_mut<T: Clone>(ptr: &mut Self::Pointer<T>) -> Result<&mut T, AllocError> {
+        Ok(Self::make_mut(ptr))
+    }
     fn clone<T>(ptr: &Self::Pointer<T>) -> Self::Pointer<T>;
     fn as_ptr<T>(this: &Self::Pointer<T>) -> *const T;
     fn into_raw<T>(this: Self::Pointer<T>) -> *const T;
     /// # Safety
     /// This must be called from a pointer as returned from into_raw
     unsafe fn from_raw<T>(this: *const T) -> Self::Pointer<T>;
+    fn downgrade<T>(this: &Self::Pointer<T>) -> Self::Weak<T>;
+    /// Returns `None` if the value has been dropped already
+    fn upgrade<T>(weak: &Self::Weak<T>) -> Option<Self::Pointer<T>>;
 }
 
-pub struct RcPointer;
-
-impl PointerFamily for RcPointer {
-    type Pointer<T: 'static> = Rc<T>;
-
-    fn new<T: 'static>(value: T) -> Self::Pointer<T> {
-        Rc::new(value)
-    }
-
-    fn strong_count<T: 'static>(this: &Self::Pointer<T>) -> usize {
-        Rc::strong_count(this)
-    }
-
-    fn try_unwrap<T: 'static>(this: Self::Pointer<T>) -> Option<T> {
-        Rc::try_unwrap(this).ok()
-    }
-
-    fn get_mut<T: 'static>(this: &mut Self::Pointer<T>) -> Option<&mut T> {
-        Rc::get_mut(this)
-    }
-
-    fn ptr_eq<T: 'static>(this: &Self::Pointer<T>, other: &Self::Pointer<T>) -> bool {
-        Rc::ptr_eq(this, other)
-    }
-
-    fn make_mut<T: Clone + 'static>(ptr: &mut Self::Pointer<T>) -> &mut T {
-        Rc::make_mut(ptr)
-    }
-
-    fn clone<T: 'static>(ptr: &Self::Pointer<T>) -> Self::Pointer<T> {
-        Rc::clone(ptr)
-    }
-
-    fn as_ptr<T: 'static>(this: &Self::Pointer<T>) -> *const T {
-        Rc::as_ptr(this)
-    }
-
-    fn into_raw<T: 'static>(this: Self::Pointer<T>) -> *const T {
-        Rc::into_raw(this)
-    }
-
-    unsafe fn from_raw<T: 'static>(this: *const T) -> Self::Pointer<T> {
-        Rc::from_raw(this)
-    }
+// Both families are implemented the same way, on top of the pointers in `rc`
+macro_rules! pointer_family {
+    ($family:ident, $pointer:ident, $weak:ident) => {
+        unsafe impl PointerFamily for $family {
+            type Pointer<T> = $pointer<T>;
+            type Weak<T> = $weak<T>;
+
+            fn new<T>(value: T) -> Self::Pointer<T> {
+                $pointer(rc::RawRc::new_in(value, &Global))
+            }
+
+            unsafe fn try_new_in<T, A: Allocator + Clone + 'static>(
+                value: T,
+                allocator: &A,
+            ) -> Result<Self::Pointer<T>, AllocError> {
+                rc::RawRc::try_new_in(value, allocator).map($pointer)
+            }
+
+            fn strong_count<T>(this: &Self::Pointer<T>) -> usize {
+                this.0.strong_count()
+            }
+
+            fn try_unwrap<T>(this: Self::Pointer<T>) -> Option<T> {
+                this.0.try_unwrap().ok()
+            }
+
+            fn get_mut<T>(this: &mut Self::Pointer<T>) -> Option<&mut T> {
+                this.0.get_mut()
+            }
+
+            fn ptr_eq<T>(this: &Self::Pointer<T>, other: &Self::Pointer<T>) -> bool {
+                this.0.ptr_eq(&other.0)
+            }
+
+            fn make_mut<T: Clone>(ptr: &mut Self::Pointer<T>) -> &mut T {
+                ptr.0.make_mut()
+            }
+
+            fn try_make_mut<T: Clone>(ptr: &mut Self::Pointer<T>) -> Result<&mut T, AllocError> {
+                ptr.0.try_make_mut()
+            }
+
+            fn clone<T>(ptr: &Self::Pointer<T>) -> Self::Pointer<T> {
+                ptr.clone()
+            }
+
+            fn as_ptr<T>(this: &Self::Pointer<T>) -> *const T {
+                this.0.as_ptr()
+            }
+
+            fn into_raw<T>(this: Self::Pointer<T>) -> *const T {
+                this.0.into_raw()
+            }
+
+            unsafe fn from_raw<T>(this: *const T) -> Self::Pointer<T> {
+                $pointer(rc::RawRc::from_raw(this))
+            }
+
+            fn downgrade<T>(this: &Self::Pointer<T>) -> Self::Weak<T> {
+                $weak(this.0.downgrade())
+            }
+
+            fn upgrade<T>(weak: &Self::Weak<T>) -> Option<Self::Pointer<T>> {
+                weak.0.upgrade().map($pointer)
+            }
+        }
+    };
 }
 
-pub struct ArcPointer;
-
-impl PointerFamily for ArcPointer {
-    type Pointer<T: 'static> = Arc<T>;
-
-    fn new<T: 'static>(value: T) -> Self::Pointer<T> {
-        Arc::new(value)
-    }
-
-    fn strong_count<T: 'static>(this: &Self::Pointer<T>) -> usize {
-        Arc::strong_count(this)
-    }
-
-    fn try_unwrap<T: 'static>(this: Self::Pointer<T>) -> Option<T> {
-        Arc::try_unwrap(this).ok()
-    }
-
-    fn get_mut<T: 'static>(this: &mut Self::Pointer<T>) -> Option<&mut T> {
-        Arc::get_mut(this)
-    }
-
-    fn ptr_eq<T: 'static>(this: &Self::Pointer<T>, other: &Self::Pointer<T>) -> bool {
-        Arc::ptr_eq(this, other)
-    }
-
-    fn make_mut<T: Clone + 'static>(ptr: &mut Self::Pointer<T>) -> &mut T {
-        Arc::make_mut(ptr)
-    }
-
-    fn clone<T: 'static>(ptr: &Self::Pointer<T>) -> Self::Pointer<T> {
-        Arc::clone(ptr)
-    }
+/// Stores nodes in [`Rc`]s, allocated from the allocator of the list.
+pub struct RcPointer;
 
-    fn as_ptr<T: 'static>(this: &Self::Pointer<T>) -> *const T {
-        Arc::as_ptr(this)
-    }
+pointer_family!(RcPointer, Rc, RcWeak);
 
-    fn into_raw<T: 'static>(this: Self::Pointer<T>) -> *const T {
-        Arc::into_raw(this)
-    }
+/// Stores nodes in [`Arc`]s, allocated from the allocator of the list.
+pub struct ArcPointer;
 
-    unsafe fn from_raw<T: 'static>(this: *const T) -> Self::Pointer<T> {
-        Arc::from_raw(this)
-    }
-}
+pointer_family!(ArcPointer, Arc, ArcWeak);
@@ -0,0 +1,623 @@
+//! The reference counted pointers behind [`RcPointer`](super::RcPointer) and
+//! [`ArcPointer`](super::ArcPointer).
+//!
+//! They work like `Rc` and `Arc` from the standard library, except that the value is allocated
+//! from an [`Allocator`] of the caller's choosing. The allocator is moved into the same block
+//! as the value and the reference counts, so the pointer itself stays one word wide, and the
+//! block is freed with that allocator once the last strong and weak reference is gone. Which
+//! allocator that is, along with how to drop the value, is looked up through a table in the
+//! header of the block, so the pointer types don't carry the allocator type around.
+//!
+//! The standard library pointers can't stand in for these on stable Rust: `Rc::new_in`,
+//! `Arc::new_in` and their fallible `try_` versions are still behind the unstable
+//! `allocator_api` feature, so nodes could neither come from the list's allocator nor report
+//! allocation failure. Swapping them out changed the `Pointer` types of both families, which
+//! is a breaking change for code that names them.
+
+use alloc::alloc::handle_alloc_error;
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+
+#[cfg(not(loom))]
+use core::{
+    hint::spin_loop,
+    sync::atomic::{
+        fence, AtomicUsize,
+        Ordering::{Acquire, Relaxed, Release},
+    },
+};
+#[cfg(loom)]
+use loom::{
+    hint::spin_loop,
+    sync::atomic::{
+        fence, AtomicUsize,
+        Ordering::{Acquire, Relaxed, Release},
+    },
+};
+
+use crate::shared_vector::alloc::{AllocError, Allocator};
+use crate::shared_vector::refcount_overflow;
+
+// Counting past this aborts, long before the count could wrap around, like `Rc` and `Arc` do
+const MAX_COUNT: usize = isize::MAX as usize;
+
+/// The strong or weak count of a block.
+///
+/// Atomic counts have to uphold the same ordering guarantees as `Arc`: when `decrement`
+/// returns `true`, or `get` returns `1`, every access made through the references that have
+/// been released happens-before whatever the caller does next.
+pub(crate) trait Count {
+    fn new(count: usize) -> Self;
+    fn get(&self) -> usize;
+    fn increment(&self);
+    /// Returns `true` if this was the last reference.
+    fn decrement(&self) -> bool;
+    /// Takes the count from one to zero, if it is one.
+    fn claim(&self) -> bool;
+    /// Increments the count unless it already is zero.
+    fn increment_if_nonzero(&self) -> bool;
+    /// Keeps new weak references from being made while uniqueness is checked. Only succeeds
+    /// if this weak count is one, which is the reference the strong ones hold together.
+    fn lock(&self) -> bool;
+    fn unlock(&self);
+    /// Increments a weak count, waiting for it to be unlocked first.
+    fn increment_weak(&self);
+}
+
+/// The counts of an [`Rc`].
+pub(crate) struct LocalCount(Cell<usize>);
+
+impl Count for LocalCount {
+    fn new(count: usize) -> Self {
+        LocalCount(Cell::new(count))
+    }
+
+    fn get(&self) -> usize {
+        self.0.get()
+    }
+
+    fn increment(&self) {
+        let count = self.0.get();
+        if count >= MAX_COUNT {
+            refcount_overflow();
+        }
+        self.0.set(count + 1);
+    }
+
+    fn decrement(&self) -> bool {
+        let count = self.0.get() - 1;
+        self.0.set(count);
+        count == 0
+    }
+
+    fn claim(&self) -> bool {
+        let unique = self.0.get() == 1;
+        if unique {
+            self.0.set(0);
+        }
+        unique
+    }
+
+    fn increment_if_nonzero(&self) -> bool {
+        let alive = self.0.get() != 0;
+        if alive {
+            self.increment();
+        }
+        alive
+    }
+
+    fn lock(&self) -> bool {
+        self.0.get() == 1
+    }
+
+    fn unlock(&self) {}
+
+    fn increment_weak(&self) {
+        self.increment()
+    }
+}
+
+/// The counts of an [`Arc`]. The orderings are the same as in the standard library.
+pub(crate) struct AtomicCount(AtomicUsize);
+
+// The weak count while it is locked
+const LOCKED: usize = usize::MAX;
+
+impl Count for AtomicCount {
+    fn new(count: usize) -> Self {
+        AtomicCount(AtomicUsize::new(count))
+    }
+
+    fn get(&self) -> usize {
+        self.0.load(Acquire)
+    }
+
+    fn increment(&self) {
+        // Relaxed is enough, the reference being cloned keeps the block alive
+        if self.0.fetch_add(1, Relaxed) > MAX_COUNT {
+            refcount_overflow();
+        }
+    }
+
+    fn decrement(&self) -> bool {
+        if self.0.fetch_sub(1, Release) != 1 {
+            return false;
+        }
+
+        // Synchronizes with the `Release` decrements of every other reference
+        fence(Acquire);
+        true
+    }
+
+    fn claim(&self) -> bool {
+        if self.0.compare_exchange(1, 0, Relaxed, Relaxed).is_err() {
+            return false;
+        }
+
+        fence(Acquire);
+        true
+    }
+
+    fn increment_if_nonzero(&self) -> bool {
+        let mut count = self.0.load(Relaxed);
+        loop {
+            if count == 0 {
+                return false;
+            }
+            if count > MAX_COUNT {
+                refcount_overflow();
+            }
+
+            match self
+                .0
+                .compare_exchange_weak(count, count + 1, Acquire, Relaxed)
+            {
+                Ok(_) => return true,
+                Err(actual) => count = actual,
+            }
+        }
+    }
+
+    fn lock(&self) -> bool {
+        self.0.compare_exchange(1, LOCKED, Acquire, Relaxed).is_ok()
+    }
+
+    fn unlock(&self) {
+        self.0.store(1, Release)
+    }
+
+    fn increment_weak(&self) {
+        let mut count = self.0.load(Relaxed);
+        loop {
+            if count == LOCKED {
+                spin_loop();
+                count = self.0.load(Relaxed);
+                continue;
+            }
+            if count > MAX_COUNT {
+                refcount_overflow();
+            }
+
+            match self
+                .0
+                .compare_exchange_weak(count, count + 1, Acquire, Relaxed)
+            {
+                Ok(_) => return,
+                Err(actual) => count = actual,
+            }
+        }
+    }
+}
+
+#[repr(C)]
+struct Header<C> {
+    strong: C,
+    // The strong references hold one weak reference between them
+    weak: C,
+    vtable: &'static VTable,
+}
+
+// What depends on the types of the value and allocator of a block
+struct VTable {
+    layout: Layout,
+    drop_value: unsafe fn(NonNull<u8>),
+    // Drops the header and allocator, and frees the block, but not the value
+    deallocate: unsafe fn(NonNull<u8>),
+    // Allocates a block for another value, from a clone of the allocator
+    allocate_like: unsafe fn(NonNull<u8>) -> Result<NonNull<u8>, AllocError>,
+}
+
+// The value comes right after the header, wherever the allocator ends up
+#[repr(C)]
+struct Block<C, T, A> {
+    header: Header<C>,
+    value: T,
+    allocator: A,
+}
+
+impl<C: Count, T, A: Allocator + Clone> Block<C, T, A> {
+    const VTABLE: VTable = VTable {
+        layout: Layout::new::<Self>(),
+        drop_value: Self::drop_value,
+        deallocate: Self::deallocate,
+        allocate_like: Self::allocate_like,
+    };
+
+    // Allocates a block with both counts at one, leaving the value uninitialized
+    fn allocate(allocator: A) -> Result<NonNull<Self>, AllocError> {
+        let block = allocator.allocate(Layout::new::<Self>())?.cast::<Self>();
+
+        unsafe {
+            ptr::addr_of_mut!((*block.as_ptr()).header).write(Header {
+                strong: C::new(1),
+                weak: C::new(1),
+                vtable: &Self::VTABLE,
+            });
+            ptr::addr_of_mut!((*block.as_ptr()).allocator).write(allocator);
+        }
+
+        Ok(block)
+    }
+
+    unsafe fn drop_value(block: NonNull<u8>) {
+        ptr::drop_in_place(ptr::addr_of_mut!((*block.cast::<Self>().as_ptr()).value))
+    }
+
+    unsafe fn deallocate(block: NonNull<u8>) {
+        let block = block.cast::<Self>();
+        let allocator = ptr::read(ptr::addr_of!((*block.as_ptr()).allocator));
+        ptr::drop_in_place(ptr::addr_of_mut!((*block.as_ptr()).header));
+        allocator.deallocate(block.cast(), Layout::new::<Self>());
+    }
+
+    unsafe fn allocate_like(block: NonNull<u8>) -> Result<NonNull<u8>, AllocError> {
+        let allocator = (*block.cast::<Self>().as_ptr()).allocator.clone();
+        Self::allocate(allocator).map(NonNull::cast)
+    }
+}
+
+// Where the value starts in its block, which doesn't depend on the allocator
+const fn value_offset<C, T>() -> usize {
+    let align = mem::align_of::<T>();
+    (mem::size_of::<Header<C>>() + align - 1) & !(align - 1)
+}
+
+/// A strong reference, pointing at the value in its block.
+pub(crate) struct RawRc<T, C: Count> {
+    ptr: NonNull<T>,
+    _marker: PhantomData<(T, C)>,
+}
+
+impl<T, C: Count> RawRc<T, C> {
+    pub(crate) fn new_in<A: Allocator + Clone>(value: T, allocator: &A) -> Self {
+        match Self::try_new_in(value, allocator) {
+            Ok(this) => this,
+            Err(_) => handle_alloc_error(Layout::new::<Block<C, T, A>>()),
+        }
+    }
+
+    pub(crate) fn try_new_in<A: Allocator + Clone>(
+        value: T,
+        allocator: &A,
+    ) -> Result<Self, AllocError> {
+        let block = Block::<C, T, A>::allocate(allocator.clone())?;
+
+        unsafe {
+            let ptr = ptr::addr_of_mut!((*block.as_ptr()).value);
+            ptr.write(value);
+            Ok(Self::from_raw(ptr))
+        }
+    }
+
+    fn block(&self) -> NonNull<u8> {
+        unsafe {
+            NonNull::new_unchecked(self.ptr.as_ptr().cast::<u8>().sub(value_offset::<C, T>()))
+        }
+    }
+
+    fn header(&self) -> &Header<C> {
+        unsafe { self.block().cast().as_ref() }
+    }
+
+    pub(crate) fn strong_count(&self) -> usize {
+        self.header().strong.get()
+    }
+
+    // Whether this is the only reference to the value, strong or weak
+    fn is_unique(&self) -> bool {
+        let header = self.header();
+        if !header.weak.lock() {
+            return false;
+        }
+
+        let unique = header.strong.get() == 1;
+        header.weak.unlock();
+        unique
+    }
+
+    pub(crate) fn get_mut(&mut self) -> Option<&mut T> {
+        if self.is_unique() {
+            Some(unsafe { &mut *self.ptr.as_ptr() })
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn make_mut(&mut self) -> &mut T
+    where
+        T: Clone,
+    {
+        let layout = self.header().vtable.layout;
+        match self.try_make_mut() {
+            Ok(value) => value,
+            Err(_) => handle_alloc_error(layout),
+        }
+    }
+
+    /// Copies the value into a new block from the same allocator, unless it is unique.
+    pub(crate) fn try_make_mut(&mut self) -> Result<&mut T, AllocError>
+    where
+        T: Clone,
+    {
+        if !self.is_unique() {
+            let value = T::clone(self);
+            unsafe {
+                let block = (self.header().vtable.allocate_like)(self.block())?;
+                let ptr = block.as_ptr().add(value_offset::<C, T>()).cast::<T>();
+                ptr.write(value);
+                *self = Self::from_raw(ptr);
+            }
+        }
+
+        Ok(unsafe { &mut *self.ptr.as_ptr() })
+    }
+
+    pub(crate) fn try_unwrap(self) -> Result<T, Self> {
+        if !self.header().strong.claim() {
+            return Err(self);
+        }
+
+        let this = ManuallyDrop::new(self);
+        unsafe {
+            let value = ptr::read(this.ptr.as_ptr());
+            drop(RawWeak::<T, C>::from_raw(this.ptr));
+            Ok(value)
+        }
+    }
+
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+
+    pub(crate) fn as_ptr(&self) -> *const T {
+        self.ptr.as_ptr()
+    }
+
+    pub(crate) fn into_raw(self) -> *const T {
+        ManuallyDrop::new(self).ptr.as_ptr()
+    }
+
+    /// # Safety
+    ///
+    /// `ptr` has to come from `into_raw`, and its reference is moved into the result.
+    pub(crate) unsafe fn from_raw(ptr: *const T) -> Self {
+        RawRc {
+            ptr: NonNull::new_unchecked(ptr as *mut T),
+            _marker: PhantomData,
+        }
+    }
+
+    pub(crate) fn downgrade(&self) -> RawWeak<T, C> {
+        self.header().weak.increment_weak();
+        unsafe { RawWeak::from_raw(self.ptr) }
+    }
+}
+
+impl<T, C: Count> Clone for RawRc<T, C> {
+    fn clone(&self) -> Self {
+        self.header().strong.increment();
+        unsafe { Self::from_raw(self.ptr.as_ptr()) }
+    }
+}
+
+impl<T, C: Count> Deref for RawRc<T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T, C: Count> Drop for RawRc<T, C> {
+    fn drop(&mut self) {
+        let header = self.header();
+        if !header.strong.decrement() {
+            return;
+        }
+
+        unsafe {
+            (header.vtable.drop_value)(self.block());
+            drop(RawWeak::<T, C>::from_raw(self.ptr));
+        }
+    }
+}
+
+/// A weak reference, which keeps the block alive but not the value in it.
+pub(crate) struct RawWeak<T, C: Count> {
+    ptr: NonNull<T>,
+    _marker: PhantomData<(T, C)>,
+}
+
+impl<T, C: Count> RawWeak<T, C> {
+    // Takes over a weak reference to the block of `ptr`
+    unsafe fn from_raw(ptr: NonNull<T>) -> Self {
+        RawWeak {
+            ptr,
+            _marker: PhantomData,
+        }
+    }
+
+    fn header(&self) -> &Header<C> {
+        unsafe {
+            &*self
+                .ptr
+                .as_ptr()
+                .cast::<u8>()
+                .sub(value_offset::<C, T>())
+                .cast::<Header<C>>()
+        }
+    }
+
+    pub(crate) fn upgrade(&self) -> Option<RawRc<T, C>> {
+        if self.header().strong.increment_if_nonzero() {
+            Some(unsafe { RawRc::from_raw(self.ptr.as_ptr()) })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, C: Count> Clone for RawWeak<T, C> {
+    fn clone(&self) -> Self {
+        // Only the weak reference of the strong ones can be locked, so this one isn't
+        self.header().weak.increment();
+        unsafe { Self::from_raw(self.ptr) }
+    }
+}
+
+impl<T, C: Count> Drop for RawWeak<T, C> {
+    fn drop(&mut self) {
+        let header = self.header();
+        if header.weak.decrement() {
+            let deallocate = header.vtable.deallocate;
+            unsafe {
+                deallocate(NonNull::new_unchecked(
+                    self.ptr.as_ptr().cast::<u8>().sub(value_offset::<C, T>()),
+                ))
+            }
+        }
+    }
+}
+
+macro_rules! pointer_types {
+    ($(#[$meta:meta])* $pointer:ident, $(#[$weak_meta:meta])* $weak:ident, $count:ty) => {
+        $(#[$meta])*
+        pub struct $pointer<T>(pub(crate) RawRc<T, $count>);
+
+        $(#[$weak_meta])*
+        pub struct $weak<T>(pub(crate) RawWeak<T, $count>);
+
+        impl<T> Clone for $pointer<T> {
+            fn clone(&self) -> Self {
+                $pointer(self.0.clone())
+            }
+        }
+
+        impl<T> Deref for $pointer<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.0
+            }
+        }
+
+        impl<T> Clone for $weak<T> {
+            fn clone(&self) -> Self {
+                $weak(self.0.clone())
+            }
+        }
+    };
+}
+
+pointer_types!(
+    /// A single threaded reference counted pointer, like `alloc::rc::Rc`, which frees its
+    /// value with the allocator it was allocated from. The pointer of
+    /// [`RcPointer`](super::RcPointer).
+    Rc,
+    /// A weak reference to the value of an [`Rc`].
+    RcWeak,
+    LocalCount
+);
+
+pointer_types!(
+    /// A thread safe reference counted pointer, like `alloc::sync::Arc`, which frees its value
+    /// with the allocator it was allocated from. The pointer of
+    /// [`ArcPointer`](super::ArcPointer).
+    Arc,
+    /// A weak reference to the value of an [`Arc`].
+    ArcWeak,
+    AtomicCount
+);
+
+// Like `Arc`, the value can be dropped on any thread. The allocator is too, which
+// `PointerFamily::try_new_in` makes sure is fine.
+unsafe impl<T: Send + Sync> Send for Arc<T> {}
+unsafe impl<T: Send + Sync> Sync for Arc<T> {}
+unsafe impl<T: Send + Sync> Send for ArcWeak<T> {}
+unsafe impl<T: Send + Sync> Sync for ArcWeak<T> {}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use crate::shared_vector::alloc::Global;
+    use crate::test_alloc::CountingAllocator;
+    use alloc::vec;
+
+    #[test]
+    fn blocks_are_freed_with_their_allocator() {
+        let allocator = CountingAllocator::default();
+        let rc = RawRc::<_, LocalCount>::new_in(vec![1u8, 2, 3], &allocator);
+        assert_eq!(allocator.live(), 1);
+
+        let mut copy = rc.clone();
+        let weak = rc.downgrade();
+        assert_eq!(rc.strong_count(), 2);
+
+        copy.make_mut().push(4);
+        assert_eq!(allocator.live(), 2);
+        assert_eq!(*copy, [1, 2, 3, 4]);
+        assert_eq!(*rc, [1, 2, 3]);
+
+        drop(rc);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(allocator.live(), 2);
+        drop(weak);
+        assert_eq!(allocator.live(), 1);
+
+        assert_eq!(copy.try_unwrap().ok(), Some(vec![1, 2, 3, 4]));
+        assert_eq!(allocator.live(), 0);
+    }
+
+    #[test]
+    fn weak_references_keep_values_shared() {
+        let mut arc = RawRc::<_, AtomicCount>::new_in(1usize, &Global);
+        let weak = arc.downgrade();
+        assert!(arc.get_mut().is_none());
+
+        let copy = weak.upgrade().unwrap();
+        *arc.make_mut() = 2;
+        assert!(!arc.ptr_eq(&copy));
+        assert_eq!((*arc, *copy), (2, 1));
+
+        drop(copy);
+        assert!(weak.upgrade().is_none());
+        drop(weak);
+        assert!(arc.get_mut().is_some());
+    }
+
+    #[test]
+    fn over_aligned_values() {
+        #[repr(align(128))]
+        #[derive(Clone)]
+        struct Aligned(u8);
+
+        let rc = RawRc::<_, LocalCount>::new_in(Aligned(7), &Global);
+        assert_eq!(rc.as_ptr() as usize % 128, 0);
+        let weak = rc.downgrade();
+        assert_eq!(weak.upgrade().map(|rc| rc.0), Some(7));
+    }
+}
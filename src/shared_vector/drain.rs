@@ -6,12 +6,13 @@ use core::mem;
 use core::ptr::{self, NonNull};
 use core::slice;
 
-use super::RawVector;
+use super::BufferSize;
 
-/// A draining iterator for `Vector<T>`.
+/// A draining iterator for `Vector<T>` and `RefCountedVector<T>`.
 ///
-/// This `struct` is created by [`crate::shared_vector::Vector::drain`].
-/// See its documentation for more.
+/// This `struct` is created by [`crate::shared_vector::Vector::drain`] and
+/// [`crate::shared_vector::RefCountedVector::drain`].
+/// See their documentation for more.
 pub struct Drain<'a, T: 'a> {
     /// Index of tail to preserve
     pub(super) tail_start: usize,
@@ -19,7 +20,11 @@ pub struct Drain<'a, T: 'a> {
     pub(super) tail_len: usize,
     /// Current remaining range to remove
     pub(super) iter: slice::Iter<'a, T>,
-    pub(super) vec: NonNull<RawVector<T>>,
+    /// Start of the source vector's storage
+    pub(super) data: NonNull<T>,
+    /// Length of the source vector, which lives either in a `RawVector` or in the
+    /// header of a reference counted buffer.
+    pub(super) len: NonNull<BufferSize>,
 }
 
 impl<T: fmt::Debug> fmt::Debug for Drain<'_, T> {
@@ -29,6 +34,34 @@ impl<T: fmt::Debug> fmt::Debug for Drain<'_, T> {
 }
 
 impl<'a, T> Drain<'a, T> {
+    /// Creates a drain removing the `start..end` range of a vector's elements.
+    ///
+    /// # Safety
+    ///
+    /// `data` must point to `*len` initialized elements, and both must be exclusively
+    /// borrowed for `'a`. The range must be within bounds.
+    pub(super) unsafe fn new(
+        data: NonNull<T>,
+        len: &'a mut BufferSize,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        let old_len = *len as usize;
+        debug_assert!(start <= end && end <= old_len);
+
+        // Set the vector's length to start, to be safe in case the Drain is leaked
+        *len = start as BufferSize;
+        let range_slice = slice::from_raw_parts(data.as_ptr().add(start), end - start);
+
+        Drain {
+            tail_start: end,
+            tail_len: old_len - end,
+            iter: range_slice.iter(),
+            data,
+            len: NonNull::from(len),
+        }
+    }
+
     /// Returns the remaining items of this iterator as a slice.
     ///
     /// # Examples
@@ -88,16 +121,17 @@ impl<T> Drop for Drain<'_, T> {
             fn drop(&mut self) {
                 if self.0.tail_len > 0 {
                     unsafe {
-                        let source_vec = self.0.vec.as_mut();
+                        let data = self.0.data.as_ptr();
+                        let len = self.0.len.as_ptr();
                         // memmove back untouched tail, update to new length
-                        let start = source_vec.len();
+                        let start = *len as usize;
                         let tail = self.0.tail_start;
                         if tail != start {
-                            let src = source_vec.as_ptr().add(tail);
-                            let dst = source_vec.as_mut_ptr().add(start);
+                            let src = data.add(tail);
+                            let dst = data.add(start);
                             ptr::copy(src, dst, self.0.tail_len);
                         }
-                        source_vec.header.len = (start + self.0.tail_len) as u32;
+                        *len = (start + self.0.tail_len) as BufferSize;
                     }
                 }
             }
@@ -106,7 +140,7 @@ impl<T> Drop for Drain<'_, T> {
         let iter = mem::replace(&mut self.iter, (&mut []).iter());
         let drop_len = iter.len();
 
-        let mut vec = self.vec;
+        let data = self.data;
 
         // if T::IS_ZST {
         //     // ZSTs have no identity, so we don't need to move them around, we only need to drop the correct amount.
@@ -138,7 +172,7 @@ impl<T> Drop for Drain<'_, T> {
             // a pointer with mutable provenance is necessary. Therefore we must reconstruct
             // it from the original vec but also avoid creating a &mut to the front since that could
             // invalidate raw pointers to it which some unsafe code might rely on.
-            let vec_ptr = vec.as_mut().as_mut_ptr();
+            let vec_ptr = data.as_ptr();
             let drop_offset = sub_ptr(drop_ptr, vec_ptr);
             let to_drop = ptr::slice_from_raw_parts_mut(vec_ptr.add(drop_offset), drop_len);
             ptr::drop_in_place(to_drop);
@@ -1,4 +1,4 @@
-use std::{
+use core::{
     mem,
     ptr::{self, NonNull},
 };
@@ -6,14 +6,17 @@ use std::{
 use crate::shared_vector::alloc::{Allocator, Global};
 use crate::shared_vector::{RawVector, Vector};
 
+/// An iterator that moves out of a vector.
+///
+/// This `struct` is created by the `into_iter` method on [`Vector`].
 pub struct IntoIter<T, A: Allocator = Global> {
     _buf: RawVector<T>, // we don't actually care about this. Just need it to live.
     iter: RawValIter<T>,
     pub(crate) allocator: A,
 }
 
-unsafe impl<T: Send> Send for IntoIter<T> {}
-unsafe impl<T: Sync> Sync for IntoIter<T> {}
+unsafe impl<T: Send, A: Allocator + Send> Send for IntoIter<T, A> {}
+unsafe impl<T: Sync, A: Allocator + Sync> Sync for IntoIter<T, A> {}
 
 impl<T, A: Allocator> Iterator for IntoIter<T, A> {
     type Item = T;
@@ -25,13 +28,13 @@ impl<T, A: Allocator> Iterator for IntoIter<T, A> {
     }
 }
 
-impl<T> DoubleEndedIterator for IntoIter<T> {
+impl<T, A: Allocator> DoubleEndedIterator for IntoIter<T, A> {
     fn next_back(&mut self) -> Option<T> {
         self.iter.next_back()
     }
 }
 
-impl<T> ExactSizeIterator for IntoIter<T> {}
+impl<T, A: Allocator> ExactSizeIterator for IntoIter<T, A> {}
 
 impl<T, A: Allocator> Drop for IntoIter<T, A> {
     fn drop(&mut self) {
@@ -46,16 +49,22 @@ impl<T, A: Allocator> Drop for IntoIter<T, A> {
 
 impl<T, A: Allocator> IntoIterator for Vector<T, A> {
     type Item = T;
-    type IntoIter = IntoIter<T>;
-    fn into_iter(self) -> IntoIter<T> {
-        let (iter, buf) = unsafe { (RawValIter::new(&self), ptr::read(&self.raw)) };
+    type IntoIter = IntoIter<T, A>;
+    fn into_iter(self) -> IntoIter<T, A> {
+        let (iter, buf, allocator) = unsafe {
+            (
+                RawValIter::new(&self),
+                ptr::read(&self.raw),
+                ptr::read(&self.allocator),
+            )
+        };
 
         mem::forget(self);
 
         IntoIter {
             iter,
             _buf: buf,
-            allocator: Global,
+            allocator,
         }
     }
 }
@@ -129,7 +138,27 @@ impl<T> ExactSizeIterator for RawValIter<T> {}
 
 #[cfg(test)]
 mod tests {
-    use std::sync::atomic::AtomicUsize;
+    use core::sync::atomic::AtomicUsize;
+
+    use crate::shared_vector::Vector;
+    use crate::test_alloc::CountingAllocator;
+
+    #[test]
+    fn into_iter_custom_allocator() {
+        let allocator = CountingAllocator::default();
+        let mut vector = Vector::with_capacity_in(4, allocator.clone());
+        vector.extend((0..4).map(Box::new));
+        assert_eq!(allocator.live(), 1);
+
+        let mut iter = vector.into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next_back(), Some(Box::new(3)));
+        assert_eq!(iter.next(), Some(Box::new(0)));
+        assert_eq!(iter.len(), 2);
+
+        drop(iter);
+        assert_eq!(allocator.live(), 0);
+    }
 
     #[test]
     fn into_iter_test() {
@@ -180,7 +209,7 @@ mod tests {
 
         impl Drop for Foo {
             fn drop(&mut self) {
-                COUNTER.fetch_add(1, std::sync::atomic::Ordering::Acquire);
+                COUNTER.fetch_add(1, core::sync::atomic::Ordering::Acquire);
             }
         }
 
@@ -193,7 +222,7 @@ mod tests {
         let resulting = vector.into_iter().collect::<Vec<_>>();
         let sum = resulting.into_iter().map(|x| *x.value).sum::<i32>();
         assert_eq!(sum, 5050);
-        assert_eq!(COUNTER.load(std::sync::atomic::Ordering::Relaxed), 101);
+        assert_eq!(COUNTER.load(core::sync::atomic::Ordering::Relaxed), 101);
     }
 
     #[test]
@@ -210,7 +239,7 @@ mod tests {
 
         impl Drop for Foo {
             fn drop(&mut self) {
-                COUNTER.fetch_add(1, std::sync::atomic::Ordering::Acquire);
+                COUNTER.fetch_add(1, core::sync::atomic::Ordering::Acquire);
             }
         }
 
@@ -227,6 +256,6 @@ mod tests {
 
         drop(iter);
 
-        assert_eq!(COUNTER.load(std::sync::atomic::Ordering::Relaxed), 101);
+        assert_eq!(COUNTER.load(core::sync::atomic::Ordering::Relaxed), 101);
     }
 }
@@ -1,22 +1,89 @@
+//! Reference counted, copy-on-write vectors.
+//!
+//! These are the buffers backing each node of a [`GenericList`](crate::list::GenericList), and
+//! can also be used on their own:
+//!
+//! - [`Vector`] is a uniquely owned vector, similar to `Vec<T>`.
+//! - [`SharedVector`] and [`AtomicSharedVector`] are reference counted vectors, similar to `Rc<[T]>`
+//!   and `Arc<[T]>`. Cloning one only bumps the reference count, and mutating one copies the buffer
+//!   first if it is shared.
+//! - [`RawVector`] is a `Vector` that doesn't store its allocator, for data structures that would rather
+//!   store it once themselves.
+//!
+//! Converting a `Vector` into either shared vector, or a unique shared vector back into a `Vector`,
+//! does not allocate or copy. Lists can be built from and converted back into any of these vectors.
+//!
+//! ```
+//! # use im_lists::list::List;
+//! # use im_lists::shared_vector::{AtomicSharedVector, Vector};
+//! # use im_lists::vector;
+//! let vector: Vector<usize> = vector![1, 2, 3];
+//! let shared: AtomicSharedVector<usize> = vector.into_shared_atomic();
+//! let copy = shared.clone();
+//! assert!(copy.ptr_eq(&shared));
+//!
+//! let list: List<usize> = shared.into();
+//! assert_eq!(list, im_lists::list![1, 2, 3]);
+//! assert_eq!(Vector::from(list).as_slice(), copy.as_slice());
+//! ```
+
 mod drain;
 mod intoiter;
 mod raw;
 mod shared;
+mod slice;
 mod splice;
 mod vector;
 
+pub(crate) use raw::refcount_overflow;
 pub use raw::{AtomicRefCount, BufferSize, DefaultRefCount, RefCount};
 
-#[allow(unused_imports)]
 pub use shared::{AtomicSharedVector, RefCountedVector, SharedVector};
+pub use slice::SharedSlice;
 pub use vector::{RawVector, Vector};
 
+pub use drain::Drain;
 pub use intoiter::IntoIter;
+pub use splice::Splice;
 
+/// The allocator API used by the vectors, re-exported from `allocator_api2`.
 pub mod alloc {
     pub use allocator_api2::alloc::{AllocError, Allocator, Global};
 }
 
+/// Resolves a range of indices into a vector of length `len`, panicking if it is out of bounds.
+pub(crate) fn range_bounds(
+    range: impl core::ops::RangeBounds<usize>,
+    len: usize,
+) -> (usize, usize) {
+    use core::ops::Bound::*;
+    let start = match range.start_bound() {
+        Included(n) => *n,
+        Excluded(n) => n
+            .checked_add(1)
+            .expect("attempted to index slice from after maximum usize"),
+        Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Included(n) => n
+            .checked_add(1)
+            .expect("attempted to index slice up to maximum usize"),
+        Excluded(n) => *n,
+        Unbounded => len,
+    };
+
+    assert!(
+        start <= end,
+        "slice index starts at {start} but ends at {end}"
+    );
+    assert!(
+        end <= len,
+        "range end index {end} out of range for length {len}"
+    );
+
+    (start, end)
+}
+
 pub(crate) fn grow_amortized(len: usize, additional: usize) -> usize {
     let required = len.saturating_add(additional);
     let cap = len.saturating_add(len).max(required).max(8);
@@ -34,6 +101,16 @@ pub(crate) fn grow_amortized(len: usize, additional: usize) -> usize {
     cap
 }
 
+/// Construct a [`Vector`] from a sequence of elements, optionally
+/// with a custom allocator.
+///
+/// ```
+/// # use im_lists::vector;
+/// # use im_lists::shared_vector::{alloc::Global, Vector};
+/// let v: Vector<u32> = vector![1, 2, 3];
+/// let w: Vector<u32> = vector!([1, 2, 3] in Global);
+/// assert_eq!(v, w);
+/// ```
 #[macro_export]
 macro_rules! vector {
     (@one@ $x:expr) => (1usize);
@@ -53,12 +130,14 @@ macro_rules! vector {
         vec
     });
     ([$x:expr;$n:expr] in $allocator:expr) => ({
-        let mut vec = $crate::Vector::try_with_capacity_in($n, $allocator).unwrap();
+        let mut vec = $crate::shared_vector::Vector::try_with_capacity_in($n, $allocator).unwrap();
         for _ in 0..$n { vec.push($x.clone()); }
         vec
     });
 }
 
+/// Construct a [`SharedVector`] from a sequence of elements,
+/// optionally with a custom allocator.
 #[macro_export]
 macro_rules! rc_vector {
     ($elem:expr; $n:expr) => ({
@@ -74,7 +153,7 @@ macro_rules! rc_vector {
     });
     ([$($x:expr),*$(,)*] in $allocator:expr) => ({
         let count = 0usize $(+ $crate::vector!(@one@ $x))*;
-        let mut vec = $crate::SharedVector::try_with_capacity_in(count, $allocator).unwrap();
+        let mut vec = $crate::shared_vector::SharedVector::try_with_capacity_in(count, $allocator).unwrap();
         $(vec.push($x);)*
         vec
     });
@@ -85,6 +164,8 @@ macro_rules! rc_vector {
     });
 }
 
+/// Construct an [`AtomicSharedVector`] from a sequence of
+/// elements, optionally with a custom allocator.
 #[macro_export]
 macro_rules! arc_vector {
     ($elem:expr; $n:expr) => ({
@@ -105,7 +186,7 @@ macro_rules! arc_vector {
         vec
     });
     ([$elem:expr;$n:expr] in $allocator:expr) => ({
-        let mut vec = $crate::AtomicSharedVector::try_with_capacity_in($n, $allocator).unwrap();
+        let mut vec = $crate::shared_vector::AtomicSharedVector::try_with_capacity_in($n, $allocator).unwrap();
         for _ in 0..$n { vec.push($elem.clone()); }
         vec
     });
@@ -118,21 +199,27 @@ fn vector_macro() {
     let v1: Vector<u32> = vector![0, 1, 2, 3, 4, 5];
     let v2: Vector<u32> = vector![2; 4];
     let v3: Vector<u32> = vector!([6, 7] in Global);
+    let v4: Vector<u32> = vector!([8; 3] in Global);
     assert_eq!(v1.as_slice(), &[0, 1, 2, 3, 4, 5]);
     assert_eq!(v2.as_slice(), &[2, 2, 2, 2]);
     assert_eq!(v3.as_slice(), &[6, 7]);
+    assert_eq!(v4.as_slice(), &[8, 8, 8]);
 
     let v1: SharedVector<u32> = rc_vector![0, 1, 2, 3, 4, 5];
     let v2: SharedVector<u32> = rc_vector![3; 5];
     let v3: SharedVector<u32> = rc_vector!([4; 3] in Global);
+    let v4: SharedVector<u32> = rc_vector!([5, 6] in Global);
     assert_eq!(v1.as_slice(), &[0, 1, 2, 3, 4, 5]);
     assert_eq!(v2.as_slice(), &[3, 3, 3, 3, 3]);
     assert_eq!(v3.as_slice(), &[4, 4, 4]);
+    assert_eq!(v4.as_slice(), &[5, 6]);
 
     let v1: AtomicSharedVector<u32> = arc_vector![0, 1, 2, 3, 4, 5];
     let v2: AtomicSharedVector<u32> = arc_vector![1; 4];
     let v3: AtomicSharedVector<u32> = arc_vector![[3, 2, 1] in Global];
+    let v4: AtomicSharedVector<u32> = arc_vector![[7; 2] in Global];
     assert_eq!(v1.as_slice(), &[0, 1, 2, 3, 4, 5]);
     assert_eq!(v2.as_slice(), &[1, 1, 1, 1]);
     assert_eq!(v3.as_slice(), &[3, 2, 1]);
+    assert_eq!(v4.as_slice(), &[7, 7]);
 }
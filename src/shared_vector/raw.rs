@@ -3,23 +3,78 @@ use core::cell::UnsafeCell;
 use core::marker::PhantomData;
 use core::mem;
 use core::ptr::{self, NonNull};
+
+#[cfg(not(loom))]
 use core::sync::atomic::{
-    AtomicI32,
-    Ordering::{Relaxed, Release},
+    fence, AtomicI32,
+    Ordering::{Acquire, Relaxed, Release},
+};
+#[cfg(loom)]
+use loom::sync::atomic::{
+    fence, AtomicI32,
+    Ordering::{Acquire, Relaxed, Release},
 };
 
+#[cfg(not(loom))]
+use crate::shared_vector::alloc::Global;
 pub use crate::shared_vector::alloc::{AllocError, Allocator};
 
 pub type BufferSize = u32;
 
-pub trait RefCount {
+/// A soft limit on the amount of references that may be made to a buffer.
+///
+/// Going above this limit will abort the program, the same way `Rc` and `Arc` do,
+/// since wrapping the count around would let the buffer be freed while references
+/// to it are still alive.
+pub const MAX_REFCOUNT: i32 = i32::MAX;
+
+/// The reference count stored in the header of a shared buffer.
+///
+/// Implementations that can be shared across threads must uphold the same ordering
+/// guarantees as `Arc`:
+///
+/// - When `release_ref` returns `true`, every access made through any other reference
+///   to the buffer happens-before the caller frees it.
+/// - When `get` returns `1`, every access made through references that have since been
+///   released happens-before the caller mutates the buffer in place.
+///
+/// This trait is sealed, the only implementations are [`DefaultRefCount`] and [`AtomicRefCount`].
+pub trait RefCount: sealed::Sealed {
+    /// # Safety
+    ///
+    /// Must only be called on the count of a live buffer, by a holder of one of its references.
     unsafe fn add_ref(&self);
+    /// Returns true if this was the last reference, in which case the caller frees the buffer.
+    ///
+    /// # Safety
+    ///
+    /// Must only be called once per reference, which is no longer used afterwards.
     unsafe fn release_ref(&self) -> bool;
     fn new(count: i32) -> Self;
     fn get(&self) -> i32;
 }
 
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::DefaultRefCount {}
+    impl Sealed for super::AtomicRefCount {}
+}
+
+/// A non-atomic reference count, used by [`SharedVector`](crate::shared_vector::SharedVector).
+#[cfg(not(loom))]
 pub struct DefaultRefCount(UnsafeCell<i32>);
+
+// Under loom the atomic count is a much larger mock type. Buffers switch between
+// reference counts in place, so the plain count is padded out to the same layout.
+#[cfg(loom)]
+#[repr(C)]
+pub union DefaultRefCount {
+    count: mem::ManuallyDrop<UnsafeCell<i32>>,
+    _layout: mem::ManuallyDrop<AtomicI32>,
+}
+
+/// An atomic reference count, used by [`AtomicSharedVector`](crate::shared_vector::AtomicSharedVector).
 pub struct AtomicRefCount(AtomicI32);
 
 #[repr(C)]
@@ -47,12 +102,31 @@ impl RefCount for AtomicRefCount {
     unsafe fn add_ref(&self) {
         // Relaxed ordering is OK since the presence of the existing reference
         // prevents threads from deleting the buffer.
-        self.0.fetch_add(1, Relaxed);
+        let old_count = self.0.fetch_add(1, Relaxed);
+
+        // Like `Arc`, we don't bother undoing the increment before aborting. Once
+        // the count has wrapped to a negative value it would take another `i32::MAX`
+        // increments from racing threads before it could reach zero, and every one
+        // of those threads will observe a negative count and abort first.
+        if old_count == MAX_REFCOUNT || old_count < 0 {
+            refcount_overflow();
+        }
     }
 
     #[inline]
     unsafe fn release_ref(&self) -> bool {
-        self.0.fetch_sub(1, Release) == 1
+        // Release so that our own accesses to the buffer happen-before whichever
+        // thread ends up freeing it.
+        if self.0.fetch_sub(1, Release) != 1 {
+            return false;
+        }
+
+        // This fence synchronizes with the `Release` decrements of every other
+        // reference, so that their accesses to the buffer happen-before the caller
+        // drops the items and deallocates. See the implementation of std `Arc`.
+        fence(Acquire);
+
+        true
     }
 
     #[inline]
@@ -62,34 +136,100 @@ impl RefCount for AtomicRefCount {
 
     #[inline]
     fn get(&self) -> i32 {
-        self.0.load(Relaxed)
+        // Observing a count of 1 lets the caller mutate the buffer in place, so this needs
+        // to synchronize with the `Release` decrements of references dropped by other
+        // threads, just like the uniqueness check in `Arc::get_mut`.
+        self.0.load(Acquire)
     }
 }
 
 impl RefCount for DefaultRefCount {
     #[inline]
     unsafe fn add_ref(&self) {
-        *self.0.get() += 1;
+        let count = self.cell().get();
+        match (*count).checked_add(1) {
+            Some(new_count) => *count = new_count,
+            None => refcount_overflow(),
+        }
     }
 
     #[inline]
     unsafe fn release_ref(&self) -> bool {
-        let count = self.0.get();
+        let count = self.cell().get();
         *count -= 1;
         *count == 0
     }
 
     #[inline]
     fn new(val: i32) -> Self {
-        DefaultRefCount(UnsafeCell::new(val))
+        #[cfg(not(loom))]
+        return DefaultRefCount(UnsafeCell::new(val));
+
+        #[cfg(loom)]
+        return DefaultRefCount {
+            count: mem::ManuallyDrop::new(UnsafeCell::new(val)),
+        };
     }
 
     #[inline]
     fn get(&self) -> i32 {
-        unsafe { *self.0.get() }
+        unsafe { *self.cell().get() }
     }
 }
 
+impl DefaultRefCount {
+    #[inline(always)]
+    fn cell(&self) -> &UnsafeCell<i32> {
+        #[cfg(not(loom))]
+        return &self.0;
+
+        #[cfg(loom)]
+        return unsafe { &self.count };
+    }
+}
+
+#[cold]
+#[inline(never)]
+pub(crate) fn refcount_overflow() -> ! {
+    #[cfg(feature = "std")]
+    std::process::abort();
+
+    // Without `std` there is no `abort`, but panicking while already panicking
+    // aborts the process all the same.
+    #[cfg(not(feature = "std"))]
+    {
+        struct Abort;
+
+        impl Drop for Abort {
+            fn drop(&mut self) {
+                panic!("reference count overflow");
+            }
+        }
+
+        let _abort = Abort;
+        panic!("reference count overflow");
+    }
+}
+
+/// A zero capacity buffer from the global allocator, which the nodes of empty lists share
+/// instead of allocating one each. Its count starts out too high to ever drop to one or zero,
+/// so it is never changed in place nor freed. It fits any element type aligned to at most 64
+/// bytes, since the data pointer of those doesn't go past its end.
+#[cfg(not(loom))]
+#[repr(C, align(64))]
+pub(crate) struct EmptyBuffer(pub(crate) Header<AtomicRefCount, Global>);
+
+// The data of elements aligned to 64 bytes starts right at the end of the buffer
+#[cfg(not(loom))]
+const _: () = assert!(mem::size_of::<EmptyBuffer>() == 64);
+
+#[cfg(not(loom))]
+pub(crate) static EMPTY_BUFFER: EmptyBuffer = EmptyBuffer(Header {
+    vec: VecHeader { cap: 0, len: 0 },
+    ref_count: AtomicRefCount(AtomicI32::new(i32::MAX / 2)),
+    allocator: Global,
+});
+
 #[inline]
 pub unsafe fn data_ptr<Header, T>(header: NonNull<Header>) -> *mut T {
     (header.as_ptr() as *mut u8).add(header_size::<Header, T>()) as *mut T
@@ -272,9 +412,11 @@ pub unsafe fn clear<T>(data: *mut T, vec: &mut VecHeader) {
     vec.len = 0;
 }
 
+// Buffers are allocated with the layout of a `DefaultRefCount` header and may later be
+// switched to a different reference count in place, so they all need the same layout.
 pub fn assert_ref_count_layout<R>() {
-    assert_eq!(mem::size_of::<R>(), mem::size_of::<i32>());
-    assert_eq!(mem::align_of::<R>(), mem::align_of::<i32>());
+    assert_eq!(mem::size_of::<R>(), mem::size_of::<AtomicRefCount>());
+    assert_eq!(mem::align_of::<R>(), mem::align_of::<AtomicRefCount>());
 }
 
 #[inline(never)]
@@ -322,3 +464,59 @@ fn buffer_layout_alignemnt() {
 
     assert_eq!(layout, atomic_layout);
 }
+
+#[test]
+fn ref_count_overflow_aborts() {
+    use crate::shared_vector::{AtomicSharedVector, SharedVector};
+    use std::process::Command;
+
+    // Aborting takes the whole process down with it, so the overflow itself runs in a
+    // child process, which is this same test binary filtered down to this test.
+    const CHILD_ENV: &str = "IM_LISTS_REF_COUNT_OVERFLOW_CHILD";
+
+    match std::env::var(CHILD_ENV).as_deref() {
+        Ok("default") => overflow_child(SharedVector::from_slice(&[1u32, 2, 3])),
+        Ok("atomic") => overflow_child(AtomicSharedVector::from_slice(&[1u32, 2, 3])),
+        _ => {
+            for variant in ["default", "atomic"] {
+                let output = Command::new(std::env::current_exe().unwrap())
+                    .args(["--exact", "shared_vector::raw::ref_count_overflow_aborts"])
+                    .args(["--test-threads", "1", "--nocapture"])
+                    .env(CHILD_ENV, variant)
+                    .output()
+                    .unwrap();
+
+                let stdout = String::from_utf8_lossy(&output.stdout);
+
+                assert!(!output.status.success(), "{variant}: {stdout}");
+                assert!(stdout.contains("at the limit"), "{variant}: {stdout}");
+                assert!(!stdout.contains("freed"), "{variant}: {stdout}");
+
+                #[cfg(unix)]
+                {
+                    use std::os::unix::process::ExitStatusExt;
+                    assert_eq!(output.status.signal(), Some(6), "{variant}: {stdout}");
+                }
+            }
+        }
+    }
+
+    fn overflow_child<R: RefCount>(vector: crate::shared_vector::RefCountedVector<u32, R>) {
+        // Driving the count up one reference at a time would take billions of clones,
+        // so jump straight to the limit instead.
+        unsafe {
+            ptr::write(
+                &mut (*vector.inner.as_ptr()).ref_count,
+                R::new(MAX_REFCOUNT),
+            );
+        }
+
+        println!("at the limit");
+
+        let overflowed = vector.clone();
+
+        // Unreachable if the overflow was caught.
+        drop(overflowed);
+        println!("freed");
+    }
+}
@@ -1,14 +1,22 @@
+use alloc::vec::Vec;
+#[cfg(not(loom))]
+use core::any::TypeId;
+use core::borrow::Borrow;
 use core::fmt::Debug;
-use core::ops::{Deref, DerefMut, Index, IndexMut};
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut, Index, IndexMut, RangeBounds};
 use core::ptr::NonNull;
-use core::sync::atomic::Ordering;
 use core::{mem, ptr};
 
 use crate::shared_vector::alloc::{AllocError, Allocator, Global};
+use crate::shared_vector::drain::Drain;
 use crate::shared_vector::raw;
 use crate::shared_vector::raw::{BufferSize, HeaderBuffer};
+use crate::shared_vector::splice::{GrowBuffer, Splice, SpliceBuffer};
 use crate::shared_vector::vector::{RawVector, Vector};
-use crate::shared_vector::{grow_amortized, AtomicRefCount, DefaultRefCount, RefCount};
+use crate::shared_vector::{
+    grow_amortized, range_bounds, AtomicRefCount, DefaultRefCount, RefCount,
+};
 
 /// A heap allocated, atomically reference counted, immutable contiguous buffer containing elements of type `T`.
 ///
@@ -44,6 +52,25 @@ pub struct RefCountedVector<T, R: RefCount, A: Allocator = Global> {
     pub(crate) inner: HeaderBuffer<T, R, A>,
 }
 
+impl<T, A: Allocator + 'static> AtomicSharedVector<T, A> {
+    /// A new reference to the shared empty buffer, if `A` is [`Global`] and `T` fits in it.
+    #[cfg(not(loom))]
+    pub(crate) fn shared_empty() -> Option<Self> {
+        if TypeId::of::<A>() != TypeId::of::<Global>() || mem::align_of::<T>() > 64 {
+            return None;
+        }
+
+        // Safety: `A` is `Global`, so this is the type of header the vector expects
+        let header = NonNull::from(&raw::EMPTY_BUFFER.0).cast::<raw::Header<AtomicRefCount, A>>();
+        unsafe {
+            header.as_ref().ref_count.add_ref();
+            Some(RefCountedVector {
+                inner: HeaderBuffer::from_raw(header),
+            })
+        }
+    }
+}
+
 impl<T, R: RefCount> RefCountedVector<T, R, Global> {
     /// Creates an empty shared buffer without allocating memory.
     #[inline]
@@ -190,6 +217,62 @@ impl<T, R: RefCount, A: Allocator> RefCountedVector<T, R, A> {
             Self::try_with_capacity_in(self.capacity(), self.inner.allocator().clone()).unwrap();
     }
 
+    /// Converts this RefCountedVector into a `Vector` without copying if this is the only
+    /// reference to the buffer, otherwise returns it unchanged.
+    ///
+    /// Unlike [`into_unique`](Self::into_unique) this never clones the elements, so it doesn't
+    /// need `T: Clone`.
+    pub fn try_into_unique(self) -> Result<Vector<T, A>, Self>
+    where
+        A: Clone,
+    {
+        if !self.is_unique() {
+            return Err(self);
+        }
+
+        unsafe {
+            let data = NonNull::new_unchecked(self.data_ptr());
+            let header = self.vec_header().clone();
+            let allocator = self.inner.as_ref().allocator.clone();
+
+            mem::forget(self);
+
+            Ok(Vector {
+                raw: RawVector { data, header },
+                allocator,
+            })
+        }
+    }
+
+    /// Appends an element if this is the only reference to the buffer and it has spare
+    /// capacity, otherwise gives the element back.
+    ///
+    /// This never copies or grows the buffer, so unlike [`push`](Self::push) it doesn't need
+    /// `T: Clone`.
+    pub fn push_if_unique(&mut self, val: T) -> Result<(), T> {
+        if self.remaining_capacity() == 0 || !self.is_unique() {
+            return Err(val);
+        }
+
+        unsafe {
+            raw::push_assuming_capacity(self.data_ptr(), self.vec_header_mut(), val);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a mutable slice of the elements if this is the only reference to the buffer.
+    ///
+    /// This never copies the buffer, so unlike [`as_mut_slice`](Self::as_mut_slice) it doesn't
+    /// need `T: Clone`.
+    pub fn as_mut_slice_if_unique(&mut self) -> Option<&mut [T]> {
+        if !self.is_unique() {
+            return None;
+        }
+
+        Some(unsafe { core::slice::from_raw_parts_mut(self.data_ptr(), self.len()) })
+    }
+
     /// Returns true if the two vectors share the same underlying storage.
     pub fn ptr_eq(&self, other: &Self) -> bool {
         self.inner.header == other.inner.header
@@ -257,17 +340,9 @@ impl<T: Clone, R: RefCount, A: Allocator + Clone> RefCountedVector<T, R, A> {
     pub fn into_unique(mut self) -> Vector<T, A> {
         self.ensure_unique();
 
-        unsafe {
-            let data = NonNull::new_unchecked(self.data_ptr());
-            let header = self.vec_header().clone();
-            let allocator = self.inner.as_ref().allocator.clone();
-
-            mem::forget(self);
-
-            Vector {
-                raw: RawVector { data, header },
-                allocator,
-            }
+        match self.try_into_unique() {
+            Ok(vector) => vector,
+            Err(_) => unreachable!("the buffer was just made unique"),
         }
     }
 
@@ -277,10 +352,18 @@ impl<T: Clone, R: RefCount, A: Allocator + Clone> RefCountedVector<T, R, A> {
     ///
     /// Panics if the new capacity exceeds `u32::MAX` bytes.
     pub fn push(&mut self, val: T) {
-        self.reserve(1);
+        self.try_push(val).unwrap()
+    }
+
+    /// Appends an element to the back of a collection, or returns an error if the buffer
+    /// could not be allocated. The element is dropped in that case.
+    pub fn try_push(&mut self, val: T) -> Result<(), AllocError> {
+        self.try_reserve(1)?;
         unsafe {
             raw::push_assuming_capacity(self.data_ptr(), &mut self.vec_header_mut(), val);
         }
+
+        Ok(())
     }
 
     /// Removes the last element from the vector and returns it, or `None` if it is empty.
@@ -375,9 +458,18 @@ impl<T: Clone, R: RefCount, A: Allocator + Clone> RefCountedVector<T, R, A> {
     /// predictable performance or in some unsafe endeavors.
     #[inline]
     pub fn ensure_unique(&mut self) {
+        self.try_ensure_unique().unwrap()
+    }
+
+    /// Like [`ensure_unique`](Self::ensure_unique), but returns an error instead of panicking
+    /// if the copy could not be allocated.
+    #[inline]
+    pub fn try_ensure_unique(&mut self) -> Result<(), AllocError> {
         if !self.is_unique() {
-            *self = self.try_clone_buffer(None).unwrap();
+            *self = self.try_clone_buffer(None)?;
         }
+
+        Ok(())
     }
 
     /// Extracts a mutable slice containing the entire vector.
@@ -530,7 +622,13 @@ impl<T: Clone, R: RefCount, A: Allocator + Clone> RefCountedVector<T, R, A> {
     ///
     /// If `other is not unique, the elements are cloned instead of moved.
     pub fn append(&mut self, other: &mut Self) {
-        self.reserve(other.len());
+        self.try_append(other).unwrap()
+    }
+
+    /// Like [`append`](Self::append), but returns an error instead of panicking if `self`
+    /// could not be grown. Both vectors are left untouched in that case.
+    pub fn try_append(&mut self, other: &mut Self) -> Result<(), AllocError> {
+        self.try_reserve(other.len())?;
 
         unsafe {
             if other.is_unique() {
@@ -543,17 +641,306 @@ impl<T: Clone, R: RefCount, A: Allocator + Clone> RefCountedVector<T, R, A> {
                 )
             } else {
                 // Slow path, clone each item.
+                let empty =
+                    Self::try_with_capacity_in(other.capacity(), self.inner.allocator().clone())?;
+
                 raw::extend_from_slice_assuming_capacity(
                     self.data_ptr(),
                     self.vec_header_mut(),
                     other.as_slice(),
                 );
 
-                *other =
-                    Self::try_with_capacity_in(other.capacity(), self.inner.allocator().clone())
-                        .unwrap();
+                *other = empty;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Inserts an element at position `index` within the vector, shifting all
+    /// elements after it to the right.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, element: T) {
+        #[cold]
+        #[inline(never)]
+        fn assert_failed(index: usize, len: usize) -> ! {
+            panic!("insertion index (is {index}) should be <= len (is {len})");
+        }
+
+        let len = self.len();
+        if index > len {
+            assert_failed(index, len);
+        }
+
+        self.reserve(1);
+
+        unsafe {
+            let ptr = self.data_ptr().add(index);
+            ptr::copy(ptr, ptr.add(1), len - index);
+            ptr::write(ptr, element);
+            self.vec_header_mut().len += 1;
+        }
+    }
+
+    /// Removes and returns the element at position `index` within the vector,
+    /// shifting all elements after it to the left.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        self.with_raw(|raw| raw.remove(index))
+    }
+
+    /// Shortens the vector, keeping the first `len` elements and dropping the rest.
+    ///
+    /// If `len` is greater than the vector's current length, this has no effect.
+    /// If the vector is not unique, only the first `len` elements are cloned.
+    pub fn truncate(&mut self, len: usize) {
+        self.try_truncate(len).unwrap()
+    }
+
+    /// Like [`truncate`](Self::truncate), but returns an error instead of panicking if the
+    /// vector is not unique and the copy could not be allocated.
+    pub fn try_truncate(&mut self, len: usize) -> Result<(), AllocError> {
+        let old_len = self.len();
+        if len >= old_len {
+            return Ok(());
+        }
+
+        if !self.is_unique() {
+            let mut clone = Self::try_with_capacity_in(self.capacity(), self.allocator().clone())?;
+            clone.extend_from_slice(&self.as_slice()[..len]);
+            *self = clone;
+            return Ok(());
+        }
+
+        unsafe {
+            // Update the length first, so that a panicking destructor leaks the remaining
+            // elements instead of dropping them twice.
+            self.vec_header_mut().len = len as BufferSize;
+            raw::drop_items(self.data_ptr().add(len), (old_len - len) as BufferSize);
+        }
+
+        Ok(())
+    }
+
+    /// Removes the specified range from the vector in bulk, returning all
+    /// removed elements as an iterator. If the iterator is dropped before
+    /// being fully consumed, it drops the remaining removed elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if
+    /// the end point is greater than the length of the vector.
+    ///
+    /// # Leaking
+    ///
+    /// If the returned iterator goes out of scope without being dropped (due to
+    /// [`mem::forget`], for example), the vector may have lost and leaked
+    /// elements arbitrarily, including elements outside the range.
+    pub fn drain<Range>(&mut self, range: Range) -> Drain<'_, T>
+    where
+        Range: RangeBounds<usize>,
+    {
+        let (start, end) = range_bounds(range, self.len());
+
+        self.ensure_unique();
+
+        unsafe {
+            let data = NonNull::new_unchecked(self.data_ptr());
+            Drain::new(data, &mut self.vec_header_mut().len, start, end)
+        }
+    }
+
+    /// Creates a splicing iterator that replaces the specified range in the vector
+    /// with the given `replace_with` iterator and yields the removed items.
+    /// `replace_with` does not need to be the same length as `range`.
+    ///
+    /// `range` is removed even if the iterator is not consumed until the end.
+    ///
+    /// It is unspecified how many elements are removed from the vector
+    /// if the `Splice` value is leaked.
+    ///
+    /// The input iterator `replace_with` is only consumed when the `Splice` value is dropped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if
+    /// the end point is greater than the length of the vector.
+    pub fn splice<Range, I>(
+        &mut self,
+        range: Range,
+        replace_with: I,
+    ) -> Splice<'_, <I as IntoIterator>::IntoIter, A>
+    where
+        Range: RangeBounds<usize>,
+        I: IntoIterator<Item = T>,
+    {
+        let mut vec = NonNull::from(self);
+
+        Splice {
+            drain: unsafe { vec.as_mut().drain(range) },
+            replace_with: replace_with.into_iter(),
+            buffer: SpliceBuffer::Shared(vec),
+        }
+    }
+
+    /// Retains only the elements specified by the predicate.
+    ///
+    /// In other words, remove all elements `e` for which `f(&e)` returns `false`.
+    /// This method operates in place, visiting each element exactly once in the
+    /// original order, and preserves the order of the retained elements.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.with_raw(|raw| raw.retain(f))
+    }
+
+    /// Retains only the elements specified by the predicate, passing a mutable reference to it.
+    ///
+    /// In other words, remove all elements `e` such that `f(&mut e)` returns `false`.
+    /// This method operates in place, visiting each element exactly once in the
+    /// original order, and preserves the order of the retained elements.
+    pub fn retain_mut<F>(&mut self, f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.with_raw(|raw| raw.retain_mut(f))
+    }
+
+    /// Removes all but the first of consecutive elements in the vector satisfying a given equality
+    /// relation.
+    ///
+    /// The `same_bucket` function is passed references to two elements from the vector and
+    /// must determine if the elements compare equal. The elements are passed in opposite order
+    /// from their order in the vector, so if `same_bucket(a, b)` returns `true`, `a` is removed.
+    pub fn dedup_by<F>(&mut self, same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        self.with_raw(|raw| raw.dedup_by(same_bucket))
+    }
+
+    /// Removes all but the first of consecutive elements in the vector that resolve to the same
+    /// key.
+    pub fn dedup_by_key<F, K>(&mut self, key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.with_raw(|raw| raw.dedup_by_key(key))
+    }
+
+    /// Removes consecutive repeated elements in the vector according to the `PartialEq` trait
+    /// implementation.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.with_raw(|raw| raw.dedup())
+    }
+
+    /// Sorts the vector, preserving the initial order of equal elements.
+    ///
+    /// See [`slice::sort`].
+    pub fn sort(&mut self)
+    where
+        T: Ord,
+    {
+        self.as_mut_slice().sort()
+    }
+
+    /// Sorts the vector with a comparator function, preserving the initial order of equal elements.
+    ///
+    /// See [`slice::sort_by`].
+    pub fn sort_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&T, &T) -> core::cmp::Ordering,
+    {
+        self.as_mut_slice().sort_by(compare)
+    }
+
+    /// Sorts the vector with a key extraction function, preserving the initial order of equal
+    /// elements.
+    ///
+    /// See [`slice::sort_by_key`].
+    pub fn sort_by_key<K, F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        self.as_mut_slice().sort_by_key(f)
+    }
+
+    /// Sorts the vector, but might not preserve the order of equal elements.
+    ///
+    /// See [`slice::sort_unstable`].
+    pub fn sort_unstable(&mut self)
+    where
+        T: Ord,
+    {
+        self.as_mut_slice().sort_unstable()
+    }
+
+    /// Sorts the vector with a comparator function, but might not preserve the order of equal
+    /// elements.
+    ///
+    /// See [`slice::sort_unstable_by`].
+    pub fn sort_unstable_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&T, &T) -> core::cmp::Ordering,
+    {
+        self.as_mut_slice().sort_unstable_by(compare)
+    }
+
+    /// Sorts the vector with a key extraction function, but might not preserve the order of equal
+    /// elements.
+    ///
+    /// See [`slice::sort_unstable_by_key`].
+    pub fn sort_unstable_by_key<K, F>(&mut self, f: F)
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        self.as_mut_slice().sort_unstable_by_key(f)
+    }
+
+    /// Reverses the order of elements in the vector, in place.
+    pub fn reverse(&mut self) {
+        self.as_mut_slice().reverse()
+    }
+
+    /// Runs `f` on a `RawVector` aliasing this vector's unique storage, which `f` must not
+    /// reallocate. The length is written back even if `f` panics.
+    fn with_raw<Ret>(&mut self, f: impl FnOnce(&mut RawVector<T>) -> Ret) -> Ret {
+        struct WriteBack<'a, T, R: RefCount, A: Allocator> {
+            vector: &'a mut RefCountedVector<T, R, A>,
+            raw: RawVector<T>,
+        }
+
+        impl<T, R: RefCount, A: Allocator> Drop for WriteBack<'_, T, R, A> {
+            fn drop(&mut self) {
+                debug_assert_eq!(self.raw.data.as_ptr(), self.vector.data_ptr());
+                unsafe {
+                    self.vector.vec_header_mut().len = self.raw.header.len;
+                }
             }
         }
+
+        self.ensure_unique();
+
+        let raw = RawVector {
+            data: unsafe { NonNull::new_unchecked(self.data_ptr()) },
+            header: self.vec_header().clone(),
+        };
+        let mut guard = WriteBack { vector: self, raw };
+
+        f(&mut guard.raw)
     }
 
     #[cold]
@@ -627,15 +1014,26 @@ impl<T: Clone, R: RefCount, A: Allocator + Clone> RefCountedVector<T, R, A> {
     }
 }
 
+impl<T: Clone, R: RefCount, A: Allocator + Clone> GrowBuffer<T> for RefCountedVector<T, R, A> {
+    unsafe fn grow(&mut self, additional: usize) -> (NonNull<T>, NonNull<BufferSize>) {
+        // The vector is unique so this reallocates in place, keeping the tail that a splice
+        // has moved past the vector's length.
+        self.reserve(additional);
+
+        (
+            NonNull::new_unchecked(self.data_ptr()),
+            NonNull::from(&mut self.vec_header_mut().len),
+        )
+    }
+}
+
 impl<T, R: RefCount, A: Allocator> Drop for RefCountedVector<T, R, A> {
     fn drop(&mut self) {
         unsafe {
+            // The atomic reference count takes care of the acquire fence that orders
+            // every other reference's accesses before the items are dropped.
             if self.inner.as_ref().ref_count.release_ref() {
                 let header = self.vec_header().clone();
-                // See the implementation of std Arc for the need to use this fence. Note that
-                // we only need it for the atomic reference counted version but I don't expect
-                // this to make a measurable difference.
-                core::sync::atomic::fence(Ordering::Acquire);
 
                 raw::drop_items(self.data_ptr(), header.len);
                 raw::dealloc::<T, R, A>(self.inner.header, header.cap);
@@ -644,7 +1042,17 @@ impl<T, R: RefCount, A: Allocator> Drop for RefCountedVector<T, R, A> {
     }
 }
 
-unsafe impl<T: Sync, A: Allocator + Send> Send for AtomicSharedVector<T, A> {}
+/// Sending a vector can move its last reference to another thread, which then drops or moves
+/// out the elements, so they have to be `Send` as well as `Sync`.
+///
+/// ```compile_fail
+/// # use im_lists::shared_vector::AtomicSharedVector;
+/// fn assert_send<T: Send>() {}
+///
+/// // A guard can be shared between threads, but must be dropped on the one that locked
+/// assert_send::<AtomicSharedVector<std::sync::MutexGuard<'static, ()>>>();
+/// ```
+unsafe impl<T: Send + Sync, A: Allocator + Send> Send for AtomicSharedVector<T, A> {}
 
 unsafe impl<T: Send + Sync, A: Allocator + Sync> Sync for AtomicSharedVector<T, A> {}
 
@@ -674,6 +1082,91 @@ impl<T, R: RefCount, A: Allocator> AsRef<[T]> for RefCountedVector<T, R, A> {
     }
 }
 
+impl<T: Eq, R: RefCount, A: Allocator> Eq for RefCountedVector<T, R, A> {}
+
+impl<T: PartialOrd<T>, R: RefCount, A: Allocator> PartialOrd<RefCountedVector<T, R, A>>
+    for RefCountedVector<T, R, A>
+{
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T: Ord, R: RefCount, A: Allocator> Ord for RefCountedVector<T, R, A> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        if self.ptr_eq(other) {
+            return core::cmp::Ordering::Equal;
+        }
+
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T: Hash, R: RefCount, A: Allocator> Hash for RefCountedVector<T, R, A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<T, R: RefCount, A: Allocator> Borrow<[T]> for RefCountedVector<T, R, A> {
+    fn borrow(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T: Clone, R: RefCount, A: Allocator + Clone + Default> FromIterator<T>
+    for RefCountedVector<T, R, A>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vector = Self::new_in(A::default());
+        vector.extend(iter);
+        vector
+    }
+}
+
+impl<T: Clone, R: RefCount, A: Allocator + Clone> Extend<T> for RefCountedVector<T, R, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        RefCountedVector::extend(self, iter)
+    }
+}
+
+impl<'a, T: Copy + 'a, R: RefCount, A: Allocator + Clone> Extend<&'a T>
+    for RefCountedVector<T, R, A>
+{
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        RefCountedVector::extend(self, iter.into_iter().copied())
+    }
+}
+
+impl<T: Clone, R: RefCount, A: Allocator + Default> From<&[T]> for RefCountedVector<T, R, A> {
+    fn from(slice: &[T]) -> Self {
+        Self::try_from_slice_in(slice, A::default()).unwrap()
+    }
+}
+
+impl<T, R: RefCount, A: Allocator + Clone + Default> From<Vec<T>> for RefCountedVector<T, R, A> {
+    fn from(vec: Vec<T>) -> Self {
+        Vector::<T, A>::from(vec).into_ref_counted()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: RefCount, A: Allocator + Clone> std::io::Write for RefCountedVector<u8, R, A> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 impl<T, R: RefCount> Default for RefCountedVector<T, R, Global> {
     fn default() -> Self {
         Self::new()
@@ -741,13 +1234,13 @@ impl<T: Debug, R: RefCount, A: Allocator> Debug for RefCountedVector<T, R, A> {
     }
 }
 
-impl<T: Clone, A: Allocator + Clone> From<Vector<T, A>> for SharedVector<T, A> {
+impl<T, A: Allocator + Clone> From<Vector<T, A>> for SharedVector<T, A> {
     fn from(vector: Vector<T, A>) -> Self {
         vector.into_shared()
     }
 }
 
-impl<T: Clone, A: Allocator + Clone> From<Vector<T, A>> for AtomicSharedVector<T, A> {
+impl<T, A: Allocator + Clone> From<Vector<T, A>> for AtomicSharedVector<T, A> {
     fn from(vector: Vector<T, A>) -> Self {
         vector.into_shared_atomic()
     }
@@ -848,3 +1341,160 @@ fn shrink_to_zero() {
     let mut v: SharedVector<u32> = SharedVector::new();
     v.shrink_to(0);
 }
+
+#[test]
+fn shared_insert_remove() {
+    let mut a: SharedVector<Box<u32>> = SharedVector::new();
+    a.extend([num(1), num(3)]);
+    let b = a.clone();
+
+    a.insert(1, num(2));
+    a.insert(3, num(4));
+    assert_eq!(a.as_slice(), &[num(1), num(2), num(3), num(4)]);
+    assert_eq!(b.as_slice(), &[num(1), num(3)]);
+
+    let c = a.clone();
+    assert_eq!(a.remove(0), num(1));
+    assert_eq!(a.as_slice(), &[num(2), num(3), num(4)]);
+    assert_eq!(c.as_slice(), &[num(1), num(2), num(3), num(4)]);
+}
+
+#[test]
+fn shared_truncate() {
+    let mut a: AtomicSharedVector<Box<u32>> = AtomicSharedVector::new();
+    a.extend((0..10).map(num));
+    let b = a.clone();
+
+    // Only the kept prefix is copied out of a shared buffer.
+    a.truncate(3);
+    assert!(!a.ptr_eq(&b));
+    assert_eq!(a.as_slice(), &[num(0), num(1), num(2)]);
+    assert_eq!(a.capacity(), b.capacity());
+    assert_eq!(b.len(), 10);
+
+    let ptr = a.data_ptr();
+    a.truncate(1);
+    a.truncate(5);
+    assert_eq!(a.data_ptr(), ptr);
+    assert_eq!(a.as_slice(), &[num(0)]);
+}
+
+#[test]
+fn shared_drain() {
+    let mut a: SharedVector<Box<u32>> = SharedVector::new();
+    a.extend((0..6).map(num));
+    let b = a.clone();
+
+    let drained: Vec<_> = a.drain(1..3).collect();
+    assert_eq!(drained, vec![num(1), num(2)]);
+    assert_eq!(a.as_slice(), &[num(0), num(3), num(4), num(5)]);
+    assert_eq!(b.len(), 6);
+
+    // Dropping the iterator early still removes the whole range.
+    let mut drain = a.drain(..3);
+    assert_eq!(drain.next(), Some(num(0)));
+    drop(drain);
+    assert_eq!(a.as_slice(), &[num(5)]);
+}
+
+#[test]
+fn shared_splice() {
+    let mut a: SharedVector<Box<u32>> = SharedVector::with_capacity(4);
+    a.extend((0..4).map(num));
+    let b = a.clone();
+
+    // Grows the buffer while the tail is moved out of the way.
+    let removed: Vec<_> = a.splice(1..2, (10..30).map(num)).collect();
+    assert_eq!(removed, vec![num(1)]);
+    let expected: Vec<_> = [0]
+        .into_iter()
+        .chain(10..30)
+        .chain([2, 3])
+        .map(num)
+        .collect();
+    assert_eq!(a.as_slice(), expected.as_slice());
+    assert_eq!(b.as_slice(), &[num(0), num(1), num(2), num(3)]);
+
+    // An iterator without an exact size hint goes through the slow path.
+    let mut c = b.clone();
+    c.splice(..3, (0..5).filter(|x| x % 2 == 0).map(num));
+    assert_eq!(c.as_slice(), &[num(0), num(2), num(4), num(3)]);
+
+    let mut d = b.clone();
+    d.splice(2.., [num(7)]);
+    assert_eq!(d.as_slice(), &[num(0), num(1), num(7)]);
+}
+
+#[test]
+fn shared_retain_dedup() {
+    let mut a: AtomicSharedVector<Box<u32>> = AtomicSharedVector::new();
+    a.extend([1, 1, 2, 3, 3, 4, 5, 5].map(num));
+    let b = a.clone();
+
+    a.dedup();
+    assert_eq!(a.as_slice(), &[num(1), num(2), num(3), num(4), num(5)]);
+
+    a.retain(|x| **x % 2 == 1);
+    assert_eq!(a.as_slice(), &[num(1), num(3), num(5)]);
+
+    a.dedup_by_key(|x| **x > 1);
+    assert_eq!(a.as_slice(), &[num(1), num(3)]);
+    assert_eq!(b.len(), 8);
+}
+
+#[test]
+fn shared_sort_reverse() {
+    let mut a: SharedVector<Box<u32>> = SharedVector::new();
+    a.extend([3, 1, 2].map(num));
+    let b = a.clone();
+
+    a.sort();
+    assert_eq!(a.as_slice(), &[num(1), num(2), num(3)]);
+    a.reverse();
+    assert_eq!(a.as_slice(), &[num(3), num(2), num(1)]);
+    a.sort_unstable_by_key(|x| **x % 3);
+    assert_eq!(a.as_slice(), &[num(3), num(1), num(2)]);
+    assert_eq!(b.as_slice(), &[num(3), num(1), num(2)]);
+}
+
+#[test]
+fn shared_std_traits() {
+    use std::collections::{BTreeSet, HashMap};
+    #[cfg(feature = "std")]
+    use std::io::Write;
+
+    let a: SharedVector<u32> = (0..3).collect();
+    let b: SharedVector<u32> = SharedVector::from(&[0, 1, 3][..]);
+    let c: SharedVector<u32> = vec![0, 1].into();
+
+    assert!(c < a && a < b);
+    assert_eq!(a.cmp(&a.clone()), core::cmp::Ordering::Equal);
+
+    let mut map = HashMap::new();
+    map.insert(a.clone(), "a");
+    map.insert(b.clone(), "b");
+    assert_eq!(map.get(&[0, 1, 2][..]), Some(&"a"));
+    assert_eq!(map.get(&b), Some(&"b"));
+
+    let set: BTreeSet<AtomicSharedVector<u32>> = [vec![2], vec![1, 2], vec![1]]
+        .into_iter()
+        .map(AtomicSharedVector::from)
+        .collect();
+    let sorted: Vec<_> = set.iter().map(|v| v.as_slice()).collect();
+    assert_eq!(sorted, vec![&[1][..], &[1, 2], &[2]]);
+
+    let mut d = c.clone();
+    Extend::extend(&mut d, &[5, 6]);
+    assert_eq!(d.as_slice(), &[0, 1, 5, 6]);
+    assert_eq!(c.as_slice(), &[0, 1]);
+
+    let boxes: AtomicSharedVector<Box<u32>> = vec![num(1), num(2)].into();
+    assert_eq!(boxes.as_slice(), &[num(1), num(2)]);
+
+    #[cfg(feature = "std")]
+    {
+        let mut bytes: SharedVector<u8> = SharedVector::new();
+        write!(bytes, "{}-{}", 1, 2).unwrap();
+        assert_eq!(bytes.as_slice(), b"1-2");
+    }
+}
@@ -0,0 +1,314 @@
+use core::fmt::Debug;
+use core::ops::{Deref, RangeBounds};
+use core::ptr;
+
+use crate::shared_vector::alloc::{AllocError, Allocator, Global};
+use crate::shared_vector::raw::{self, BufferSize};
+use crate::shared_vector::shared::RefCountedVector;
+use crate::shared_vector::vector::Vector;
+use crate::shared_vector::{range_bounds, RefCount};
+
+/// A reference counted view into a range of a [`RefCountedVector`].
+///
+/// Creating or sub-slicing a view only adds a reference to the underlying buffer, the elements
+/// are never cloned. Similar in principle to a `&[T]` that keeps its buffer alive.
+///
+/// A view can be turned back into an owned vector with [`SharedSlice::into_vector`]. Like the
+/// other mutable operations on shared vectors, this reuses the buffer in place if the view holds
+/// the only reference to it, and otherwise clones just the elements in view.
+pub struct SharedSlice<T, R: RefCount, A: Allocator = Global> {
+    vector: RefCountedVector<T, R, A>,
+    start: BufferSize,
+    len: BufferSize,
+}
+
+impl<T, R: RefCount, A: Allocator> SharedSlice<T, R, A> {
+    /// Returns the number of elements in view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns `true` if there are no elements in view.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Extracts a slice containing the elements in view.
+    #[inline]
+    pub fn as_slice(&self) -> &[T] {
+        let start = self.start as usize;
+        &self.vector.as_slice()[start..start + self.len as usize]
+    }
+
+    /// Returns the vector this is a view into.
+    pub fn buffer(&self) -> &RefCountedVector<T, R, A> {
+        &self.vector
+    }
+
+    /// Returns the offset of the view from the start of its buffer.
+    pub fn offset(&self) -> usize {
+        self.start as usize
+    }
+
+    /// Creates a view into a sub-range of this view, without cloning any elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if
+    /// the end point is greater than the length of the view.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Self {
+        let (start, end) = range_bounds(range, self.len());
+
+        SharedSlice {
+            vector: self.vector.new_ref(),
+            start: self.start + start as BufferSize,
+            len: (end - start) as BufferSize,
+        }
+    }
+
+    /// Divides the view into two at an index, without cloning any elements.
+    ///
+    /// The first will contain all indices from `[0, mid)` and the second will contain all
+    /// indices from `[mid, len)`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid > len`.
+    pub fn split_at(&self, mid: usize) -> (Self, Self) {
+        (self.slice(..mid), self.slice(mid..))
+    }
+
+    /// Returns true if the two views share the same underlying storage and range.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.vector.ptr_eq(&other.vector) && self.start == other.start && self.len == other.len
+    }
+}
+
+impl<T: Clone, R: RefCount, A: Allocator + Clone> SharedSlice<T, R, A> {
+    /// Converts the view into a vector containing only the elements in view.
+    ///
+    /// If this is the only reference to the buffer, the elements outside of the view are
+    /// dropped and the buffer is reused in place. Otherwise the elements in view are cloned
+    /// into a new buffer.
+    pub fn into_vector(self) -> RefCountedVector<T, R, A> {
+        self.try_into_vector().unwrap()
+    }
+
+    /// Like [`into_vector`](Self::into_vector), but returns an error instead of panicking if
+    /// the elements have to be cloned and the new buffer could not be allocated.
+    pub fn try_into_vector(self) -> Result<RefCountedVector<T, R, A>, AllocError> {
+        let SharedSlice {
+            mut vector,
+            start,
+            len,
+        } = self;
+
+        if !vector.is_unique() {
+            let slice = &vector.as_slice()[start as usize..(start + len) as usize];
+            return RefCountedVector::try_from_slice_in(slice, vector.allocator().clone());
+        }
+
+        let total = vector.len() as BufferSize;
+
+        if start == 0 && len == total {
+            return Ok(vector);
+        }
+
+        unsafe {
+            let data = vector.data_ptr();
+
+            // If dropping an element panics, leak the rest rather than risk dropping
+            // them twice.
+            vector.vec_header_mut().len = 0;
+
+            raw::drop_items(data.add((start + len) as usize), total - start - len);
+            raw::drop_items(data, start);
+
+            ptr::copy(data.add(start as usize), data, len as usize);
+
+            vector.vec_header_mut().len = len;
+        }
+
+        Ok(vector)
+    }
+
+    /// Converts the view into a unique vector containing only the elements in view.
+    ///
+    /// See [`SharedSlice::into_vector`].
+    pub fn into_unique(self) -> Vector<T, A> {
+        self.into_vector().into_unique()
+    }
+}
+
+impl<T, R: RefCount, A: Allocator> RefCountedVector<T, R, A> {
+    /// Creates a view into a range of this vector, without cloning any elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if
+    /// the end point is greater than the length of the vector.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> SharedSlice<T, R, A> {
+        self.new_ref().into_slice(range)
+    }
+
+    /// Converts this vector into a view of a range of it, without cloning any elements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the starting point is greater than the end point or if
+    /// the end point is greater than the length of the vector.
+    pub fn into_slice(self, range: impl RangeBounds<usize>) -> SharedSlice<T, R, A> {
+        let (start, end) = range_bounds(range, self.len());
+
+        SharedSlice {
+            vector: self,
+            start: start as BufferSize,
+            len: (end - start) as BufferSize,
+        }
+    }
+}
+
+impl<T, R: RefCount, A: Allocator> From<RefCountedVector<T, R, A>> for SharedSlice<T, R, A> {
+    fn from(vector: RefCountedVector<T, R, A>) -> Self {
+        vector.into_slice(..)
+    }
+}
+
+impl<T: Clone, R: RefCount, A: Allocator + Clone> From<SharedSlice<T, R, A>>
+    for RefCountedVector<T, R, A>
+{
+    fn from(slice: SharedSlice<T, R, A>) -> Self {
+        slice.into_vector()
+    }
+}
+
+impl<T, R: RefCount, A: Allocator> Clone for SharedSlice<T, R, A> {
+    fn clone(&self) -> Self {
+        SharedSlice {
+            vector: self.vector.new_ref(),
+            start: self.start,
+            len: self.len,
+        }
+    }
+}
+
+impl<T, R: RefCount, A: Allocator> Deref for SharedSlice<T, R, A> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, R: RefCount, A: Allocator> AsRef<[T]> for SharedSlice<T, R, A> {
+    fn as_ref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<'a, T, R: RefCount, A: Allocator> IntoIterator for &'a SharedSlice<T, R, A> {
+    type Item = &'a T;
+    type IntoIter = core::slice::Iter<'a, T>;
+    fn into_iter(self) -> core::slice::Iter<'a, T> {
+        self.as_slice().iter()
+    }
+}
+
+impl<T: PartialEq<T>, R: RefCount, A: Allocator> PartialEq<SharedSlice<T, R, A>>
+    for SharedSlice<T, R, A>
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr_eq(other) || self.as_slice() == other.as_slice()
+    }
+}
+
+impl<T: PartialEq<T>, R: RefCount, A: Allocator> PartialEq<&[T]> for SharedSlice<T, R, A> {
+    fn eq(&self, other: &&[T]) -> bool {
+        self.as_slice() == *other
+    }
+}
+
+impl<T: Debug, R: RefCount, A: Allocator> Debug for SharedSlice<T, R, A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        self.as_slice().fmt(f)
+    }
+}
+
+#[cfg(test)]
+fn num(val: u32) -> Box<u32> {
+    Box::new(val)
+}
+
+#[test]
+fn slice_shares_buffer() {
+    use crate::shared_vector::AtomicSharedVector;
+
+    let vector: AtomicSharedVector<Box<u32>> = crate::arc_vector![num(0), num(1), num(2), num(3)];
+    let slice = vector.slice(1..3);
+
+    assert_eq!(slice.as_slice(), &[num(1), num(2)]);
+    assert!(slice.buffer().ptr_eq(&vector));
+    assert_eq!(slice.offset(), 1);
+
+    let inner = slice.slice(1..);
+    assert_eq!(inner.as_slice(), &[num(2)]);
+    assert_eq!(inner.offset(), 2);
+    assert!(inner.buffer().ptr_eq(&vector));
+
+    let (left, right) = vector.slice(..).split_at(1);
+    assert_eq!(left.as_slice(), &[num(0)]);
+    assert_eq!(right.as_slice(), &[num(1), num(2), num(3)]);
+    assert!(left.buffer().ptr_eq(right.buffer()));
+
+    let empty = vector.slice(4..);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn into_vector_in_place() {
+    use crate::shared_vector::SharedVector;
+
+    let vector: SharedVector<Box<u32>> = crate::rc_vector![num(0), num(1), num(2), num(3)];
+    let data = vector.data_ptr();
+
+    let slice = vector.into_slice(1..3);
+    let vector = slice.into_vector();
+
+    assert_eq!(vector.as_slice(), &[num(1), num(2)]);
+    assert!(vector.is_unique());
+    assert_eq!(vector.data_ptr(), data);
+}
+
+#[test]
+fn into_vector_shared() {
+    use crate::shared_vector::SharedVector;
+
+    let vector: SharedVector<Box<u32>> = crate::rc_vector![num(0), num(1), num(2), num(3)];
+
+    let copy = vector.slice(2..).into_vector();
+    assert_eq!(copy.as_slice(), &[num(2), num(3)]);
+    assert!(!copy.ptr_eq(&vector));
+    assert_eq!(vector.as_slice(), &[num(0), num(1), num(2), num(3)]);
+
+    let unique = vector.slice(..1).into_unique();
+    assert_eq!(unique.as_slice(), &[num(0)]);
+}
+
+#[test]
+#[should_panic]
+fn slice_out_of_bounds() {
+    use crate::shared_vector::SharedVector;
+
+    let vector: SharedVector<u32> = crate::rc_vector![0, 1, 2];
+    vector.slice(1..4);
+}
+
+#[test]
+#[should_panic(expected = "maximum usize")]
+fn slice_to_maximum_usize() {
+    use crate::shared_vector::SharedVector;
+
+    let vector: SharedVector<u32> = crate::rc_vector![0, 1, 2];
+    vector.slice(1..=usize::MAX);
+}
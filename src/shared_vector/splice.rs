@@ -1,8 +1,11 @@
 use crate::shared_vector::alloc::{Allocator, Global};
-use core::ptr::{self};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr::{self, NonNull};
 use core::slice::{self};
 
 use crate::shared_vector::drain::Drain;
+use crate::shared_vector::{BufferSize, RawVector};
 
 /// A splicing iterator for `Vec`.
 ///
@@ -16,11 +19,64 @@ use crate::shared_vector::drain::Drain;
 /// let new = [7, 8];
 /// let iter: std::vec::Splice<_> = v.splice(1.., new);
 /// ```
-#[derive(Debug)]
 pub struct Splice<'a, I: Iterator + 'a, A: Allocator + 'a = Global> {
     pub(crate) drain: Drain<'a, I::Item>,
     pub(crate) replace_with: I,
-    pub(crate) allocator: &'a A,
+    pub(crate) buffer: SpliceBuffer<'a, I::Item, A>,
+}
+
+/// The vector a `Splice` may have to grow to fit the replacement elements.
+pub(crate) enum SpliceBuffer<'a, T, A: Allocator> {
+    Raw {
+        vec: NonNull<RawVector<T>>,
+        allocator: &'a A,
+    },
+    /// A unique `RefCountedVector`, whose header moves along with its storage.
+    Shared(NonNull<dyn GrowBuffer<T> + 'a>),
+}
+
+/// A vector that can grow in the middle of a splice.
+pub(crate) trait GrowBuffer<T> {
+    /// Reserves room for at least `additional` more elements, preserving the whole
+    /// allocation including the elements past the vector's length, and returns the
+    /// new location of the storage and of the length.
+    ///
+    /// # Safety
+    ///
+    /// The vector must be unique.
+    unsafe fn grow(&mut self, additional: usize) -> (NonNull<T>, NonNull<BufferSize>);
+}
+
+impl<T, A: Allocator> SpliceBuffer<'_, T, A> {
+    /// Reserves room for `additional` elements and points the drain at the new storage.
+    unsafe fn reserve(&mut self, drain: &mut Drain<'_, T>, additional: usize) {
+        match self {
+            SpliceBuffer::Raw { vec, allocator } => {
+                let vec = vec.as_mut();
+                vec.try_reserve(*allocator, additional).unwrap();
+                drain.data = vec.data;
+            }
+            SpliceBuffer::Shared(vec) => {
+                let (data, len) = vec.as_mut().grow(additional);
+                drain.data = data;
+                drain.len = len;
+            }
+        }
+    }
+}
+
+impl<'a, I, A> fmt::Debug for Splice<'a, I, A>
+where
+    I: Iterator + fmt::Debug + 'a,
+    I::Item: fmt::Debug,
+    A: Allocator + 'a,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Splice")
+            .field("drain", &self.drain)
+            .field("replace_with", &self.replace_with)
+            .finish()
+    }
 }
 
 impl<I: Iterator, A: Allocator> Iterator for Splice<'_, I, A> {
@@ -54,11 +110,9 @@ impl<I: Iterator, A: Allocator> Drop for Splice<'_, I, A> {
         self.drain.iter = (&[]).iter();
 
         unsafe {
-            if self.drain.tail_len == 0 {
-                self.drain
-                    .vec
-                    .as_mut()
-                    .extend(self.allocator, self.replace_with.by_ref());
+            if let (0, SpliceBuffer::Raw { vec, allocator }) = (self.drain.tail_len, &self.buffer) {
+                let mut vec = *vec;
+                vec.as_mut().extend(*allocator, self.replace_with.by_ref());
                 return;
             }
 
@@ -71,7 +125,7 @@ impl<I: Iterator, A: Allocator> Drop for Splice<'_, I, A> {
             // FIXME: Is the upper bound a better guess? Or something else?
             let (lower_bound, _upper_bound) = self.replace_with.size_hint();
             if lower_bound > 0 {
-                self.drain.move_tail(self.allocator, lower_bound);
+                self.drain.move_tail(&mut self.buffer, lower_bound);
                 if !self.drain.fill(&mut self.replace_with) {
                     return;
                 }
@@ -86,7 +140,7 @@ impl<I: Iterator, A: Allocator> Drop for Splice<'_, I, A> {
                 .into_iter();
             // Now we have an exact count.
             if collected.len() > 0 {
-                self.drain.move_tail(self.allocator, collected.len());
+                self.drain.move_tail(&mut self.buffer, collected.len());
                 let filled = self.drain.fill(&mut collected);
                 debug_assert!(filled);
                 debug_assert_eq!(collected.len(), 0);
@@ -98,22 +152,22 @@ impl<I: Iterator, A: Allocator> Drop for Splice<'_, I, A> {
 
 /// Private helper methods for `Splice::drop`
 impl<T> Drain<'_, T> {
-    /// The range from `self.vec.len` to `self.tail_start` contains elements
+    /// The range from `self.len` to `self.tail_start` contains elements
     /// that have been moved out.
     /// Fill that range as much as possible with new elements from the `replace_with` iterator.
     /// Returns `true` if we filled the entire range. (`replace_with.next()` didn’t return `None`.)
     unsafe fn fill<I: Iterator<Item = T>>(&mut self, replace_with: &mut I) -> bool {
-        let vec = unsafe { self.vec.as_mut() };
-        let range_start = vec.header.len as usize;
-        let range_end = self.tail_start as usize;
+        let len = unsafe { self.len.as_mut() };
+        let range_start = *len as usize;
+        let range_end = self.tail_start;
         let range_slice = unsafe {
-            slice::from_raw_parts_mut(vec.as_mut_ptr().add(range_start), range_end - range_start)
+            slice::from_raw_parts_mut(self.data.as_ptr().add(range_start), range_end - range_start)
         };
 
         for place in range_slice {
             if let Some(new_item) = replace_with.next() {
                 unsafe { ptr::write(place, new_item) };
-                vec.header.len += 1;
+                *len += 1;
             } else {
                 return false;
             }
@@ -122,17 +176,20 @@ impl<T> Drain<'_, T> {
     }
 
     /// Makes room for inserting more elements before the tail.
-    unsafe fn move_tail<A: Allocator>(&mut self, allocator: &A, additional: usize) {
-        let vec = unsafe { self.vec.as_mut() };
+    unsafe fn move_tail<A: Allocator>(
+        &mut self,
+        buffer: &mut SpliceBuffer<'_, T, A>,
+        additional: usize,
+    ) {
         let len = self.tail_start + self.tail_len;
         unsafe {
-            vec.try_reserve(allocator, len + additional).unwrap();
+            buffer.reserve(self, len + additional);
         }
 
         let new_tail_start = self.tail_start + additional;
         unsafe {
-            let src = vec.as_ptr().add(self.tail_start);
-            let dst = vec.as_mut_ptr().add(new_tail_start);
+            let src = self.data.as_ptr().add(self.tail_start);
+            let dst = self.data.as_ptr().add(new_tail_start);
             ptr::copy(src, dst, self.tail_len);
         }
         self.tail_start = new_tail_start;
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+use core::borrow::{Borrow, BorrowMut};
 use core::fmt::Debug;
 use core::ops::RangeBounds;
 use core::ops::{Deref, DerefMut, Index, IndexMut};
@@ -7,12 +9,11 @@ use core::{mem, ptr};
 use crate::shared_vector::alloc::{AllocError, Allocator, Global};
 use crate::shared_vector::drain::Drain;
 use crate::shared_vector::raw::{
-    self, buffer_layout, move_data, AtomicRefCount, BufferSize, Header, HeaderBuffer, RefCount,
-    VecHeader,
+    self, buffer_layout, move_data, BufferSize, Header, HeaderBuffer, RefCount, VecHeader,
 };
-use crate::shared_vector::shared::{AtomicSharedVector, SharedVector};
-use crate::shared_vector::splice::Splice;
-use crate::shared_vector::{grow_amortized, DefaultRefCount};
+use crate::shared_vector::shared::{AtomicSharedVector, RefCountedVector, SharedVector};
+use crate::shared_vector::splice::{Splice, SpliceBuffer};
+use crate::shared_vector::{grow_amortized, range_bounds, DefaultRefCount};
 
 /// A heap allocated, mutable contiguous buffer containing elements of type `T`, with manual deallocation.
 ///
@@ -129,6 +130,12 @@ impl<T> RawVector<T> {
         self.header.len = 0;
     }
 
+    /// Deallocates this raw vector without dropping its items, leaving it in its unallocated state.
+    ///
+    /// # Safety
+    ///
+    /// The provided allocator must be the one this raw vector was created with. The items are
+    /// leaked unless they were moved out beforehand.
     pub unsafe fn deallocate_no_drop<A: Allocator>(&mut self, allocator: &A) {
         if self.header.cap == 0 {
             return;
@@ -307,6 +314,10 @@ impl<T> RawVector<T> {
     /// Inserts an element at position `index` within the vector, shifting all
     /// elements after it to the right.
     ///
+    /// # Safety
+    ///
+    /// The provided allocator must be the one this raw vector was created with.
+    ///
     /// # Panics
     ///
     /// Panics if `index > len`.
@@ -626,32 +637,9 @@ impl<T> RawVector<T> {
         // When finished, remaining tail of the vec is copied back to cover
         // the hole, and the vector length is restored to the new length.
         //
-        use core::ops::Bound::*;
-        let len = self.len();
-        let end = match range.end_bound() {
-            Included(n) => *n + 1,
-            Excluded(n) => *n,
-            Unbounded => len,
-        };
-        let start = match range.start_bound() {
-            Included(n) => *n,
-            Excluded(n) => *n + 1,
-            Unbounded => 0,
-        };
-        assert!(end <= len);
-        assert!(start <= end);
+        let (start, end) = range_bounds(range, self.len());
 
-        unsafe {
-            // Set self.vec length's to start, to be safe in case Drain is leaked
-            self.header.len = start as u32;
-            let range_slice = core::slice::from_raw_parts(self.as_ptr().add(start), end - start);
-            Drain {
-                tail_start: end,
-                tail_len: len - end,
-                iter: range_slice.iter(),
-                vec: NonNull::from(self),
-            }
-        }
+        unsafe { Drain::new(self.data, &mut self.header.len, start, end) }
     }
 
     /// Creates a splicing iterator that replaces the specified range in the vector
@@ -678,6 +666,9 @@ impl<T> RawVector<T> {
     /// Panics if the starting point is greater than the end point or if
     /// the end point is greater than the length of the vector.
     ///
+    /// # Safety
+    ///
+    /// The provided allocator must be the one this raw vector was created with.
     pub unsafe fn splice<'l, A, R, I>(
         &'l mut self,
         allocator: &'l A,
@@ -689,10 +680,12 @@ impl<T> RawVector<T> {
         R: RangeBounds<usize>,
         I: IntoIterator<Item = T>,
     {
+        let mut vec = NonNull::from(self);
+
         Splice {
-            drain: self.drain(range),
+            drain: vec.as_mut().drain(range),
             replace_with: replace_with.into_iter(),
-            allocator,
+            buffer: SpliceBuffer::Raw { vec, allocator },
         }
     }
 
@@ -811,6 +804,100 @@ impl<T> RawVector<T> {
         drop(g);
     }
 
+    /// Removes all but the first of consecutive elements in the vector satisfying a given equality
+    /// relation.
+    ///
+    /// The `same_bucket` function is passed references to two elements from the vector and
+    /// must determine if the elements compare equal. The elements are passed in opposite order
+    /// from their order in the vector, so if `same_bucket(a, b)` returns `true`, `a` is removed.
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        let len = self.len();
+        if len <= 1 {
+            return;
+        }
+
+        // Vec: [Kept, Kept, Hole, Hole, Unchecked, Unchecked]
+        //                   ^- write      ^- read
+        //
+        // This drop guard will be invoked when `same_bucket` or `drop` of an element panicked.
+        // It shifts the unchecked elements to cover the holes and sets the correct length.
+        struct FillGapOnDrop<'a, T> {
+            read: usize,
+            write: usize,
+            len: usize,
+            v: &'a mut RawVector<T>,
+        }
+
+        impl<T> Drop for FillGapOnDrop<'_, T> {
+            fn drop(&mut self) {
+                // SAFETY: Items in `write..read` have been moved out or dropped, items in
+                // `read..len` are valid.
+                unsafe {
+                    let ptr = self.v.as_mut_ptr();
+                    ptr::copy(
+                        ptr.add(self.read),
+                        ptr.add(self.write),
+                        self.len - self.read,
+                    );
+                }
+                self.v.header.len = (self.len - (self.read - self.write)) as u32;
+            }
+        }
+
+        let mut gap = FillGapOnDrop {
+            read: 1,
+            write: 1,
+            len,
+            v: self,
+        };
+
+        unsafe {
+            let ptr = gap.v.as_mut_ptr();
+
+            while gap.read < len {
+                let read_ptr = ptr.add(gap.read);
+                let prev_ptr = ptr.add(gap.write - 1);
+
+                if same_bucket(&mut *read_ptr, &mut *prev_ptr) {
+                    // Advance early to avoid a double drop if `drop_in_place` panicked.
+                    gap.read += 1;
+                    ptr::drop_in_place(read_ptr);
+                } else {
+                    if gap.read != gap.write {
+                        ptr::copy_nonoverlapping(read_ptr, ptr.add(gap.write), 1);
+                    }
+                    gap.write += 1;
+                    gap.read += 1;
+                }
+            }
+        }
+
+        // All items are processed, the guard only has to set the length.
+        drop(gap);
+    }
+
+    /// Removes all but the first of consecutive elements in the vector that resolve to the same
+    /// key.
+    pub fn dedup_by_key<F, K>(&mut self, mut key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Removes consecutive repeated elements in the vector according to the `PartialEq` trait
+    /// implementation.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.dedup_by(|a, b| a == b)
+    }
+
     /// Transfers ownership of this raw vector's contents to the one that is returned, and leaves
     /// this one empty and unallocated.
     pub fn take(&mut self) -> Self {
@@ -1052,22 +1139,26 @@ impl<T, A: Allocator> Vector<T, A> {
         unsafe { raw::clear(self.raw.data_ptr(), &mut self.raw.header) }
     }
 
-    unsafe fn into_header_buffer<R>(mut self) -> HeaderBuffer<T, R, A>
+    unsafe fn into_header_buffer<R>(self) -> HeaderBuffer<T, R, A>
     where
         R: RefCount,
     {
         debug_assert!(self.raw.header.cap != 0);
         unsafe {
-            let mut header = raw::header_from_data_ptr(self.raw.data);
-
-            *header.as_mut() = raw::Header {
-                vec: VecHeader {
-                    len: self.raw.header.len,
-                    cap: self.raw.header.cap,
+            let header = raw::header_from_data_ptr(self.raw.data);
+
+            // The header is uninitialized memory at this point, so it must not be dropped.
+            ptr::write(
+                header.as_ptr(),
+                raw::Header {
+                    vec: VecHeader {
+                        len: self.raw.header.len,
+                        cap: self.raw.header.cap,
+                    },
+                    ref_count: R::new(1),
+                    allocator: ptr::read(&self.allocator),
                 },
-                ref_count: R::new(1),
-                allocator: ptr::read(&mut self.allocator),
-            };
+            );
 
             mem::forget(self);
 
@@ -1084,13 +1175,7 @@ impl<T, A: Allocator> Vector<T, A> {
     where
         A: Allocator + Clone,
     {
-        if self.raw.header.cap == 0 {
-            return SharedVector::try_with_capacity_in(0, self.allocator.clone()).unwrap();
-        }
-        unsafe {
-            let inner = self.into_header_buffer::<DefaultRefCount>();
-            SharedVector { inner }
-        }
+        self.into_ref_counted()
     }
 
     /// Make this vector immutable.
@@ -1099,15 +1184,23 @@ impl<T, A: Allocator> Vector<T, A> {
     /// to be reallocated.
     #[inline]
     pub fn into_shared_atomic(self) -> AtomicSharedVector<T, A>
+    where
+        A: Allocator + Clone,
+    {
+        self.into_ref_counted()
+    }
+
+    /// Makes this vector immutable, with either kind of reference count.
+    pub(crate) fn into_ref_counted<R: RefCount>(self) -> RefCountedVector<T, R, A>
     where
         A: Allocator + Clone,
     {
         if self.raw.header.cap == 0 {
-            return AtomicSharedVector::try_with_capacity_in(0, self.allocator.clone()).unwrap();
+            return RefCountedVector::try_with_capacity_in(0, self.allocator.clone()).unwrap();
         }
         unsafe {
-            let inner = self.into_header_buffer::<AtomicRefCount>();
-            AtomicSharedVector { inner }
+            let inner = self.into_header_buffer::<R>();
+            RefCountedVector { inner }
         }
     }
 
@@ -1372,6 +1465,38 @@ impl<T, A: Allocator> Vector<T, A> {
         self.raw.retain_mut(f)
     }
 
+    /// Removes all but the first of consecutive elements in the vector satisfying a given equality
+    /// relation.
+    ///
+    /// The `same_bucket` function is passed references to two elements from the vector and
+    /// must determine if the elements compare equal. The elements are passed in opposite order
+    /// from their order in the vector, so if `same_bucket(a, b)` returns `true`, `a` is removed.
+    pub fn dedup_by<F>(&mut self, same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        self.raw.dedup_by(same_bucket)
+    }
+
+    /// Removes all but the first of consecutive elements in the vector that resolve to the same
+    /// key.
+    pub fn dedup_by_key<F, K>(&mut self, key: F)
+    where
+        F: FnMut(&mut T) -> K,
+        K: PartialEq,
+    {
+        self.raw.dedup_by_key(key)
+    }
+
+    /// Removes consecutive repeated elements in the vector according to the `PartialEq` trait
+    /// implementation.
+    pub fn dedup(&mut self)
+    where
+        T: PartialEq,
+    {
+        self.raw.dedup()
+    }
+
     #[inline(always)]
     pub fn take(&mut self) -> Self
     where
@@ -1503,6 +1628,92 @@ impl<T: core::hash::Hash, A: Allocator> core::hash::Hash for Vector<T, A> {
     }
 }
 
+impl<T: Eq, A: Allocator> Eq for Vector<T, A> {}
+
+impl<T: PartialOrd<T>, A: Allocator> PartialOrd<Vector<T, A>> for Vector<T, A> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.as_slice().partial_cmp(other.as_slice())
+    }
+}
+
+impl<T: Ord, A: Allocator> Ord for Vector<T, A> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+impl<T, A: Allocator> Borrow<[T]> for Vector<T, A> {
+    fn borrow(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<T, A: Allocator> BorrowMut<[T]> for Vector<T, A> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<T, A: Allocator + Default> FromIterator<T> for Vector<T, A> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut vector = Vector::new_in(A::default());
+        vector.extend(iter);
+        vector
+    }
+}
+
+impl<T, A: Allocator> Extend<T> for Vector<T, A> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        Vector::extend(self, iter)
+    }
+}
+
+impl<'a, T: Copy + 'a, A: Allocator> Extend<&'a T> for Vector<T, A> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        Vector::extend(self, iter.into_iter().copied())
+    }
+}
+
+impl<T: Clone, A: Allocator + Default> From<&[T]> for Vector<T, A> {
+    fn from(slice: &[T]) -> Self {
+        let mut vector = Vector::with_capacity_in(slice.len(), A::default());
+        vector.extend_from_slice(slice);
+        vector
+    }
+}
+
+impl<T, A: Allocator + Default> From<Vec<T>> for Vector<T, A> {
+    fn from(mut vec: Vec<T>) -> Self {
+        let mut vector = Vector::with_capacity_in(vec.len(), A::default());
+
+        // Move the elements over and leave the `Vec` to free its own buffer.
+        unsafe {
+            ptr::copy_nonoverlapping(vec.as_ptr(), vector.raw.data_ptr(), vec.len());
+            vector.raw.header.len = vec.len() as BufferSize;
+            vec.set_len(0);
+        }
+
+        vector
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Allocator> std::io::Write for Vector<u8, A> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        self.extend_from_slice(buf);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[test]
 fn basic_unique() {
     fn num(val: u32) -> Box<u32> {
@@ -1634,3 +1845,46 @@ fn drain1() {
     let end = 16059518370053021185.min(len);
     vectors[2].drain(start..end);
 }
+
+#[test]
+fn dedup() {
+    let mut v: Vector<Box<u32>> = Vector::new();
+    v.extend([1, 1, 2, 3, 3, 3, 1, 4, 4].into_iter().map(Box::new));
+
+    v.dedup();
+    assert_eq!(v.as_slice(), &[1, 2, 3, 1, 4].map(Box::new));
+
+    v.dedup_by_key(|x| **x % 2);
+    assert_eq!(v.as_slice(), &[1, 2, 3, 4].map(Box::new));
+
+    v.dedup_by(|_, _| true);
+    assert_eq!(v.as_slice(), &[Box::new(1)]);
+}
+
+#[test]
+fn std_traits() {
+    use std::collections::HashSet;
+    #[cfg(feature = "std")]
+    use std::io::Write;
+
+    let a: Vector<u32> = (0..3).collect();
+    let b: Vector<u32> = Vector::from(&[0, 1, 3][..]);
+    let c: Vector<Box<u32>> = vec![Box::new(0), Box::new(1)].into();
+
+    assert!(a < b);
+    assert_eq!(c.as_slice(), &[Box::new(0), Box::new(1)]);
+
+    let set: HashSet<Vector<u32>> = [a.clone(), b.clone()].into_iter().collect();
+    assert!(set.contains(&[0, 1, 2][..]));
+
+    let mut d = a.clone();
+    Extend::extend(&mut d, &[7]);
+    assert_eq!(d.as_slice(), &[0, 1, 2, 7]);
+
+    #[cfg(feature = "std")]
+    {
+        let mut bytes: Vector<u8> = Vector::new();
+        bytes.write_all(b"abc").unwrap();
+        assert_eq!(bytes.as_slice(), b"abc");
+    }
+}
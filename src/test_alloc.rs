@@ -0,0 +1,60 @@
+//! Allocators for tests, which count or limit the memory they hand out.
+
+use alloc::sync::Arc;
+use core::alloc::Layout;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use crate::shared_vector::alloc::{AllocError, Allocator, Global};
+
+/// Counts its live allocations, to check that memory goes back to the allocator it came from.
+#[derive(Clone, Default)]
+pub(crate) struct CountingAllocator(Arc<AtomicUsize>);
+
+impl CountingAllocator {
+    /// The number of allocations that haven't been freed yet.
+    pub(crate) fn live(&self) -> usize {
+        self.0.load(Relaxed)
+    }
+
+    /// Whether `other` is a clone of this allocator.
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+unsafe impl Allocator for CountingAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0.fetch_add(1, Relaxed);
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.0.fetch_sub(1, Relaxed);
+        Global.deallocate(ptr, layout)
+    }
+}
+
+/// Fails once it has handed out its budget of allocations, which starts out empty.
+#[derive(Clone, Default)]
+pub(crate) struct LimitedAllocator(Arc<AtomicUsize>);
+
+impl LimitedAllocator {
+    /// Sets how many more allocations succeed.
+    pub(crate) fn set_budget(&self, budget: usize) {
+        self.0.store(budget, Relaxed)
+    }
+}
+
+unsafe impl Allocator for LimitedAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.0
+            .fetch_update(Relaxed, Relaxed, |budget| budget.checked_sub(1))
+            .map_err(|_| AllocError)?;
+        Global.allocate(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        Global.deallocate(ptr, layout)
+    }
+}
@@ -0,0 +1,68 @@
+//! Walking the nodes and elements of lists, for tracing garbage collectors.
+//!
+//! A collector for a custom [`PointerFamily`](crate::shared::PointerFamily) has to find every
+//! node a list can reach, along with the elements in them. [`GenericList::trace`] walks a
+//! list front to back, handing each node, the buffer holding its elements and the elements
+//! themselves to a [`Tracer`].
+//!
+//! [`GenericList::trace`]: crate::list::GenericList::trace
+
+/// Identifies a node of a list for as long as the node is alive.
+///
+/// This is the address a pointer to the node dereferences to, which is the pointer returned
+/// by [`PointerFamily::into_raw`](crate::shared::PointerFamily::into_raw).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(pub(crate) usize);
+
+impl NodeId {
+    /// Returns the id of the node at `ptr`.
+    pub fn from_ptr(ptr: *const ()) -> Self {
+        NodeId(ptr as usize)
+    }
+
+    /// Returns the address of the node.
+    pub fn as_ptr(self) -> *const () {
+        self.0 as *const ()
+    }
+}
+
+/// Identifies the element buffer of a node for as long as the buffer is alive. Nodes of
+/// different lists can share a buffer.
+///
+/// This is the address of the buffer's first element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BufferId(pub(crate) usize);
+
+impl BufferId {
+    /// Returns the address of the buffer's first element.
+    pub fn as_ptr(self) -> *const () {
+        self.0 as *const ()
+    }
+}
+
+/// Visits the nodes and elements of lists, see [`GenericList::trace`].
+///
+/// [`GenericList::trace`]: crate::list::GenericList::trace
+pub trait Tracer<T> {
+    /// Called on each node, before its elements. Returning `false` skips the node along with
+    /// every node after it, which tracers should do for nodes they already visited during
+    /// this trace. Lists share their tails, so every node is then walked exactly once.
+    fn visit_node(&mut self, node: NodeId) -> bool;
+
+    /// Called on each node that is walked, after `visit_node`, with its buffer and the number
+    /// of elements the node holds in it. Returns how many of those were already visited
+    /// through another node sharing the buffer, which are then skipped.
+    ///
+    /// The elements of a node are always the first `len` of its buffer, so a tracer that
+    /// remembers the largest `len` it saw for each buffer, and returns it, visits every
+    /// element once. By default no element is skipped.
+    fn visit_buffer(&mut self, buffer: BufferId, len: usize) -> usize {
+        let _ = (buffer, len);
+        0
+    }
+
+    /// Called on each element of a node that is walked, front to back, except for the ones
+    /// `visit_buffer` skipped. Elements that were popped off the front of the node aren't
+    /// visited, even while its buffer still holds them.
+    fn visit_element(&mut self, element: &T);
+}
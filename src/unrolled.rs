@@ -1,126 +1,434 @@
 #[cfg(test)]
 mod proptests;
 
+use crate::shared_vector::alloc::{AllocError, Allocator, Global};
 use crate::shared_vector::{AtomicSharedVector, Vector};
 use smallvec::SmallVec;
 
 use crate::shared::PointerFamily;
-
-use std::cmp::Ordering;
-use std::iter::{FlatMap, FromIterator, Rev};
-use std::marker::PhantomData;
-
-type ConsumingIter<T, P, const N: u32, const G: u32> = FlatMap<
-    NodeIter<T, P, N, G>,
+use crate::trace::{BufferId, NodeId, Tracer};
+
+use alloc::alloc::handle_alloc_error;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::cmp::Ordering;
+use core::iter::{FlatMap, FromIterator, Rev};
+use core::marker::PhantomData;
+use core::mem::{self, ManuallyDrop};
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+type ConsumingIter<T, P, const N: u32, const G: u32, A> = FlatMap<
+    NodeIter<T, P, N, G, A>,
     // Rev<std::iter::Take<std::vec::IntoIter<T>>>,
-    MaybeCloned<T, N, G>,
-    fn(UnrolledList<T, P, N, G>) -> MaybeCloned<T, N, G>, // Rev<std::iter::Take<std::vec::IntoIter<T>>>,
+    MaybeCloned<T, N, G, A>,
+    fn(UnrolledList<T, P, N, G, A>) -> MaybeCloned<T, N, G, A>, // Rev<core::iter::Take<std::vec::IntoIter<T>>>,
 >;
 
-type MaybeCloned<T, const N: u32, const G: u32> =
-    Rev<std::iter::Take<crate::shared_vector::IntoIter<T>>>;
+type MaybeCloned<T, const N: u32, const G: u32, A> =
+    Rev<core::iter::Take<crate::shared_vector::IntoIter<T, A>>>;
 
-type RefIter<'a, T, P, const N: u32, const G: u32> = FlatMap<
-    NodeIterRef<'a, T, P, N, G>,
-    Rev<std::slice::Iter<'a, T>>,
-    fn(&'a UnrolledList<T, P, N, G>) -> Rev<std::slice::Iter<'a, T>>,
+type RefIter<'a, T, P, const N: u32, const G: u32, A> = FlatMap<
+    NodeIterRef<'a, T, P, N, G, A>,
+    Rev<core::slice::Iter<'a, T>>,
+    fn(&'a UnrolledList<T, P, N, G, A>) -> Rev<core::slice::Iter<'a, T>>,
 >;
 
-type DrainingConsumingIter<T, P, const N: u32, const G: u32> = FlatMap<
-    DrainingNodeIter<T, P, N, G>,
+type DrainingConsumingIter<T, P, const N: u32, const G: u32, A> = FlatMap<
+    DrainingNodeIter<T, P, N, G, A>,
     // Rev<std::iter::Take<std::vec::IntoIter<T>>>,
-    Rev<std::iter::Take<crate::shared_vector::IntoIter<T>>>,
-    // fn(UnrolledList<T, P, N, G>) -> Rev<std::iter::Take<std::vec::IntoIter<T>>>,
-    fn(UnrolledList<T, P, N, G>) -> Rev<std::iter::Take<crate::shared_vector::IntoIter<T>>>,
+    Rev<core::iter::Take<crate::shared_vector::IntoIter<T, A>>>,
+    // fn(UnrolledList<T, P, N, G, A>) -> Rev<std::iter::Take<std::vec::IntoIter<T>>>,
+    fn(UnrolledList<T, P, N, G, A>) -> Rev<core::iter::Take<crate::shared_vector::IntoIter<T, A>>>,
 >;
 
-fn empty_list<T: Clone, P: PointerFamily, const N: u32, const G: u32>(
-) -> P::Pointer<UnrolledCell<T, P, N, G>>
+fn empty_list<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>(
+    allocator: A,
+) -> Result<NodePtr<T, P, N, G, A>, AllocError> {
+    NodePtr::try_new(UnrolledCell::try_new_in(allocator)?)
+}
+
+// Aborts when a node couldn't be allocated, like the global allocator does
+fn node_alloc_error<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static,
+>() -> ! {
+    handle_alloc_error(Layout::new::<UnrolledCell<T, P, N, G, A>>())
+}
+
+#[repr(transparent)]
+pub struct UnrolledList<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+>(pub(crate) NodePtr<T, P, N, G, A>);
+
+impl<T: Eq, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Eq
+    for UnrolledList<T, P, N, G, A>
+{
+}
+
+/// An owned strong reference to a node, held as the raw pointer of a `P::Pointer`.
+///
+/// The compiler has to treat `P::Pointer<T>` as invariant in `T`, since it can't see which
+/// pointer `P` picks. Storing the raw pointer instead keeps lists covariant in their element
+/// type, which the safety contract of [`PointerFamily`] makes sound.
+#[repr(transparent)]
+pub(crate) struct NodePtr<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static,
+> {
+    ptr: NonNull<UnrolledCell<T, P, N, G, A>>,
+    _marker: PhantomData<(UnrolledCell<T, P, N, G, A>, P)>,
+}
+
+// A `NodePtr` is just a `P::Pointer` in disguise, so it can be sent wherever that can.
+unsafe impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Send
+    for NodePtr<T, P, N, G, A>
 where
-    P::Pointer<UnrolledCell<T, P, N, G>>: 'static,
+    P::Pointer<UnrolledCell<T, P, N, G, A>>: Send,
 {
-    let mut output = None;
-    generic_singleton::get_or_init_thread_local!(
-        || P::new(UnrolledCell::new()),
-        |cell: &P::Pointer<UnrolledCell<T, P, N, G>>| {
-            output = Some(P::clone(cell));
+}
+
+unsafe impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Sync
+    for NodePtr<T, P, N, G, A>
+where
+    P::Pointer<UnrolledCell<T, P, N, G, A>>: Sync,
+{
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> NodePtr<T, P, N, G, A> {
+    /// Allocates a node for `cell` from the allocator of its element buffer.
+    pub(crate) fn new(cell: UnrolledCell<T, P, N, G, A>) -> Self {
+        Self::try_new(cell).unwrap_or_else(|_| node_alloc_error::<T, P, N, G, A>())
+    }
+
+    pub(crate) fn try_new(cell: UnrolledCell<T, P, N, G, A>) -> Result<Self, AllocError> {
+        let allocator = cell.elements.allocator().clone();
+        // Safety: the cell owns a clone of the allocator, in its element buffer
+        let pointer = if mem::needs_drop::<T>() {
+            unsafe { P::try_new_in(cell, &allocator) }
+        } else {
+            // Dropping the node only frees its buffer and drops the next node's pointer
+            unsafe { P::try_new_forgettable_in(cell, &allocator) }
+        };
+        pointer.map(Self::from_pointer)
+    }
+
+    fn from_pointer(pointer: P::Pointer<UnrolledCell<T, P, N, G, A>>) -> Self {
+        // Safety: `into_raw` never hands out a null pointer
+        unsafe { Self::from_raw(P::into_raw(pointer)) }
+    }
+
+    fn into_pointer(self) -> P::Pointer<UnrolledCell<T, P, N, G, A>> {
+        // Safety: the pointer came from `into_raw`, and ownership moves out of `self`
+        unsafe { P::from_raw(Self::into_raw(self)) }
+    }
+
+    /// Lend out the underlying `P::Pointer`, without touching the reference count.
+    fn with_pointer<O>(
+        &self,
+        func: impl FnOnce(&P::Pointer<UnrolledCell<T, P, N, G, A>>) -> O,
+    ) -> O {
+        // Safety: the pointer came from `into_raw`, and is forgotten again afterwards
+        let pointer = ManuallyDrop::new(unsafe { P::from_raw(self.ptr.as_ptr()) });
+        func(&pointer)
+    }
+
+    pub(crate) fn make_mut(this: &mut Self) -> &mut UnrolledCell<T, P, N, G, A> {
+        Self::try_make_mut(this).unwrap_or_else(|_| node_alloc_error::<T, P, N, G, A>())
+    }
+
+    /// Makes the node unique, copying it into a new node from the same allocator if it is
+    /// shared. On failure `this` is left as it was.
+    pub(crate) fn try_make_mut(
+        this: &mut Self,
+    ) -> Result<&mut UnrolledCell<T, P, N, G, A>, AllocError> {
+        // Puts the (possibly new) pointer back into `slot`, even if cloning the node panics
+        struct WriteBack<'a, C, P: PointerFamily> {
+            slot: &'a mut NonNull<C>,
+            pointer: ManuallyDrop<P::Pointer<C>>,
+        }
+
+        impl<C, P: PointerFamily> Drop for WriteBack<'_, C, P> {
+            fn drop(&mut self) {
+                let pointer = unsafe { ManuallyDrop::take(&mut self.pointer) };
+                *self.slot = unsafe { NonNull::new_unchecked(P::into_raw(pointer) as *mut C) };
+            }
+        }
+
+        let pointer = ManuallyDrop::new(unsafe { P::from_raw(this.ptr.as_ptr()) });
+        let mut guard = WriteBack::<_, P> {
+            slot: &mut this.ptr,
+            pointer,
+        };
+        let cell: *mut UnrolledCell<T, P, N, G, A> = P::try_make_mut(&mut guard.pointer)?;
+        drop(guard);
+
+        // Safety: the node is unique now, and `this` holds the pointer it lives behind
+        Ok(unsafe { &mut *cell })
+    }
+
+    pub(crate) fn get_mut(this: &mut Self) -> Option<&mut UnrolledCell<T, P, N, G, A>> {
+        let mut pointer = ManuallyDrop::new(unsafe { P::from_raw(this.ptr.as_ptr()) });
+        let cell: *mut UnrolledCell<T, P, N, G, A> = P::get_mut(&mut pointer)?;
+
+        // Safety: `get_mut` only succeeds when the node is unique, and `this` is borrowed mutably
+        Some(unsafe { &mut *cell })
+    }
+
+    pub(crate) fn strong_count(this: &Self) -> usize {
+        this.with_pointer(P::strong_count)
+    }
+
+    pub(crate) fn ptr_eq(this: &Self, other: &Self) -> bool {
+        this.ptr == other.ptr
+    }
+
+    pub(crate) fn as_ptr(this: &Self) -> *const UnrolledCell<T, P, N, G, A> {
+        this.ptr.as_ptr()
+    }
+
+    pub(crate) fn try_unwrap(this: Self) -> Option<UnrolledCell<T, P, N, G, A>> {
+        P::try_unwrap(this.into_pointer())
+    }
+
+    pub(crate) fn downgrade(this: &Self) -> P::Weak<UnrolledCell<T, P, N, G, A>> {
+        this.with_pointer(P::downgrade)
+    }
+
+    pub(crate) fn upgrade(weak: &P::Weak<UnrolledCell<T, P, N, G, A>>) -> Option<Self> {
+        P::upgrade(weak).map(Self::from_pointer)
+    }
+
+    pub(crate) fn into_raw(this: Self) -> *const UnrolledCell<T, P, N, G, A> {
+        ManuallyDrop::new(this).ptr.as_ptr()
+    }
+
+    /// # Safety
+    /// This must be called with a pointer as returned from `into_raw`
+    pub(crate) unsafe fn from_raw(ptr: *const UnrolledCell<T, P, N, G, A>) -> Self {
+        Self {
+            ptr: NonNull::new_unchecked(ptr as *mut _),
+            _marker: PhantomData,
         }
-    );
+    }
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Clone
+    for NodePtr<T, P, N, G, A>
+{
+    fn clone(&self) -> Self {
+        self.with_pointer(|pointer| Self::from_pointer(P::clone(pointer)))
+    }
+}
 
-    output.unwrap()
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Drop
+    for NodePtr<T, P, N, G, A>
+{
+    fn drop(&mut self) {
+        // Safety: the pointer came from `into_raw`, and this is the last use of it
+        drop(unsafe { P::from_raw(self.ptr.as_ptr()) });
+    }
 }
 
-#[derive(Eq)]
-#[repr(transparent)]
-pub struct UnrolledList<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32>(
-    pub(crate) P::Pointer<UnrolledCell<T, P, N, G>>,
-);
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Deref
+    for NodePtr<T, P, N, G, A>
+{
+    type Target = UnrolledCell<T, P, N, G, A>;
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Clone for UnrolledList<T, P, N, G> {
+    fn deref(&self) -> &Self::Target {
+        // Safety: `P` promises the raw pointer is the address it dereferences to
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Clone
+    for UnrolledList<T, P, N, G, A>
+{
     fn clone(&self) -> Self {
-        Self(P::clone(&self.0))
+        Self(NodePtr::clone(&self.0))
     }
 }
 
-// Check if these lists are equivalent via the iterator
-impl<T: Clone + PartialEq, P: PointerFamily, const N: u32, const G: u32> PartialEq
-    for UnrolledList<T, P, N, G>
+// Check if these lists are equivalent a chunk at a time, see `compare_chunks`
+impl<T: PartialEq, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> PartialEq
+    for UnrolledList<T, P, N, G, A>
 {
     fn eq(&self, other: &Self) -> bool {
-        Iterator::eq(self.iter(), other.iter())
+        self.compare_chunks(other, |l, r| (l == r).then_some(Ordering::Equal))
+            == Some(Ordering::Equal)
     }
 }
 
-impl<T: Clone + PartialOrd, P: PointerFamily, const N: u32, const G: u32> PartialOrd
-    for UnrolledList<T, P, N, G>
+impl<T: PartialOrd, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> PartialOrd
+    for UnrolledList<T, P, N, G, A>
 {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.iter().partial_cmp(other.iter())
+        self.compare_chunks(other, |l, r| l.iter().rev().partial_cmp(r.iter().rev()))
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Default for UnrolledList<T, P, N, G> {
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + Default> Default
+    for UnrolledList<T, P, N, G, A>
+{
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P, N, G> {
-    pub fn new() -> Self {
-        // UnrolledList(P::new(UnrolledCell::new()))
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    UnrolledList<T, P, N, G, A>
+{
+    pub fn new() -> Self
+    where
+        A: Default,
+    {
+        Self::new_in(A::default())
+    }
+
+    pub fn new_in(allocator: A) -> Self {
+        Self::or_alloc_error(Self::try_new_in(allocator))
+    }
+
+    pub fn try_new_in(allocator: A) -> Result<Self, AllocError> {
+        empty_list::<T, P, N, G, A>(allocator).map(UnrolledList)
+    }
+
+    // Aborts like the global allocator does, for the infallible versions of `try_` operations
+    fn or_alloc_error<R>(result: Result<R, AllocError>) -> R {
+        result.unwrap_or_else(|_| node_alloc_error::<T, P, N, G, A>())
+    }
 
-        UnrolledList(empty_list::<T, P, N, G>())
+    // An empty list that allocates from the same allocator as this one
+    fn empty(&self) -> Self {
+        Self::or_alloc_error(self.try_empty())
     }
 
-    pub fn as_ptr(&self) -> *const UnrolledCell<T, P, N, G> {
-        P::as_ptr(&self.0)
+    fn try_empty(&self) -> Result<Self, AllocError> {
+        Self::try_new_in(self.allocator().clone())
     }
 
-    pub fn new_with_capacity() -> Self {
-        UnrolledList(P::new(UnrolledCell::new_with_capacity()))
+    pub fn allocator(&self) -> &A {
+        self.0.elements.allocator()
+    }
+
+    // Moves the list out, leaving an empty list with the same allocator behind
+    pub(crate) fn take_list(&mut self) -> Self {
+        Self::or_alloc_error(self.try_take_list())
+    }
+
+    pub(crate) fn try_take_list(&mut self) -> Result<Self, AllocError> {
+        let empty = self.try_empty()?;
+        Ok(core::mem::replace(self, empty))
+    }
+
+    pub fn as_ptr(&self) -> *const UnrolledCell<T, P, N, G, A> {
+        NodePtr::as_ptr(&self.0)
+    }
+
+    pub fn new_with_capacity() -> Self
+    where
+        A: Default,
+    {
+        UnrolledList(NodePtr::new(UnrolledCell::new_with_capacity(A::default())))
+    }
+
+    // A new node in front of `next`, holding all of `elements`, which are stored back to front
+    pub(crate) fn from_node_parts(
+        elements: AtomicSharedVector<T, A>,
+        size: u32,
+        next: Option<Self>,
+    ) -> Self {
+        UnrolledList(NodePtr::new(UnrolledCell {
+            index: elements.len() as u32,
+            elements,
+            next,
+            size,
+        }))
+    }
+
+    // Moves the elements of the first node onto the front of `onto` in reverse order, so the
+    // last of them ends up first. A node this list owns outright, buffer and all, is moved
+    // over with its buffer reversed in place. A shared one is copied into a single new node
+    // instead of being consed one element at a time.
+    pub(crate) fn shift_front_node(&mut self, onto: &mut Self)
+    where
+        T: Clone,
+    {
+        let count = self.index() as usize;
+        if count == 0 {
+            return;
+        }
+
+        let mut node = match NodePtr::get_mut(&mut self.0) {
+            Some(cell) if cell.elements.is_unique() => {
+                // Popped elements are dropped in place, since the buffer is unique
+                cell.elements.truncate(count);
+                if let Some(elements) = cell.elements.as_mut_slice_if_unique() {
+                    elements.reverse();
+                }
+
+                let rest = match cell.next.take() {
+                    Some(next) => next,
+                    None => self.empty(),
+                };
+                core::mem::replace(self, rest)
+            }
+            _ => {
+                let mut vec = Vector::with_capacity_in(count, self.allocator().clone());
+                for value in self.elements()[0..count].iter().rev() {
+                    vec.push(value.clone());
+                }
+
+                let node = Self::from_node_parts(
+                    vec.into_shared_atomic(),
+                    self.size().max(count as u32),
+                    None,
+                );
+                *self = match &self.0.next {
+                    Some(next) => next.clone(),
+                    None => self.empty(),
+                };
+                node
+            }
+        };
+
+        core::mem::swap(onto, &mut node);
+        if !node.is_empty() {
+            NodePtr::get_mut(&mut onto.0).unwrap().next = Some(node);
+        }
     }
 
     // Get the strong count of the node in question
     pub fn strong_count(&self) -> usize {
-        P::strong_count(&self.0)
+        NodePtr::strong_count(&self.0)
     }
 
     // Compare the nodes for pointer equality
     pub fn ptr_eq(&self, other: &Self) -> bool {
-        P::ptr_eq(&self.0, &other.0)
+        NodePtr::ptr_eq(&self.0, &other.0)
     }
 
     pub fn shared_ptr_eq(&self, other: &Self) -> bool {
-        // P::ptr_eq(&self.0.elements, &other.0.elements) && self.0.index == other.0.index
+        // NodePtr::ptr_eq(&self.0.elements, &other.0.elements) && self.0.index == other.0.index
         self.0.elements.ptr_eq(&other.0.elements) && self.0.index == other.0.index
     }
 
     pub fn as_ptr_usize(&self) -> usize {
-        P::as_ptr(&self.0) as usize
+        NodePtr::as_ptr(&self.0) as usize
     }
 
     pub fn elements_as_ptr_usize(&self) -> usize {
-        // P::as_ptr(&self.0.elements) as usize
+        // NodePtr::as_ptr(&self.0.elements) as usize
         self.0.elements.as_ptr() as usize
     }
 
@@ -132,28 +440,22 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
         self.0.elements.iter().take(self.index() as _).rev()
     }
 
-    pub fn draining_iterator(self) -> DrainingConsumingWrapper<T, P, N, G> {
+    pub fn draining_iterator(self) -> DrainingConsumingWrapper<T, P, N, G, A> {
         DrainingConsumingWrapper(self.into_draining_node_iter().flat_map(|x| {
             let index = x.index();
-
-            P::try_unwrap(x.0)
-                .map(|mut cell| {
-                    if cell.elements.is_unique() {
-                        // Get the values inside
-                        std::mem::take(&mut cell.elements)
-                            .into_unique()
-                            .into_iter()
-                            .take(index as _)
-                            .rev()
-                    } else {
-                        Vector::new().into_iter().take(0).rev()
+            let allocator = x.allocator().clone();
+
+            match NodePtr::try_unwrap(x.0) {
+                Some(mut cell) if cell.elements.is_unique() => {
+                    // Get the values inside
+                    let empty = AtomicSharedVector::new_in(allocator.clone());
+                    match core::mem::replace(&mut cell.elements, empty).try_into_unique() {
+                        Ok(elements) => elements.into_iter().take(index as _).rev(),
+                        Err(_) => Vector::new_in(allocator).into_iter().take(0).rev(),
                     }
-
-                    // P::get_mut(&mut cell.elements)
-                    //     .map(|vec| std::mem::take(vec).into_iter().take(index).rev())
-                    //     .unwrap_or_else(|| Vec::new().into_iter().take(0).rev())
-                })
-                .unwrap_or_else(|| Vector::new().into_iter().take(0).rev())
+                }
+                _ => Vector::new_in(allocator).into_iter().take(0).rev(),
+            }
         }))
     }
 
@@ -171,40 +473,37 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
     // [0 1 2 3 4 5] -> [6 7 8 9 10]
     // [5 4 3 2 1] <- [10 9 8 7 6]
     // This should be O(n / 256)
-    pub fn reverse(self) -> Self {
+    pub fn reverse(self) -> Self
+    where
+        T: Clone,
+    {
+        Self::or_alloc_error(self.try_reverse())
+    }
+
+    pub fn try_reverse(self) -> Result<Self, AllocError>
+    where
+        T: Clone,
+    {
         let mut node_iter = self.into_node_iter();
         let mut left = node_iter.next().expect("This node should always exist");
         {
-            let inner = P::make_mut(&mut left.0);
-            // let elements_mut = P::make_mut(&mut inner.elements);
-
-            if inner.index() < inner.elements.len() {
-                // elements_mut.truncate(inner.index);
-                truncate(&mut inner.elements, inner.index as usize);
-            }
-
-            // elements_mut.reverse();
+            let inner = NodePtr::try_make_mut(&mut left.0)?;
+            inner.elements.try_truncate(inner.index as usize)?;
+            inner.elements.try_ensure_unique()?;
             inner.elements.reverse();
             inner.next = None;
         }
 
         for mut right in node_iter {
-            let cell = P::make_mut(&mut right.0);
-            // let elements_mut = P::make_mut(&mut cell.elements);
-
-            if cell.index() < cell.elements.len() {
-                // elements_mut.truncate(cell.index);
-
-                truncate(&mut cell.elements, cell.index as usize)
-            }
-
-            // elements_mut.reverse();
+            let cell = NodePtr::try_make_mut(&mut right.0)?;
+            cell.elements.try_truncate(cell.index as usize)?;
+            cell.elements.try_ensure_unique()?;
             cell.elements.reverse();
             cell.next = Some(left);
             left = right;
         }
 
-        left
+        Ok(left)
     }
 
     pub fn last(&self) -> Option<&T> {
@@ -212,18 +511,41 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
     }
 
     // Should be O(1) always
-    pub fn car(&self) -> Option<T> {
+    pub fn car(&self) -> Option<T>
+    where
+        T: Clone,
+    {
         self.0.car().cloned()
     }
 
-    pub fn cons(value: T, other: Self) -> Self {
-        UnrolledCell::cons(value, other)
+    pub fn cons(value: T, other: Self) -> Self
+    where
+        T: Clone,
+    {
+        Self::or_alloc_error(Self::try_cons(value, other))
+    }
+
+    pub fn try_cons(value: T, other: Self) -> Result<Self, AllocError>
+    where
+        T: Clone,
+    {
+        UnrolledCell::try_cons(value, other)
     }
 
-    pub fn take(&self, mut count: usize) -> Self {
+    pub fn take(&self, count: usize) -> Self
+    where
+        T: Clone,
+    {
+        Self::or_alloc_error(self.try_take(count))
+    }
+
+    pub fn try_take(&self, mut count: usize) -> Result<Self, AllocError>
+    where
+        T: Clone,
+    {
         // If the count of the vector
         if count == 0 {
-            return Self::new();
+            return self.try_empty();
         }
 
         let mut nodes = Vec::new();
@@ -231,37 +553,34 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
         // If we've asked for more elements than this list contains
         // and there aren't any more to follow, just return this list
         if count > self.0.index() && self.0.next.is_none() {
-            return self.clone();
+            return Ok(self.clone());
         }
 
         for mut node in self.clone().into_node_iter() {
             if count < node.0.index() {
-                let inner = P::make_mut(&mut node.0);
+                let inner = NodePtr::try_make_mut(&mut node.0)?;
                 // this is the new tail, point to the end
                 inner.next = None;
 
-                // We want to chop off whatever we need to
-                // let elements_mut = P::make_mut(&mut inner.elements);
-
-                inner.elements.ensure_unique();
-
-                // Grab the end of the vector, this will be the new backing
-                // let remaining = elements_mut.split_off(inner.index - count);
-
-                let remaining = split_off(&mut inner.elements, inner.index as usize - count);
-
+                // The new tail keeps the last `count` elements of the buffer in view.
+                // This only copies those elements, and only if the buffer is shared.
+                let index = inner.index as usize;
+                let allocator = inner.elements.allocator().clone();
+                let empty = AtomicSharedVector::try_with_capacity_in(0, allocator)?;
+                let elements = core::mem::replace(&mut inner.elements, empty);
+                inner.elements = elements
+                    .into_slice(index - count..index)
+                    .try_into_vector()?;
                 inner.index = count as _;
 
-                // *elements_mut = remaining;
-
-                inner.elements = remaining;
-
+                nodes.try_reserve(1).map_err(|_| AllocError)?;
                 nodes.push(node);
                 break;
             } else {
                 // Note: We might want to truncate the remaining
                 // elements of the vector.
                 count -= node.0.index();
+                nodes.try_reserve(1).map_err(|_| AllocError)?;
                 nodes.push(node);
             }
         }
@@ -273,13 +592,16 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
             let prev = nodes.pop().unwrap();
 
             if let Some(UnrolledList(cell)) = nodes.get_mut(i) {
-                P::make_mut(cell).next = Some(prev);
+                NodePtr::try_make_mut(cell)?.next = Some(prev);
             } else {
                 unreachable!()
             }
         }
 
-        nodes.pop().unwrap_or_default()
+        match nodes.pop() {
+            Some(list) => Ok(list),
+            None => self.try_empty(),
+        }
     }
 
     pub fn tail(&self, mut len: usize) -> Option<Self> {
@@ -290,7 +612,7 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
 
         for mut node in self.clone().into_node_iter() {
             if len < node.0.index() {
-                let inner = P::make_mut(&mut node.0);
+                let inner = NodePtr::make_mut(&mut node.0);
                 // this is the new tail, point to the end
                 // inner.next = None;
                 inner.index -= len as u32;
@@ -301,18 +623,71 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
         }
 
         if len == 0 {
-            return Some(Self::new());
+            return Some(self.empty());
         }
 
         None
     }
 
     /// Alias for cons_mut
-    pub fn push_front(&mut self, value: T) {
+    pub fn push_front(&mut self, value: T)
+    where
+        T: Clone,
+    {
         self.cons_mut(value)
     }
 
-    pub fn cons_mut(&mut self, value: T) {
+    pub fn cons_mut(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        Self::or_alloc_error(self.try_cons_mut(value))
+    }
+
+    // Like `cons_mut`, but never copies any elements, so it works for any `T`. The value
+    // is pushed in place when this node and its buffer are uniquely owned and have room
+    // for it, otherwise it goes into a new node in front of this one.
+    pub fn cons_mut_no_clone(&mut self, mut value: T) {
+        let size = self.size();
+
+        if self.0.index() == self.elements().len() && self.elements().len() < size as usize {
+            if let Some(inner) = NodePtr::get_mut(&mut self.0) {
+                match inner.elements.push_if_unique(value) {
+                    Ok(()) => {
+                        inner.index += 1;
+                        return;
+                    }
+                    Err(rejected) => value = rejected,
+                }
+            }
+        }
+
+        let (size, next) = if self.is_empty() {
+            (size, None)
+        } else if self.elements().len() >= size as usize {
+            (
+                size * UnrolledCell::<T, P, N, G, A>::GROWTH_RATE,
+                Some(self.clone()),
+            )
+        } else {
+            (size, Some(self.clone()))
+        };
+
+        let mut elements = Vector::with_capacity_in(size as usize, self.allocator().clone());
+        elements.push(value);
+
+        *self = UnrolledList(NodePtr::new(UnrolledCell {
+            index: 1,
+            elements: elements.into_shared_atomic(),
+            next,
+            size,
+        }));
+    }
+
+    pub fn try_cons_mut(&mut self, value: T) -> Result<(), AllocError>
+    where
+        T: Clone,
+    {
         let index = self.0.index;
 
         // This is saying: If we are pointing to a cell where the offset
@@ -320,39 +695,43 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
         // yet been truncated, we should attempt to eagerly do so, otherwise
         // we should fall back to the existing implementation.
         if self.0.index() < self.elements().len() {
-            // P::make_mut(&mut P::make_mut(&mut self.0).elements).truncate(index);
-            let cell = P::make_mut(&mut self.0);
-            truncate(&mut cell.elements, index as _);
+            NodePtr::try_make_mut(&mut self.0)?
+                .elements
+                .try_truncate(index as _)?;
         }
 
         // TODO cdr here is an issue - only moves the offset, no way to know that its full
         // Cause its not actually full
         if self.elements().len() > self.size() as usize - 1 {
             // Always initialize a vec with half the capacity of the previous one
-            let mut vec = Vector::with_capacity(self.size() as usize / 2);
+            let mut vec =
+                Vector::try_with_capacity_in(self.size() as usize / 2, self.allocator().clone())?;
+            vec.try_reserve(1)?;
             vec.push(value);
 
             // Make dummy node
             // return reference to this new node
-            let mut default = UnrolledList(P::new(UnrolledCell {
+            let mut default = UnrolledList(NodePtr::try_new(UnrolledCell {
                 index: 1,
                 elements: vec.into_shared_atomic(),
                 next: Some(self.clone()),
-                size: self.size() * UnrolledCell::<T, P, N, G>::GROWTH_RATE,
-            }));
+                size: self.size() * UnrolledCell::<T, P, N, G, A>::GROWTH_RATE,
+            })?);
 
-            std::mem::swap(self, &mut default);
+            core::mem::swap(self, &mut default);
+            Ok(())
         } else {
-            match P::get_mut(&mut self.0) {
+            match NodePtr::get_mut(&mut self.0) {
                 Some(inner) => {
                     if inner.elements.is_unique() {
-                        inner.elements.push(value);
+                        inner.elements.try_push(value)?;
                         inner.index += 1;
+                        Ok(())
                     } else {
-                        self.slow_path_new_node(value);
+                        self.slow_path_new_node(value)
                     }
 
-                    // match P::get_mut(&mut inner.elements) {
+                    // match NodePtr::get_mut(&mut inner.elements) {
                     //     Some(reference) => {
                     //         reference.push(value);
                     //         inner.index += 1;
@@ -363,42 +742,48 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
                     //     }
                     // }
                 }
-                None => {
-                    self.slow_path_new_node(value);
-                }
+                None => self.slow_path_new_node(value),
             }
         }
     }
 
-    fn slow_path_new_node(&mut self, value: T) {
+    fn slow_path_new_node(&mut self, value: T) -> Result<(), AllocError>
+    where
+        T: Clone,
+    {
         if self.elements().len() > self.size() as usize / 2 {
-            let mut vec = Vector::with_capacity(N as _);
+            let mut vec = Vector::try_with_capacity_in(N as _, self.allocator().clone())?;
+            vec.try_reserve(1)?;
             vec.push(value);
 
-            let mut default = UnrolledList(P::new(UnrolledCell {
+            let mut default = UnrolledList(NodePtr::try_new(UnrolledCell {
                 index: 1,
                 elements: vec.into_shared_atomic(),
                 next: Some(self.clone()),
                 size: N,
-            }));
+            })?);
 
-            std::mem::swap(self, &mut default);
+            core::mem::swap(self, &mut default);
+            Ok(())
         } else {
-            let inner = P::make_mut(&mut self.0);
-            inner.cons_mut(value);
+            let inner = NodePtr::try_make_mut(&mut self.0)?;
+            inner.try_cons_mut(value)
         }
     }
 
     // Should be O(1) always
     // Should also not have to clone
-    pub fn cdr(&self) -> Option<UnrolledList<T, P, N, G>> {
+    pub fn cdr(&self) -> Option<UnrolledList<T, P, N, G, A>> {
         self.0.cdr()
     }
 
     // Just pop off the internal value and move the index up
-    pub fn pop_front(&mut self) -> Option<T> {
-        let cell = P::make_mut(&mut self.0);
-        // let elements = P::make_mut(&mut cell.elements);
+    pub fn pop_front(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        let cell = NodePtr::make_mut(&mut self.0);
+        // let elements = NodePtr::make_mut(&mut cell.elements);
         // let ret = elements.pop();
         let ret = cell.elements.pop();
 
@@ -409,9 +794,8 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
         // If after we've popped, its empty, move the pointer to the
         // next one (if there is one)
         if cell.index == 0 {
-            if let Some(next) = &mut cell.next {
-                let mut next = std::mem::take(next);
-                std::mem::swap(self, &mut next);
+            if let Some(next) = cell.next.take() {
+                *self = next;
             }
         }
 
@@ -427,10 +811,10 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
     pub fn cdr_mut(&mut self) -> Option<&mut Self> {
         if self.0.index > 1 {
             // This will allocate a new cell
-            P::make_mut(&mut self.0).index -= 1;
+            NodePtr::make_mut(&mut self.0).index -= 1;
             Some(self)
         } else {
-            let inner = P::get_mut(&mut self.0);
+            let inner = NodePtr::get_mut(&mut self.0);
 
             match inner {
                 Some(inner) => {
@@ -441,7 +825,7 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
                             Some(self)
                         }
                         None => {
-                            *self = Self::new();
+                            *self = self.empty();
                             None
                         }
                     }
@@ -452,7 +836,7 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
                         Some(self)
                     }
                     None => {
-                        *self = Self::new();
+                        *self = self.empty();
                         None
                     }
                 },
@@ -478,27 +862,133 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
         assert!(self.does_node_satisfy_invariant())
     }
 
-    pub(crate) fn into_draining_node_iter(self) -> DrainingNodeIter<T, P, N, G> {
+    pub(crate) fn into_draining_node_iter(self) -> DrainingNodeIter<T, P, N, G, A> {
         DrainingNodeIter {
             cur: Some(self),
             _inner: PhantomData,
         }
     }
 
-    pub(crate) fn into_node_iter(self) -> NodeIter<T, P, N, G> {
+    pub(crate) fn into_node_iter(self) -> NodeIter<T, P, N, G, A> {
         NodeIter {
             cur: Some(self),
             _inner: PhantomData,
         }
     }
 
-    pub(crate) fn node_iter(&self) -> NodeIterRef<'_, T, P, N, G> {
+    pub(crate) fn node_iter(&self) -> NodeIterRef<'_, T, P, N, G, A> {
         NodeIterRef {
             cur: Some(self),
             _inner: PhantomData,
         }
     }
 
+    /// Calls `func` on every element, popped ones included, of the nodes that only this list
+    /// refers to, in buffers that only those nodes refer to. These are the elements that
+    /// dropping the list would drop.
+    pub(crate) fn for_each_owned_mut(&mut self, mut func: impl FnMut(&mut T)) {
+        let mut cur = NodePtr::get_mut(&mut self.0);
+
+        while let Some(cell) = cur {
+            if let Some(elements) = cell.elements.as_mut_slice_if_unique() {
+                elements.iter_mut().for_each(&mut func);
+            }
+
+            cur = cell
+                .next
+                .as_mut()
+                .and_then(|next| NodePtr::get_mut(&mut next.0));
+        }
+    }
+
+    pub(crate) fn trace(&self, tracer: &mut impl Tracer<T>) {
+        for node in self.node_iter() {
+            if !tracer.visit_node(NodeId(node.as_ptr() as usize)) {
+                return;
+            }
+
+            // The live elements, stored back to front, are the ones before the index
+            let elements = &node.elements()[..node.index() as usize];
+            let buffer = BufferId(elements.as_ptr() as usize);
+            let seen = tracer
+                .visit_buffer(buffer, elements.len())
+                .min(elements.len());
+            for element in elements[seen..].iter().rev() {
+                tracer.visit_element(element);
+            }
+        }
+    }
+
+    // The live elements of each node, which are stored back to front
+    pub(crate) fn chunks(&self) -> impl Iterator<Item = &'_ [T]> {
+        self.node_iter()
+            .map(|x| &x.elements()[0..x.index() as usize])
+    }
+
+    /// Compares this list against another one, a chunk at a time. `compare` is called on
+    /// parts of both lists of the same length, stored back to front, and the first result
+    /// that isn't `Equal` is returned. If all of them are, the shorter list is the lesser.
+    ///
+    /// Whenever both lists reach the same node at the same position, the rest of them is
+    /// equal, and parts that are the same region of a shared buffer are skipped. So comparing
+    /// lists that share a tail only looks at the elements in front of it. Like comparing a
+    /// value to itself, this treats the shared elements as equal even if they aren't, such
+    /// as `NaN`.
+    pub(crate) fn compare_chunks<P2: PointerFamily, const N2: u32, const G2: u32, A2>(
+        &self,
+        other: &UnrolledList<T, P2, N2, G2, A2>,
+        mut compare: impl FnMut(&[T], &[T]) -> Option<Ordering>,
+    ) -> Option<Ordering>
+    where
+        A2: Allocator + Clone,
+    {
+        let (mut left, mut right) = (Some(self), Some(other));
+        let (mut left_chunk, mut right_chunk): (&[T], &[T]) = (&[], &[]);
+
+        loop {
+            if left_chunk.is_empty() && right_chunk.is_empty() {
+                if let (Some(l), Some(r)) = (left, right) {
+                    if core::ptr::eq(l.as_ptr().cast::<()>(), r.as_ptr().cast()) {
+                        return Some(Ordering::Equal);
+                    }
+                }
+            }
+
+            while left_chunk.is_empty() {
+                let Some(node) = left else { break };
+                left_chunk = &node.elements()[0..node.index() as usize];
+                left = node.0.next.as_ref();
+            }
+
+            while right_chunk.is_empty() {
+                let Some(node) = right else { break };
+                right_chunk = &node.elements()[0..node.index() as usize];
+                right = node.0.next.as_ref();
+            }
+
+            match (left_chunk.is_empty(), right_chunk.is_empty()) {
+                (true, true) => return Some(Ordering::Equal),
+                (true, false) => return Some(Ordering::Less),
+                (false, true) => return Some(Ordering::Greater),
+                (false, false) => {}
+            }
+
+            let length = left_chunk.len().min(right_chunk.len());
+            let (left_rest, l) = left_chunk.split_at(left_chunk.len() - length);
+            let (right_rest, r) = right_chunk.split_at(right_chunk.len() - length);
+            left_chunk = left_rest;
+            right_chunk = right_rest;
+
+            // Zero sized elements all live at the same dangling address
+            if core::mem::size_of::<T>() == 0 || !core::ptr::eq(l.as_ptr(), r.as_ptr()) {
+                match compare(l, r) {
+                    Some(Ordering::Equal) => {}
+                    ordering => return ordering,
+                }
+            }
+        }
+    }
+
     // TODO investigate using this for the other iterators and see if its faster
     // Consuming iterators
     pub fn iter(&self) -> impl Iterator<Item = &'_ T> {
@@ -535,32 +1025,57 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
     }
 
     // Be able to in place mutate
-    pub fn append_mut(&mut self, other: Self) {
+    pub fn append_mut(&mut self, other: Self)
+    where
+        T: Clone,
+    {
         if other.elements().is_empty() {
             return;
         }
 
-        let mut default = UnrolledList::new();
-        std::mem::swap(self, &mut default);
+        let list = self.take_list();
+        *self = list.append(other);
+    }
+
+    pub fn try_append_mut(&mut self, other: Self) -> Result<(), AllocError>
+    where
+        T: Clone,
+    {
+        if other.elements().is_empty() {
+            return Ok(());
+        }
 
-        default = default.append(other);
-        std::mem::swap(self, &mut default);
+        // Append onto another reference, so that this list is left as it was on failure
+        *self = self.clone().try_append(other)?;
+        Ok(())
     }
 
     // Functional append
-    pub fn append(self, other: Self) -> Self {
+    pub fn append(self, other: Self) -> Self
+    where
+        T: Clone,
+    {
+        Self::or_alloc_error(self.try_append(other))
+    }
+
+    pub fn try_append(self, other: Self) -> Result<Self, AllocError>
+    where
+        T: Clone,
+    {
         if other.elements().is_empty() {
-            return self;
+            return Ok(self);
         }
 
-        self.into_node_iter()
-            .chain(other.into_node_iter())
-            .collect()
+        Ok(
+            Self::try_link_nodes(self.into_node_iter().chain(other.into_node_iter()))?
+                .expect("This node should always exist"),
+        )
     }
 
     // Figure out how in the heck you sort this
     pub fn sort(&mut self)
     where
+        T: Clone,
         T: Ord,
     {
         self.sort_by(Ord::cmp)
@@ -569,17 +1084,216 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
     // Figure out how you sort this
     pub fn sort_by<F>(&mut self, cmp: F)
     where
+        T: Clone,
         F: Fn(&T, &T) -> Ordering,
     {
-        let list = std::mem::take(self);
-        let mut vec = list.into_iter().collect::<Vec<_>>();
-        vec.sort_by(cmp);
-        *self = vec.into();
+        Self::or_alloc_error(self.try_sort_by(cmp))
+    }
+
+    // Sorts the elements within the existing nodes. Anything that could fail to
+    // allocate happens before the first element is moved, so on failure the list
+    // is left as it was.
+    pub fn try_sort_by<F>(&mut self, cmp: F) -> Result<(), AllocError>
+    where
+        T: Clone,
+        F: Fn(&T, &T) -> Ordering,
+    {
+        // If the comparison panics, leave behind an empty list rather than
+        // nodes whose elements have been moved out
+        struct OnUnwind<'a, L>(&'a mut L, fn(&mut L));
+
+        impl<L> Drop for OnUnwind<'_, L> {
+            fn drop(&mut self) {
+                (self.1)(self.0)
+            }
+        }
+
+        let len = self.len();
+        if len < 2 {
+            return Ok(());
+        }
+
+        let mut vector = Vector::try_with_capacity_in(len, self.allocator().clone())?;
+
+        // Make each node and its buffer unique, dropping the elements that have
+        // been moved past
+        let mut cur = Some(&mut *self);
+        while let Some(node) = cur {
+            let cell = NodePtr::try_make_mut(&mut node.0)?;
+            cell.elements.try_truncate(cell.index as usize)?;
+            cell.elements.try_ensure_unique()?;
+            cur = cell.next.as_mut();
+        }
+
+        let guard = OnUnwind(self, |list| {
+            let cell = NodePtr::make_mut(&mut list.0);
+            cell.index = 0;
+            cell.next = None;
+        });
+
+        let mut cur = Some(&mut *guard.0);
+        while let Some(node) = cur {
+            let cell = NodePtr::make_mut(&mut node.0);
+            vector.extend(cell.elements.drain(..).rev());
+            cur = cell.next.as_mut();
+        }
+
+        vector.sort_by(cmp);
+
+        // Every node has room for the elements it held before
+        let mut values = vector.into_iter();
+        let mut cur = Some(&mut *guard.0);
+        while let Some(node) = cur {
+            let cell = NodePtr::make_mut(&mut node.0);
+            cell.elements
+                .extend(values.by_ref().take(cell.index as usize));
+            cell.elements.reverse();
+            cur = cell.next.as_mut();
+        }
+
+        core::mem::forget(guard);
+        Ok(())
     }
 
     // Append a single value to the end
-    pub fn push_back(&mut self, value: T) {
-        self.extend(std::iter::once(value))
+    pub fn push_back(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        self.extend(core::iter::once(value))
+    }
+
+    pub fn try_push_back(&mut self, value: T) -> Result<(), AllocError>
+    where
+        T: Clone,
+    {
+        let other = Self::try_from_iter_in(core::iter::once(value), self.allocator().clone())?;
+        self.try_append_mut(other)
+    }
+
+    pub fn try_from_iter_in(
+        iter: impl IntoIterator<Item = T>,
+        allocator: A,
+    ) -> Result<Self, AllocError> {
+        let iter = iter.into_iter();
+        let mut vec = Vec::new();
+        vec.try_reserve(iter.size_hint().0)
+            .map_err(|_| AllocError)?;
+
+        for value in iter {
+            vec.try_reserve(1).map_err(|_| AllocError)?;
+            vec.push(value);
+        }
+
+        try_from_exact_size_iter(vec.into_iter(), allocator)
+    }
+
+    // The edits of a `TransientList`. Each node goes through `make_mut`, which copies it only
+    // while it is shared, so a node the batch has copied is changed in place from then on.
+
+    pub(crate) fn transient_cons(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        let size = self.size();
+        let cell = NodePtr::make_mut(&mut self.0);
+        cell.elements.truncate(cell.index());
+
+        if cell.index() < size as usize {
+            cell.elements.push(value);
+            cell.index += 1;
+            return;
+        }
+
+        let size = size * UnrolledCell::<T, P, N, G, A>::GROWTH_RATE;
+        let mut elements =
+            AtomicSharedVector::with_capacity_in(size as usize, self.allocator().clone());
+        elements.push(value);
+
+        let node = NodePtr::new(UnrolledCell {
+            index: 1,
+            elements,
+            next: None,
+            size,
+        });
+        let next = core::mem::replace(&mut self.0, node);
+        NodePtr::make_mut(&mut self.0).next = Some(UnrolledList(next));
+    }
+
+    pub(crate) fn transient_pop_front(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        if self.is_empty() {
+            return None;
+        }
+
+        let cell = NodePtr::make_mut(&mut self.0);
+        cell.elements.truncate(cell.index());
+        let value = cell.elements.pop();
+        cell.index -= 1;
+
+        if cell.index == 0 {
+            if let Some(next) = cell.next.take() {
+                *self = next;
+            }
+        }
+
+        value
+    }
+
+    // Makes every node on the way to the last one unique, and returns the last one
+    fn last_mut(&mut self) -> &mut UnrolledCell<T, P, N, G, A> {
+        let mut node = self;
+        loop {
+            let cell = NodePtr::make_mut(&mut node.0);
+            match cell.next {
+                Some(ref mut next) => node = next,
+                None => return cell,
+            }
+        }
+    }
+
+    pub(crate) fn transient_push_back(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        let allocator = self.allocator().clone();
+        let last = self.last_mut();
+        last.elements.truncate(last.index());
+
+        // The elements of a node are stored back to front
+        if last.index() < last.size as usize {
+            last.elements.insert(0, value);
+            last.index += 1;
+        } else {
+            let mut elements = AtomicSharedVector::with_capacity_in(N as usize, allocator);
+            elements.push(value);
+
+            last.next = Some(UnrolledList(NodePtr::new(UnrolledCell {
+                index: 1,
+                elements,
+                next: None,
+                size: N,
+            })));
+        }
+    }
+
+    pub(crate) fn transient_append(&mut self, other: Self) {
+        if other.is_empty() {
+            return;
+        }
+
+        if self.is_empty() {
+            *self = other;
+        } else {
+            self.last_mut().next = Some(other);
+        }
+    }
+
+    pub(crate) fn transient_extend(&mut self, iter: impl IntoIterator<Item = T>) {
+        let allocator = self.allocator().clone();
+        self.transient_append(from_vec(iter.into_iter().collect(), allocator))
     }
 
     pub fn is_empty(&self) -> bool {
@@ -592,14 +1306,14 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledList<T, P,
 }
 
 // Don't blow the stack
-impl<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> Drop
-    for UnrolledCell<T, P, N, G>
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Drop
+    for UnrolledCell<T, P, N, G, A>
 {
     fn drop(&mut self) {
         let mut cur = self.next.take().map(|x| x.0);
         loop {
             match cur {
-                Some(r) => match P::try_unwrap(r) {
+                Some(r) => match NodePtr::try_unwrap(r) {
                     Some(UnrolledCell { ref mut next, .. }) => cur = next.take().map(|x| x.0),
                     _ => return,
                 },
@@ -609,15 +1323,24 @@ impl<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> Drop
     }
 }
 
-#[repr(C)]
-pub struct UnrolledCell<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> {
+// Aligned so that the low bits of pointers to nodes are free, see `GenericList::into_raw`
+#[repr(C, align(8))]
+pub struct UnrolledCell<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+> {
     index: u32,
-    pub(crate) elements: AtomicSharedVector<T>,
-    pub(crate) next: Option<UnrolledList<T, P, N, G>>,
+    pub(crate) elements: AtomicSharedVector<T, A>,
+    pub(crate) next: Option<UnrolledList<T, P, N, G, A>>,
     size: u32,
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Clone for UnrolledCell<T, P, N, G> {
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Clone
+    for UnrolledCell<T, P, N, G, A>
+{
     fn clone(&self) -> Self {
         Self {
             index: self.index,
@@ -628,34 +1351,44 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Clone for UnrolledC
     }
 }
 
-impl<T: Clone + std::fmt::Debug, P: PointerFamily, const N: u32, const G: u32> std::fmt::Debug
-    for UnrolledList<T, P, N, G>
+impl<T: core::fmt::Debug, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    core::fmt::Debug for UnrolledList<T, P, N, G, A>
 {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         f.debug_list().entries(self).finish()
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledCell<T, P, N, G> {
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    UnrolledCell<T, P, N, G, A>
+{
     const GROWTH_RATE: u32 = if G == 0 { 1 } else { G };
 
-    fn new() -> Self {
-        UnrolledCell {
+    fn try_new_in(allocator: A) -> Result<Self, AllocError> {
+        #[cfg(not(loom))]
+        let elements = match AtomicSharedVector::shared_empty() {
+            Some(elements) => elements,
+            None => AtomicSharedVector::try_with_capacity_in(0, allocator)?,
+        };
+        #[cfg(loom)]
+        let elements = AtomicSharedVector::try_with_capacity_in(0, allocator)?;
+
+        Ok(UnrolledCell {
             index: 0,
-            elements: AtomicSharedVector::new(),
+            elements,
             next: None,
             size: N,
-        }
+        })
     }
 
     fn index(&self) -> usize {
         self.index as _
     }
 
-    fn new_with_capacity() -> Self {
+    fn new_with_capacity(allocator: A) -> Self {
         UnrolledCell {
             index: 0,
-            elements: AtomicSharedVector::with_capacity(N as _),
+            elements: AtomicSharedVector::with_capacity_in(N as _, allocator),
             next: None,
             size: N,
         }
@@ -671,9 +1404,9 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledCell<T, P,
 
     // This _does_ create a boxed representation of the next item. Its possible we don't actually
     // need to do this, but for now we do
-    fn cdr(&self) -> Option<UnrolledList<T, P, N, G>> {
+    fn cdr(&self) -> Option<UnrolledList<T, P, N, G, A>> {
         if self.index > 1 {
-            Some(UnrolledList(P::new(self.advance_cursor())))
+            Some(UnrolledList(NodePtr::new(self.advance_cursor())))
         } else {
             self.next.clone()
         }
@@ -689,67 +1422,89 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> UnrolledCell<T, P,
     }
 
     // TODO make this better
-    fn cons_mut(&mut self, value: T) {
-        // let reference = P::make_mut(&mut self.elements);
-        self.elements.push(value);
+    fn try_cons_mut(&mut self, value: T) -> Result<(), AllocError>
+    where
+        T: Clone,
+    {
+        // let reference = NodePtr::make_mut(&mut self.elements);
+        self.elements.try_push(value)?;
         // reference.push(value);
         self.index += 1;
+        Ok(())
     }
 
     // Spill over the values to a new node
     // otherwise, copy the node and spill over
-    fn cons(value: T, mut cdr: UnrolledList<T, P, N, G>) -> UnrolledList<T, P, N, G> {
+    fn try_cons(
+        value: T,
+        mut cdr: UnrolledList<T, P, N, G, A>,
+    ) -> Result<UnrolledList<T, P, N, G, A>, AllocError>
+    where
+        T: Clone,
+    {
         let size = cdr.size();
 
         if cdr.elements().len() > size as usize - 1 {
-            UnrolledList(P::new(UnrolledCell {
+            let mut elements =
+                AtomicSharedVector::try_with_capacity_in(1, cdr.allocator().clone())?;
+            elements.try_push(value)?;
+
+            Ok(UnrolledList(NodePtr::try_new(UnrolledCell {
                 index: 1,
-                // elements: P::new(vec![value]),
-                elements: crate::arc_vector![value],
+                elements,
                 next: Some(cdr),
                 size: size * Self::GROWTH_RATE,
-            }))
+            })?))
         } else {
-            let inner = P::make_mut(&mut cdr.0);
-            // let elements = P::make_mut(&mut inner.elements);
+            let inner = NodePtr::try_make_mut(&mut cdr.0)?;
+            // let elements = NodePtr::make_mut(&mut inner.elements);
 
-            inner.index += 1;
+            // Drop the elements that have already been moved past, the new
+            // value goes right after the current car
+            inner.elements.try_truncate(inner.index as _)?;
             // elements.push(value);
 
-            inner.elements.push(value);
-            cdr
+            inner.elements.try_push(value)?;
+            inner.index += 1;
+            Ok(cdr)
         }
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Extend<T>
-    for UnrolledList<T, P, N, G>
+impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Extend<T>
+    for UnrolledList<T, P, N, G, A>
 {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
-        self.append_mut(iter.into_iter().collect())
+        let allocator = self.allocator().clone();
+        self.append_mut(from_vec(iter.into_iter().collect(), allocator))
     }
 }
 
-pub(crate) struct DrainingNodeIter<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32>
-{
-    cur: Option<UnrolledList<T, P, N, G>>,
+pub(crate) struct DrainingNodeIter<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+> {
+    cur: Option<UnrolledList<T, P, N, G, A>>,
     _inner: PhantomData<T>,
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
-    for DrainingNodeIter<T, P, N, G>
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+    for DrainingNodeIter<T, P, N, G, A>
 {
-    type Item = UnrolledList<T, P, N, G>;
+    type Item = UnrolledList<T, P, N, G, A>;
     fn next(&mut self) -> Option<Self::Item> {
         // This is doing allocation. Don't want that.
 
-        if let Some(mut _self) = std::mem::take(&mut self.cur) {
+        if let Some(mut _self) = core::mem::take(&mut self.cur) {
             if let Some(next) = _self.0.next.as_ref() {
                 // If we can, drop these values!
-                // if next.strong_count() == 1 && P::strong_count(&next.0.elements) == 1 {
+                // if next.strong_count() == 1 && NodePtr::strong_count(&next.0.elements) == 1 {
                 if next.strong_count() == 1 && next.0.elements.is_unique() {
                     // self.cur = _self.0.next.clone();
-                    self.cur = P::get_mut(&mut _self.0).and_then(|x| x.next.take());
+                    self.cur = NodePtr::get_mut(&mut _self.0).and_then(|x| x.next.take());
                 } else {
                     self.cur = None
                 }
@@ -774,14 +1529,14 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
         //     }
 
         //     // If we can, drop these values!
-        //     if next.strong_count() == 1 && P::strong_count(&next.0.elements) == 1 {
+        //     if next.strong_count() == 1 && NodePtr::strong_count(&next.0.elements) == 1 {
         //         // self.cur = _self.0.next.clone();
-        //         // self.cur = P::get_mut(&mut _self.0).and_then(|x| x.next.take());
+        //         // self.cur = NodePtr::get_mut(&mut _self.0).and_then(|x| x.next.take());
         //         // todo!()
 
         //         let mut value = _self
         //             .as_mut()
-        //             .and_then(|x| P::get_mut(&mut x.0).and_then(|x| x.next.take()));
+        //             .and_then(|x| NodePtr::get_mut(&mut x.0).and_then(|x| x.next.take()));
 
         //         std::mem::swap(&mut self.cur, &mut value);
 
@@ -797,15 +1552,23 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
     }
 }
 
-pub(crate) struct NodeIter<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> {
-    cur: Option<UnrolledList<T, P, N, G>>,
+pub(crate) struct NodeIter<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+> {
+    cur: Option<UnrolledList<T, P, N, G, A>>,
     _inner: PhantomData<T>,
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator for NodeIter<T, P, N, G> {
-    type Item = UnrolledList<T, P, N, G>;
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+    for NodeIter<T, P, N, G, A>
+{
+    type Item = UnrolledList<T, P, N, G, A>;
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(_self) = std::mem::take(&mut self.cur) {
+        if let Some(_self) = core::mem::take(&mut self.cur) {
             self.cur = _self.0.next.clone();
             Some(_self)
         } else {
@@ -814,16 +1577,22 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator for NodeIt
     }
 }
 
-pub(crate) struct NodeIterRef<'a, T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32>
-{
-    cur: Option<&'a UnrolledList<T, P, N, G>>,
+pub(crate) struct NodeIterRef<
+    'a,
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+> {
+    cur: Option<&'a UnrolledList<T, P, N, G, A>>,
     _inner: PhantomData<T>,
 }
 
-impl<'a, T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> Iterator
-    for NodeIterRef<'a, T, P, N, G>
+impl<'a, T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+    for NodeIterRef<'a, T, P, N, G, A>
 {
-    type Item = &'a UnrolledList<T, P, N, G>;
+    type Item = &'a UnrolledList<T, P, N, G, A>;
     fn next(&mut self) -> Option<Self::Item> {
         if let Some(_self) = &self.cur {
             let ret_val = self.cur;
@@ -836,14 +1605,15 @@ impl<'a, T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> Itera
 }
 
 pub struct DrainingConsumingWrapper<
-    T: Clone + 'static,
+    T,
     P: PointerFamily,
     const N: u32,
     const G: u32,
->(DrainingConsumingIter<T, P, N, G>);
+    A: Allocator + Clone + 'static = Global,
+>(DrainingConsumingIter<T, P, N, G, A>);
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
-    for DrainingConsumingWrapper<T, P, N, G>
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+    for DrainingConsumingWrapper<T, P, N, G, A>
 {
     type Item = T;
 
@@ -867,12 +1637,16 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
     }
 }
 
-pub struct ConsumingWrapper<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32>(
-    ConsumingIter<T, P, N, G>,
-);
+pub struct ConsumingWrapper<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+>(ConsumingIter<T, P, N, G, A>);
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
-    for ConsumingWrapper<T, P, N, G>
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+    for ConsumingWrapper<T, P, N, G, A>
 {
     type Item = T;
 
@@ -896,12 +1670,12 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
     }
 }
 
-// struct OwnedNodeIterator<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32> {
-//     list: UnrolledCell<T, P, N, G>,
+// struct OwnedNodeIterator<T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> {
+//     list: UnrolledCell<T, P, N, G, A>,
 // }
 
-// impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
-//     for OwnedNodeIterator<T, P, N, G>
+// impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+//     for OwnedNodeIterator<T, P, N, G, A>
 // {
 //     type Item = T;
 
@@ -916,11 +1690,11 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
 //     }
 // }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
-    for UnrolledList<T, P, N, G>
+impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> IntoIterator
+    for UnrolledList<T, P, N, G, A>
 {
     type Item = T;
-    type IntoIter = ConsumingWrapper<T, P, N, G>;
+    type IntoIter = ConsumingWrapper<T, P, N, G, A>;
 
     fn into_iter(self) -> Self::IntoIter {
         ConsumingWrapper(self.into_node_iter().flat_map(move |mut x| {
@@ -935,11 +1709,11 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
             // we need to reallocate the whole vec _just_ to get owned
             // references.
             /*
-            let cell = P::make_mut(&mut x.0);
+            let cell = NodePtr::make_mut(&mut x.0);
 
-            match P::get_mut(&mut cell.elements) {
+            match NodePtr::get_mut(&mut cell.elements) {
                 Some(vec) => {
-                    let elements = std::mem::take(vec);
+                    let elements = core::mem::take(vec);
                     MaybeCloned::Owned(elements.into_iter().take(x.index()).rev())
                 }
                 None => MaybeCloned::Cloned(OwnedNodeIterator {
@@ -948,9 +1722,10 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
             }
             */
 
-            let cell = P::make_mut(&mut x.0);
+            let cell = NodePtr::make_mut(&mut x.0);
 
-            let v = std::mem::take(&mut cell.elements);
+            let empty = AtomicSharedVector::new_in(cell.elements.allocator().clone());
+            let v = core::mem::replace(&mut cell.elements, empty);
 
             let unique = v.into_unique();
 
@@ -962,7 +1737,7 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
 
             // todo!()
 
-            // let vec = P::make_mut(&mut cell.elements);
+            // let vec = NodePtr::make_mut(&mut cell.elements);
 
             // let elements = std::mem::take(vec);
             // elements.into_iter().take(x.index()).rev()
@@ -971,12 +1746,17 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
 }
 
 // TODO have this also expose TryFold
-pub struct IterWrapper<'a, T: Clone + 'static, P: PointerFamily, const N: u32, const G: u32>(
-    RefIter<'a, T, P, N, G>,
-);
+pub struct IterWrapper<
+    'a,
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone + 'static = Global,
+>(RefIter<'a, T, P, N, G, A>);
 
-impl<'a, T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
-    for IterWrapper<'a, T, P, N, G>
+impl<'a, T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> Iterator
+    for IterWrapper<'a, T, P, N, G, A>
 {
     type Item = &'a T;
 
@@ -1000,11 +1780,11 @@ impl<'a, T: Clone, P: PointerFamily, const N: u32, const G: u32> Iterator
     }
 }
 
-impl<'a, T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
-    for &'a UnrolledList<T, P, N, G>
+impl<'a, T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> IntoIterator
+    for &'a UnrolledList<T, P, N, G, A>
 {
     type Item = &'a T;
-    type IntoIter = IterWrapper<'a, T, P, N, G>;
+    type IntoIter = IterWrapper<'a, T, P, N, G, A>;
 
     #[inline(always)]
     fn into_iter(self) -> Self::IntoIter {
@@ -1015,7 +1795,61 @@ impl<'a, T: Clone, P: PointerFamily, const N: u32, const G: u32> IntoIterator
     }
 }
 
-struct ExponentialChunks<I, const N: u32, const G: u32>
+/// Walks the nodes of a list and a sequence of slices in step, yielding the parts of the
+/// current chunks that line up with each other, which always have the same length. Nodes
+/// store their elements back to front, so their parts are taken from the end, while the
+/// parts of the slices are taken from the start.
+pub(crate) struct ChunkPairs<'a, T, L, R> {
+    left: L,
+    right: R,
+    left_chunk: &'a [T],
+    right_chunk: &'a [T],
+}
+
+impl<'a, T, L, R> ChunkPairs<'a, T, L, R>
+where
+    L: Iterator<Item = &'a [T]>,
+    R: Iterator<Item = &'a [T]>,
+{
+    pub(crate) fn new(left: L, right: R) -> Self {
+        Self {
+            left,
+            right,
+            left_chunk: &[],
+            right_chunk: &[],
+        }
+    }
+}
+
+impl<'a, T, L, R> Iterator for ChunkPairs<'a, T, L, R>
+where
+    L: Iterator<Item = &'a [T]>,
+    R: Iterator<Item = &'a [T]>,
+{
+    type Item = (&'a [T], &'a [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.left_chunk.is_empty() {
+            self.left_chunk = self.left.next()?;
+        }
+
+        while self.right_chunk.is_empty() {
+            self.right_chunk = self.right.next()?;
+        }
+
+        let length = self.left_chunk.len().min(self.right_chunk.len());
+
+        let (rest, left) = self.left_chunk.split_at(self.left_chunk.len() - length);
+        self.left_chunk = rest;
+
+        let (right, rest) = self.right_chunk.split_at(length);
+        self.right_chunk = rest;
+
+        Some((left, right))
+    }
+}
+
+struct ExponentialChunks<I, const N: u32, const G: u32, A: Allocator + Clone>
 where
     I: Iterator,
 {
@@ -1023,13 +1857,14 @@ where
     size: usize,
     length: usize,
     running_sum: usize,
+    allocator: A,
 }
 
-impl<I, const N: u32, const G: u32> ExponentialChunks<I, N, G>
+impl<I, const N: u32, const G: u32, A: Allocator + Clone> ExponentialChunks<I, N, G, A>
 where
     I: Iterator,
 {
-    fn new(iter: I, length: usize, mut size: usize) -> Self {
+    fn new(iter: I, length: usize, mut size: usize, allocator: A) -> Self {
         let mut running_sum = size;
 
         while running_sum < length {
@@ -1042,15 +1877,16 @@ where
             size,
             length,
             running_sum: running_sum - size,
+            allocator,
         }
     }
 }
 
-impl<I, const N: u32, const G: u32> Iterator for ExponentialChunks<I, N, G>
+impl<I, const N: u32, const G: u32, A: Allocator + Clone> Iterator for ExponentialChunks<I, N, G, A>
 where
     I: Iterator,
 {
-    type Item = (usize, Vector<I::Item>);
+    type Item = Result<(usize, Vector<I::Item, A>), AllocError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let chunk_size = if self.length > self.running_sum {
@@ -1061,7 +1897,13 @@ where
 
         let iter = self.iter.by_ref().take(chunk_size);
 
-        let mut chunk = Vector::with_capacity(iter.size_hint().1.unwrap_or(0));
+        let mut chunk = match Vector::try_with_capacity_in(
+            iter.size_hint().1.unwrap_or(0),
+            self.allocator.clone(),
+        ) {
+            Ok(chunk) => chunk,
+            Err(e) => return Some(Err(e)),
+        };
 
         for item in iter {
             chunk.push(item);
@@ -1077,29 +1919,66 @@ where
         self.size /= G as usize;
         self.length -= result.len();
 
-        Some((size, result))
+        Some(Ok((size, result)))
     }
 }
 
-fn from_vec<T: Clone, P: PointerFamily, const N: u32, const G: u32>(
+fn from_vec<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>(
     vec: Vec<T>,
-) -> UnrolledList<T, P, N, G> {
-    let length = vec.len();
+    allocator: A,
+) -> UnrolledList<T, P, N, G, A> {
+    from_exact_size_iter(vec.into_iter(), allocator)
+}
+
+fn from_exact_size_iter<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>(
+    iter: impl ExactSizeIterator<Item = T>,
+    allocator: A,
+) -> UnrolledList<T, P, N, G, A> {
+    try_from_exact_size_iter(iter, allocator)
+        .unwrap_or_else(|_| node_alloc_error::<T, P, N, G, A>())
+}
 
-    let mut pairs: SmallVec<[UnrolledList<_, _, N, G>; 16]> =
-        ExponentialChunks::<_, N, G>::new(vec.into_iter(), length, N as usize)
-            .map(|(size, x)| {
-                let mut elements = x;
+// Collects the nodes of a spine, failing instead of aborting if they don't fit
+fn try_collect_spine<V>(
+    iter: impl IntoIterator<Item = Result<V, AllocError>>,
+) -> Result<SmallVec<[V; 16]>, AllocError> {
+    let mut spine = SmallVec::new();
+    for item in iter {
+        let item = item?;
+        spine.try_reserve(1).map_err(|_| AllocError)?;
+        spine.push(item);
+    }
+    Ok(spine)
+}
+
+fn try_from_exact_size_iter<
+    T,
+    P: PointerFamily,
+    const N: u32,
+    const G: u32,
+    A: Allocator + Clone,
+>(
+    iter: impl ExactSizeIterator<Item = T>,
+    allocator: A,
+) -> Result<UnrolledList<T, P, N, G, A>, AllocError> {
+    let length = iter.len();
+
+    let mut pairs: SmallVec<[UnrolledList<_, _, N, G, A>; 16]> = try_collect_spine(
+        ExponentialChunks::<_, N, G, A>::new(iter, length, N as usize, allocator.clone()).map(
+            |chunk| {
+                let (size, mut elements) = chunk?;
                 elements.reverse();
 
-                UnrolledList(P::new(UnrolledCell {
+                NodePtr::try_new(UnrolledCell {
                     index: elements.len() as u32,
                     elements: elements.into_shared_atomic(),
                     next: None,
                     size: size as u32,
-                }))
-            })
-            .collect();
+                })
+                .map(UnrolledList)
+            },
+        ),
+    )?;
 
     let mut rev_iter = (0..pairs.len()).rev();
     rev_iter.next();
@@ -1108,7 +1987,7 @@ fn from_vec<T: Clone, P: PointerFamily, const N: u32, const G: u32>(
         let prev = pairs.pop().unwrap();
 
         if let Some(UnrolledList(cell)) = pairs.get_mut(i) {
-            P::get_mut::<UnrolledCell<T, P, N, G>>(cell)
+            NodePtr::get_mut(cell)
                 .expect("Only one owner allowed in construction")
                 .next = Some(prev);
         } else {
@@ -1116,26 +1995,21 @@ fn from_vec<T: Clone, P: PointerFamily, const N: u32, const G: u32>(
         }
     }
 
-    pairs.pop().unwrap_or_else(UnrolledList::new)
-}
-
-// and we'll implement FromIterator
-// TODO specialize this for the into version?
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> FromIterator<T>
-    for UnrolledList<T, P, N, G>
-{
-    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let reversed: Vec<_> = iter.into_iter().collect();
-        from_vec(reversed)
+    match pairs.pop() {
+        Some(list) => Ok(list),
+        None => UnrolledList::try_new_in(allocator),
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> FromIterator<UnrolledList<T, P, N, G>>
-    for UnrolledList<T, P, N, G>
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    UnrolledList<T, P, N, G, A>
 {
-    fn from_iter<I: IntoIterator<Item = UnrolledList<T, P, N, G>>>(iter: I) -> Self {
-        // Links up the nodes
-        let mut nodes: SmallVec<[_; 16]> = iter.into_iter().collect();
+    // Links up the nodes, returning `None` if there weren't any
+    fn try_link_nodes(iter: impl IntoIterator<Item = Self>) -> Result<Option<Self>, AllocError>
+    where
+        T: Clone,
+    {
+        let mut nodes = try_collect_spine(iter.into_iter().map(Ok))?;
 
         let mut rev_iter = (0..nodes.len()).rev();
         rev_iter.next();
@@ -1146,95 +2020,230 @@ impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> FromIterator<Unroll
 
             if let Some(UnrolledList(cell)) = nodes.get_mut(i) {
                 // Check if this node can fit entirely into the previous one
-                if cell.elements.len() + prev.0.elements.len() <= prev.0.size as usize {
-                    let left_inner = P::make_mut(cell);
-                    let right_inner = P::make_mut(&mut prev.0);
-
-                    // let left_vector = P::make_mut(&mut left_inner.elements);
-                    // let right_vector = P::make_mut(&mut right_inner.elements);
+                if cell.elements.len() + prev.0.elements.len() <= prev.size() as usize {
+                    let left_inner = NodePtr::try_make_mut(cell)?;
+                    let right_inner = NodePtr::try_make_mut(&mut prev.0)?;
 
                     // Drop the useless elements
-                    if left_inner.index() < left_inner.elements.len() {
-                        // left_vector.truncate(left_inner.index);
-
-                        truncate(&mut left_inner.elements, left_inner.index as _);
-                    }
-
-                    // TODO
-                    if right_inner.index() < right_inner.elements.len() {
-                        // right_vector.truncate(right_inner.index);
-
-                        truncate(&mut right_inner.elements, right_inner.index as _);
-                    }
+                    left_inner.elements.try_truncate(left_inner.index as _)?;
+                    right_inner.elements.try_truncate(right_inner.index as _)?;
 
                     // Perform the actual move of the values
-                    // right_vector.append(left_vector);
-
-                    right_inner.elements.append(&mut left_inner.elements);
+                    right_inner.elements.try_append(&mut left_inner.elements)?;
 
                     // Swap the locations now after we've done the update
-                    std::mem::swap(&mut left_inner.elements, &mut right_inner.elements);
+                    core::mem::swap(&mut left_inner.elements, &mut right_inner.elements);
 
                     // Adjust the indices accordingly
                     left_inner.index = left_inner.elements.len() as u32;
                     right_inner.index = 0;
 
                     // Update this node to now point to the right nodes tail
-                    std::mem::swap(&mut left_inner.next, &mut right_inner.next);
+                    core::mem::swap(&mut left_inner.next, &mut right_inner.next);
                 } else {
-                    P::make_mut(cell).next = Some(prev);
+                    NodePtr::try_make_mut(cell)?.next = Some(prev);
                 }
             } else {
                 unreachable!()
             }
         }
 
-        nodes.pop().unwrap_or_else(Self::new)
+        Ok(nodes.pop())
     }
 }
 
-impl<'a, T: 'a + Clone, P: 'a + PointerFamily, const N: u32, const G: u32>
-    FromIterator<&'a UnrolledList<T, P, N, G>> for UnrolledList<T, P, N, G>
+// Links up freshly made nodes front to back, returning `None` if there weren't any
+fn try_link_cells<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>(
+    cells: impl IntoIterator<Item = UnrolledCell<T, P, N, G, A>>,
+) -> Result<Option<UnrolledList<T, P, N, G, A>>, AllocError> {
+    let mut cells = try_collect_spine(cells.into_iter().map(Ok))?;
+    let mut list = None;
+
+    while let Some(mut cell) = cells.pop() {
+        cell.next = list;
+        list = Some(UnrolledList(NodePtr::try_new(cell)?));
+    }
+
+    Ok(list)
+}
+
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    UnrolledList<T, P, N, G, A>
 {
-    fn from_iter<I: IntoIterator<Item = &'a UnrolledList<T, P, N, G>>>(iter: I) -> Self {
+    // Copies the spine into nodes of another pointer family. The element buffers are
+    // atomically reference counted no matter the family, so they can all be shared.
+    pub(crate) fn convert<P2: PointerFamily>(&self) -> UnrolledList<T, P2, N, G, A> {
+        let cells = self.node_iter().map(|node| UnrolledCell {
+            index: node.0.index,
+            elements: node.0.elements.clone(),
+            next: None,
+            size: node.0.size,
+        });
+
+        try_link_cells(cells)
+            .unwrap_or_else(|_| node_alloc_error::<T, P2, N, G, A>())
+            .expect("A list always has at least one node")
+    }
+
+    pub(crate) fn rechunk<const N2: u32, const G2: u32>(&self) -> UnrolledList<T, P, N2, G2, A>
+    where
+        T: Clone,
+    {
+        Self::or_alloc_error(self.try_rechunk())
+    }
+
+    // Copies the spine into nodes of another configuration. A buffer is shared whenever
+    // it fits in a node of the new configuration, which is always the case once nodes
+    // can grow. Otherwise the live elements are copied out into nodes of `N2` elements.
+    pub(crate) fn try_rechunk<const N2: u32, const G2: u32>(
+        &self,
+    ) -> Result<UnrolledList<T, P, N2, G2, A>, AllocError>
+    where
+        T: Clone,
+    {
+        let mut cells: SmallVec<[UnrolledCell<T, P, N2, G2, A>; 16]> = SmallVec::new();
+        let mut push = |cell| {
+            cells.try_reserve(1).map_err(|_| AllocError)?;
+            cells.push(cell);
+            Ok::<_, AllocError>(())
+        };
+
+        for node in self.node_iter() {
+            if G2 > 1 || node.elements().len() <= N2 as usize {
+                push(UnrolledCell {
+                    index: node.0.index,
+                    elements: node.0.elements.clone(),
+                    next: None,
+                    size: if G2 > 1 { node.size().max(N2) } else { N2 },
+                })?;
+                continue;
+            }
+
+            // Elements are stored back to front, so the last chunk holds the front of the
+            // node, and is the one left partial. Every node that gets split leaves a partial
+            // node behind like that, so unlike a list built from an iterator, partial nodes
+            // can end up anywhere in the new list.
+            for chunk in node.elements()[..node.index() as usize]
+                .chunks(N2 as usize)
+                .rev()
+            {
+                let mut elements =
+                    Vector::try_with_capacity_in(chunk.len(), self.allocator().clone())?;
+                elements.extend_from_slice(chunk);
+
+                push(UnrolledCell {
+                    index: chunk.len() as u32,
+                    elements: elements.into_shared_atomic(),
+                    next: None,
+                    size: N2,
+                })?;
+            }
+        }
+
+        match try_link_cells(cells)? {
+            Some(list) => Ok(list),
+            None => UnrolledList::try_new_in(self.allocator().clone()),
+        }
+    }
+}
+
+// and we'll implement FromIterator
+// TODO specialize this for the into version?
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + Default>
+    FromIterator<T> for UnrolledList<T, P, N, G, A>
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let reversed: Vec<_> = iter.into_iter().collect();
+        from_vec(reversed, A::default())
+    }
+}
+
+impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + Default>
+    FromIterator<UnrolledList<T, P, N, G, A>> for UnrolledList<T, P, N, G, A>
+{
+    fn from_iter<I: IntoIterator<Item = UnrolledList<T, P, N, G, A>>>(iter: I) -> Self {
+        Self::or_alloc_error(Self::try_link_nodes(iter)).unwrap_or_else(Self::new)
+    }
+}
+
+impl<
+        'a,
+        T: 'a + Clone,
+        P: 'a + PointerFamily,
+        const N: u32,
+        const G: u32,
+        A: Allocator + Clone + Default,
+    > FromIterator<&'a UnrolledList<T, P, N, G, A>> for UnrolledList<T, P, N, G, A>
+{
+    fn from_iter<I: IntoIterator<Item = &'a UnrolledList<T, P, N, G, A>>>(iter: I) -> Self {
         iter.into_iter().cloned().collect()
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> From<Vec<T>>
-    for UnrolledList<T, P, N, G>
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + Default> From<Vec<T>>
+    for UnrolledList<T, P, N, G, A>
 {
     fn from(vec: Vec<T>) -> Self {
-        from_vec(vec)
+        from_vec(vec, A::default())
     }
 }
 
-impl<T: Clone, P: PointerFamily, const N: u32, const G: u32> From<&[T]>
-    for UnrolledList<T, P, N, G>
+impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone + Default>
+    From<&[T]> for UnrolledList<T, P, N, G, A>
 {
     fn from(vec: &[T]) -> Self {
-        from_vec(vec.to_vec())
+        from_vec(vec.to_vec(), A::default())
     }
 }
 
-fn truncate<T: Clone>(value: &mut AtomicSharedVector<T>, index: usize) {
-    for _ in 0..value.len() - index {
-        value.pop();
+impl<T, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone> From<Vector<T, A>>
+    for UnrolledList<T, P, N, G, A>
+{
+    fn from(mut vector: Vector<T, A>) -> Self {
+        if vector.is_empty() {
+            return Self::new_in(vector.allocator().clone());
+        }
+
+        // A vector that fits in a single node becomes that node's storage as is.
+        if vector.len() <= N as usize {
+            vector.reverse();
+
+            return UnrolledList(NodePtr::new(UnrolledCell {
+                index: vector.len() as u32,
+                elements: vector.into_shared_atomic(),
+                next: None,
+                size: N,
+            }));
+        }
+
+        let allocator = vector.allocator().clone();
+        from_exact_size_iter(vector.into_iter(), allocator)
     }
 }
 
-fn split_off<T: Clone>(value: &mut AtomicSharedVector<T>, index: usize) -> AtomicSharedVector<T> {
-    let capacity = value.len() - index;
-    let mut new = Vector::with_capacity(index);
-    for i in index..value.len() {
-        let value = value.get_mut(i).unwrap();
-        new.push(unsafe { std::ptr::read(value) })
-    }
-    for _ in 0..capacity {
-        std::mem::forget(value.pop());
-    }
+impl<T: Clone, P: PointerFamily, const N: u32, const G: u32, A: Allocator + Clone>
+    From<AtomicSharedVector<T, A>> for UnrolledList<T, P, N, G, A>
+{
+    fn from(mut vector: AtomicSharedVector<T, A>) -> Self {
+        if vector.is_empty() {
+            return Self::new_in(vector.allocator().clone());
+        }
+
+        // Nodes store their elements back to front, so the buffer is only reused as is
+        // when it is unique, otherwise it is copied before being reversed.
+        if vector.len() <= N as usize {
+            vector.reverse();
+
+            return UnrolledList(NodePtr::new(UnrolledCell {
+                index: vector.len() as u32,
+                elements: vector,
+                next: None,
+                size: N,
+            }));
+        }
 
-    new.into_shared_atomic()
+        vector.into_unique().into()
+    }
 }
 
 #[cfg(test)]
@@ -1296,7 +2305,7 @@ mod iterator_tests {
     fn check_size() {
         println!(
             "{}",
-            std::mem::size_of::<UnrolledCell<usize, RcPointer, 256, 256>>()
+            core::mem::size_of::<UnrolledCell<usize, RcPointer, 256, 256>>()
         );
     }
 
@@ -1486,6 +2495,17 @@ mod iterator_tests {
         assert!(Iterator::eq(0..CAPACITY + 100usize, next.into_iter()))
     }
 
+    #[test]
+    fn take_shares_prefix_nodes() {
+        let list: RcList<usize> = (0..2 * CAPACITY).collect();
+        let next = list.take(CAPACITY + 100);
+
+        // The untouched first node shares its buffer, only the new tail is copied
+        assert!(list.0.elements.ptr_eq(&next.0.elements));
+        assert_eq!(next.0.next.as_ref().unwrap().0.elements.len(), 100);
+        assert!(Iterator::eq(0..2 * CAPACITY, list))
+    }
+
     #[test]
     fn tail() {
         let list: RcList<usize> = (0..2 * CAPACITY).into_iter().collect();
@@ -0,0 +1,353 @@
+//! A cursor for editing a list around a position.
+//!
+//! A [`Zipper`] splits a list in two at its cursor: the elements before it are kept in a
+//! reversed list, so the one right before the cursor comes first, and the focus and the
+//! elements after it are kept in another. Moving the cursor by one, or editing at it, only
+//! touches the front of one of the two, which is O(1) like consing. Nothing is copied out of
+//! the list it started from, which stays as it was.
+//!
+//! Moving further than one element shifts whole nodes at a time, rather than consing their
+//! elements over one by one. A node that only the zipper refers to is moved to the other side
+//! as it is, with its buffer reversed in place. A shared one is copied in reverse into a
+//! single new node.
+
+use crate::{
+    handler::{DefaultDropHandler, DropHandler},
+    list::GenericList,
+    shared::{PointerFamily, RcPointer},
+    shared_vector::alloc::{Allocator, Global},
+};
+
+/// A cursor into a list, which can move back and forth and edit the list at its position.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate im_lists;
+/// # use im_lists::list;
+/// # use im_lists::zipper::Zipper;
+/// let list = list![1, 2, 3, 5];
+/// let mut zipper = Zipper::new(list.clone());
+///
+/// zipper.seek(3);
+/// zipper.insert(4);
+/// assert_eq!(zipper.focus(), Some(&4));
+///
+/// zipper.move_left();
+/// assert_eq!(zipper.replace(30), Some(3));
+///
+/// assert_eq!(zipper.into_list(), list![1, 2, 30, 4, 5]);
+/// assert_eq!(list, list![1, 2, 3, 5]);
+/// ```
+pub struct Zipper<
+    T,
+    P: PointerFamily = RcPointer,
+    const N: u32 = 256,
+    const G: u32 = 1,
+    D: DropHandler<GenericList<T, P, N, G, D, A>> = DefaultDropHandler,
+    A: Allocator + Clone + 'static = Global,
+> {
+    // The elements before the cursor, the closest one first
+    prefix: GenericList<T, P, N, G, D, A>,
+    // The focus, followed by the rest of the elements
+    suffix: GenericList<T, P, N, G, D, A>,
+    position: usize,
+}
+
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > Zipper<T, P, N, G, D, A>
+{
+    /// Makes a zipper with its cursor on the first element of `list`. The zipper allocates
+    /// from the allocator of `list`.
+    ///
+    /// Time: O(1)
+    pub fn new(list: GenericList<T, P, N, G, D, A>) -> Self {
+        Zipper {
+            prefix: GenericList::new_in(list.allocator().clone()),
+            suffix: list,
+            position: 0,
+        }
+    }
+
+    /// Returns the index of the cursor. It is equal to [`len`](Zipper::len) when the cursor is
+    /// past the last element.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Get the number of elements in the list.
+    ///
+    /// Time: O(n / N)
+    pub fn len(&self) -> usize {
+        self.position + self.suffix.len()
+    }
+
+    /// Checks whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.prefix.is_empty() && self.suffix.is_empty()
+    }
+
+    /// Get a reference to the element at the cursor, or `None` if the cursor is past the last
+    /// element.
+    pub fn focus(&self) -> Option<&T> {
+        self.suffix.first()
+    }
+
+    /// Moves the cursor one element towards the front of the list. Returns `false`, and leaves
+    /// the cursor where it was, if it already is on the first element.
+    ///
+    /// Time: O(1)
+    pub fn move_left(&mut self) -> bool {
+        match self.prefix.pop_front() {
+            Some(value) => {
+                self.suffix.push_front(value);
+                self.position -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the cursor one element towards the back of the list. Returns `false`, and leaves
+    /// the cursor where it was, if it already is past the last element.
+    ///
+    /// Time: O(1)
+    pub fn move_right(&mut self) -> bool {
+        match self.suffix.pop_front() {
+            Some(value) => {
+                self.prefix.push_front(value);
+                self.position += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the element at the cursor with `value`, returning the old one. If the cursor
+    /// is past the last element, `value` is added there instead and `None` is returned.
+    ///
+    /// Time: O(1)
+    pub fn replace(&mut self, value: T) -> Option<T> {
+        let old = self.suffix.pop_front();
+        self.suffix.push_front(value);
+        old
+    }
+
+    /// Inserts `value` at the cursor, in front of the element that was there. The cursor is
+    /// then on `value`.
+    ///
+    /// Time: O(1)
+    pub fn insert(&mut self, value: T) {
+        self.suffix.push_front(value)
+    }
+
+    /// Removes the element at the cursor and returns it, leaving the cursor on the element
+    /// after it. Returns `None` if the cursor is past the last element.
+    ///
+    /// Time: O(1)
+    pub fn delete(&mut self) -> Option<T> {
+        self.suffix.pop_front()
+    }
+
+    /// Moves the cursor to `index`, which can be one past the last element.
+    ///
+    /// Time: O(d), where d is the distance to `index`. Whole nodes are moved at once, so
+    /// only about one node is moved an element at a time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the length of the list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use im_lists::list::List;
+    /// # use im_lists::zipper::Zipper;
+    /// let mut zipper = Zipper::new((0..10000).collect::<List<usize>>());
+    /// zipper.seek(9000);
+    /// assert_eq!(zipper.focus(), Some(&9000));
+    ///
+    /// zipper.seek(10);
+    /// assert_eq!(zipper.focus(), Some(&10));
+    /// ```
+    pub fn seek(&mut self, index: usize) {
+        while self.position > index {
+            let count = self.prefix.front_node_len();
+            if count <= self.position - index {
+                self.prefix.shift_front_node(&mut self.suffix);
+                self.position -= count;
+            } else {
+                self.move_left();
+            }
+        }
+
+        if index > self.position {
+            assert!(
+                index <= self.len(),
+                "index {index} is out of bounds for a list of length {}",
+                self.len()
+            );
+        }
+
+        while self.position < index {
+            let count = self.suffix.front_node_len();
+            if count <= index - self.position {
+                self.suffix.shift_front_node(&mut self.prefix);
+                self.position += count;
+            } else {
+                self.move_right();
+            }
+        }
+    }
+
+    /// Returns the edited list.
+    ///
+    /// Time: O(k), where k is the position of the cursor
+    pub fn into_list(mut self) -> GenericList<T, P, N, G, D, A> {
+        while !self.prefix.is_empty() {
+            self.prefix.shift_front_node(&mut self.suffix);
+        }
+        self.suffix
+    }
+}
+
+impl<
+        T: Clone,
+        P: PointerFamily,
+        const N: u32,
+        const G: u32,
+        D: DropHandler<GenericList<T, P, N, G, D, A>>,
+        A: Allocator + Clone,
+    > From<GenericList<T, P, N, G, D, A>> for Zipper<T, P, N, G, D, A>
+{
+    fn from(list: GenericList<T, P, N, G, D, A>) -> Self {
+        Zipper::new(list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::{List, SharedVList, VList};
+    use crate::test_alloc::CountingAllocator;
+
+    #[test]
+    fn edits_match_a_vec() {
+        let list: List<usize> = (0..1000).collect();
+        let mut expected: Vec<usize> = (0..1000).collect();
+        let mut zipper = Zipper::new(list.clone());
+
+        // A simple linear congruential generator, so the edits are spread over the list
+        let mut state = 7usize;
+        for step in 0..2000 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let index = (state >> 33) % (expected.len() + 1);
+            zipper.seek(index);
+            assert_eq!(zipper.position(), index);
+            assert_eq!(zipper.focus(), expected.get(index));
+
+            match step % 4 {
+                0 => {
+                    zipper.insert(step);
+                    expected.insert(index, step);
+                }
+                1 => assert_eq!(
+                    zipper.delete(),
+                    (index < expected.len()).then(|| expected.remove(index))
+                ),
+                2 if index < expected.len() => {
+                    assert_eq!(zipper.replace(step), Some(expected[index]));
+                    expected[index] = step;
+                }
+                _ => {
+                    assert_eq!(zipper.move_left(), index > 0);
+                    assert_eq!(zipper.move_right(), index > 0 || !expected.is_empty());
+                }
+            }
+            assert_eq!(zipper.len(), expected.len());
+        }
+
+        assert!(zipper.into_list().iter().eq(expected.iter()));
+        assert!(list.iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn seek_moves_whole_nodes() {
+        let list: VList<usize> = (0..5000).collect();
+        let mut zipper = Zipper::new(list.clone());
+
+        zipper.seek(4999);
+        assert_eq!(zipper.focus(), Some(&4999));
+        assert!(zipper.prefix.iter().copied().eq((0..4999).rev()));
+        assert!(zipper.prefix.node_count() <= list.node_count() + 1);
+
+        zipper.seek(1);
+        assert_eq!(zipper.focus(), Some(&1));
+        assert!(zipper.suffix.iter().copied().eq(1..5000));
+
+        zipper.seek(5000);
+        assert_eq!(zipper.focus(), None);
+        assert!(!zipper.move_right());
+        assert!(zipper.into_list().iter().copied().eq(0..5000));
+    }
+
+    #[test]
+    fn seek_moves_unique_nodes_without_copying() {
+        type CountingList =
+            GenericList<usize, RcPointer, 4, 1, DefaultDropHandler, CountingAllocator>;
+
+        let allocator = CountingAllocator::default();
+        let mut list = CountingList::new_in(allocator.clone());
+        list.extend(0..100);
+        let mut zipper = Zipper::new(list);
+        let live = allocator.live();
+
+        // Nothing else refers to the nodes, so they are moved over as they are
+        zipper.seek(100);
+        assert!(zipper.prefix.iter().copied().eq((0..100).rev()));
+        zipper.seek(0);
+        assert_eq!(allocator.live(), live);
+        assert_eq!(zipper.focus(), Some(&0));
+
+        // Shared nodes are copied, and the list they are shared with left as it was
+        let list = zipper.into_list();
+        let mut zipper = Zipper::new(list.clone());
+        zipper.seek(100);
+        assert!(allocator.live() > live);
+        assert!(zipper.into_list().iter().copied().eq(0..100));
+        assert!(list.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn edges() {
+        let mut zipper = Zipper::new(SharedVList::<usize>::new());
+        assert!(zipper.is_empty());
+        assert!(!zipper.move_left());
+        assert!(!zipper.move_right());
+        assert_eq!(zipper.delete(), None);
+        assert_eq!(zipper.replace(1), None);
+        assert_eq!(zipper.focus(), Some(&1));
+        assert!(zipper.move_right());
+        zipper.insert(2);
+        zipper.seek(0);
+        assert_eq!(
+            zipper.into_list().iter().copied().collect::<Vec<_>>(),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn seek_past_the_end() {
+        let mut zipper = Zipper::new((0..10).collect::<List<usize>>());
+        zipper.seek(11);
+    }
+}